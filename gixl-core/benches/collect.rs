@@ -0,0 +1,54 @@
+//! Time listing the history of a repository, the one of `GIXL_BENCH_REPO` or of the current
+//! directory, with `cargo bench -p gixl-core`.
+//!
+//! Commits are decoded differently whether the repository has a commit-graph or not: compare
+//! after `git commit-graph write --reachable`, and after removing
+//! `.git/objects/info/commit-graph`.
+
+use std::time::{Duration, Instant};
+
+use color_eyre::Result;
+use gixl_core::{
+    log::{self, Filter},
+    source::{Root, Source},
+};
+
+/// Times a walk is repeated, the fastest being printed.
+const RUNS: usize = 5;
+
+fn main() -> Result<()> {
+    let dir = std::env::var_os("GIXL_BENCH_REPO").unwrap_or_else(|| ".".into());
+    let repo = gix::discover(&dir)?;
+    let graph = match repo.commit_graph_if_enabled()? {
+        Some(_) => "with a commit-graph",
+        None => "without a commit-graph",
+    };
+    let root = Root {
+        name: String::new(),
+        repo,
+        dir: dir.into(),
+    };
+    for defer in [false, true] {
+        let mut fastest = Duration::MAX;
+        let mut commits = 0;
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let source = Source::Root(&root);
+            let filter = Filter::default();
+            let (entries, _) = log::collect(
+                &root.repo,
+                &source,
+                "HEAD",
+                &filter,
+                defer,
+                None,
+                &mut |_, _| true,
+            )?;
+            fastest = fastest.min(start.elapsed());
+            commits = entries.len();
+        }
+        let what = if defer { "deferred" } else { "decoded" };
+        println!("{commits} commits {what} {graph} in {fastest:?}");
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+//! The caches of the objects of a repository, read again and again while walking its history
+//! and diffing its trees: decoded objects, and the bases of the deltas packed objects are made
+//! of.
+//!
+//! Both are sized by the configuration of the repository, as gix reads it:
+//! `gitoxide.objects.cacheLimit`, or `GIX_OBJECT_CACHE_MEMORY` as set by `--object-cache`, for
+//! objects, and `core.deltaBaseCacheLimit`, or `GIX_PACK_CACHE_MEMORY`, for delta bases, the
+//! last 64 of them being kept otherwise, in at most `gitoxide.core.deltaBaseCacheLimit` bytes.
+//! Without any limit configured, the object cache is sized after the files of the repository,
+//! see [`object_cache_size`].
+
+/// Bounds of the object cache sized by [`object_cache_size`].
+const MIN_OBJECT_CACHE: usize = 4 * 1024 * 1024;
+const MAX_OBJECT_CACHE: usize = 256 * 1024 * 1024;
+
+/// Bytes of decoded objects worth keeping to diff the trees of `repo`, as many as its index
+/// suggests: about 10 MiB for every 10k files, from 4 MiB to 256 MiB.
+pub fn object_cache_size(repo: &gix::Repository) -> usize {
+    let bytes = match repo.index_or_empty() {
+        Ok(index) => repo.compute_object_cache_size_for_tree_diffs(&index),
+        Err(_) => 0,
+    };
+    bytes.clamp(MIN_OBJECT_CACHE, MAX_OBJECT_CACHE)
+}
+
+/// Size the object cache of `repo` with [`object_cache_size`] unless configured, gix having
+/// sized the others when opening it.
+pub fn apply(repo: &mut gix::Repository) {
+    let configured = repo
+        .config_snapshot()
+        .integer("gitoxide.objects.cacheLimit")
+        .is_some();
+    if !configured {
+        let bytes = object_cache_size(repo);
+        repo.object_cache_size(bytes);
+    }
+}
@@ -0,0 +1,282 @@
+use color_eyre::Result;
+use gix::{
+    ObjectId,
+    bstr::{BString, ByteSlice},
+    diff::blob::{
+        Algorithm, UnifiedDiff,
+        intern::InternedInput,
+        unified_diff::{ContextSize, NewlineSeparator},
+    },
+    object::tree::EntryMode,
+    object::tree::diff::ChangeDetached,
+};
+
+/// A file changed by a commit, relative to its first parent.
+#[derive(Clone, Debug)]
+pub struct FileDiff {
+    pub path: BString,
+    /// Source path of a rename or copy.
+    pub previous_path: Option<BString>,
+    pub previous_mode: Option<EntryMode>,
+    pub mode: Option<EntryMode>,
+    pub previous_id: ObjectId,
+    pub id: ObjectId,
+    /// Hunks in unified format, `None` for binary blobs, trees and submodules.
+    pub patch: Option<BString>,
+    /// A blob of the change isn't in the repository, left out of a partial clone: there is no
+    /// patch, rather than fetching it.
+    pub missing: bool,
+}
+
+impl FileDiff {
+    /// The change as `git diff` shows it, `None` when there is no patch.
+    pub fn to_patch(&self) -> Option<BString> {
+        let patch = self.patch.as_ref()?;
+        let old = self.previous_path.as_ref().unwrap_or(&self.path);
+        let mut text = BString::from(format!("diff --git a/{old} b/{}\n", self.path));
+        match self.previous_mode {
+            Some(_) => text.extend_from_slice(format!("--- a/{old}\n").as_bytes()),
+            None => text.extend_from_slice(b"--- /dev/null\n"),
+        }
+        match self.mode {
+            Some(_) => text.extend_from_slice(format!("+++ b/{}\n", self.path).as_bytes()),
+            None => text.extend_from_slice(b"+++ /dev/null\n"),
+        }
+        text.extend_from_slice(patch);
+        Some(text)
+    }
+}
+
+fn tree_changes(repo: &gix::Repository, id: ObjectId) -> Result<Vec<ChangeDetached>> {
+    let commit = repo.find_commit(id)?;
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent_ids().next() {
+        Some(parent) => parent.object()?.into_commit().tree()?,
+        None => repo.empty_tree(),
+    };
+    Ok(repo.diff_tree_to_tree(&parent_tree, &tree, None)?)
+}
+
+/// Whether commit `id` adds or removes blob or tree `object`, like `git log --find-object`.
+pub fn touches_object(repo: &gix::Repository, id: ObjectId, object: ObjectId) -> Result<bool> {
+    Ok(tree_changes(repo, id)?.iter().any(|change| match change {
+        ChangeDetached::Addition { id, .. } | ChangeDetached::Deletion { id, .. } => *id == object,
+        ChangeDetached::Modification {
+            previous_id, id, ..
+        }
+        | ChangeDetached::Rewrite {
+            source_id: previous_id,
+            id,
+            ..
+        } => (*id == object) != (*previous_id == object),
+    }))
+}
+
+/// Files changed by commit `id` against its first parent.
+pub fn commit_diff(repo: &gix::Repository, id: ObjectId) -> Result<Vec<FileDiff>> {
+    files(repo, tree_changes(repo, id)?)
+}
+
+/// Files changed from commit `from` to commit `to`, like the commits in between squashed into
+/// one.
+pub fn range_diff(repo: &gix::Repository, from: ObjectId, to: ObjectId) -> Result<Vec<FileDiff>> {
+    let (from, to) = (
+        repo.find_commit(from)?.tree()?,
+        repo.find_commit(to)?.tree()?,
+    );
+    files(repo, repo.diff_tree_to_tree(&from, &to, None)?)
+}
+
+fn files(repo: &gix::Repository, changes: Vec<ChangeDetached>) -> Result<Vec<FileDiff>> {
+    let mut files = Vec::new();
+    for change in changes {
+        let null = ObjectId::null(repo.object_hash());
+        let file = match change {
+            ChangeDetached::Addition {
+                location,
+                entry_mode,
+                id,
+                ..
+            } => FileDiff {
+                path: location,
+                previous_path: None,
+                previous_mode: None,
+                mode: Some(entry_mode),
+                previous_id: null,
+                id,
+                patch: None,
+                missing: false,
+            },
+            ChangeDetached::Deletion {
+                location,
+                entry_mode,
+                id,
+                ..
+            } => FileDiff {
+                path: location,
+                previous_path: None,
+                previous_mode: Some(entry_mode),
+                mode: None,
+                previous_id: id,
+                id: null,
+                patch: None,
+                missing: false,
+            },
+            ChangeDetached::Modification {
+                location,
+                previous_entry_mode,
+                previous_id,
+                entry_mode,
+                id,
+            } => FileDiff {
+                path: location,
+                previous_path: None,
+                previous_mode: Some(previous_entry_mode),
+                mode: Some(entry_mode),
+                previous_id,
+                id,
+                patch: None,
+                missing: false,
+            },
+            ChangeDetached::Rewrite {
+                source_location,
+                source_entry_mode,
+                source_id,
+                entry_mode,
+                id,
+                location,
+                ..
+            } => FileDiff {
+                path: location,
+                previous_path: Some(source_location),
+                previous_mode: Some(source_entry_mode),
+                mode: Some(entry_mode),
+                previous_id: source_id,
+                id,
+                patch: None,
+                missing: false,
+            },
+        };
+        if file
+            .mode
+            .or(file.previous_mode)
+            .is_some_and(|m| m.is_tree())
+        {
+            continue;
+        }
+        files.push(file);
+    }
+
+    for file in &mut files {
+        let is_blob = |mode: Option<EntryMode>| mode.is_none_or(|m| m.is_blob());
+        if !is_blob(file.mode) || !is_blob(file.previous_mode) {
+            continue;
+        }
+        let local = |id: ObjectId| id.is_null() || repo.has_object(id);
+        if local(file.previous_id) && local(file.id) {
+            file.patch = unified(repo, file.previous_id, file.id)?;
+        } else {
+            file.missing = true;
+        }
+    }
+    Ok(files)
+}
+
+fn blob_data(repo: &gix::Repository, id: ObjectId) -> Result<Vec<u8>> {
+    if id.is_null() {
+        return Ok(Vec::new());
+    }
+    Ok(repo.find_blob(id)?.take_data())
+}
+
+/// Unified diff hunks between two blobs (null ids stand for empty content), `None` when binary.
+pub fn unified(repo: &gix::Repository, old: ObjectId, new: ObjectId) -> Result<Option<BString>> {
+    unified_data(repo, &blob_data(repo, old)?, &blob_data(repo, new)?)
+}
+
+/// Unified diff hunks between two contents, `None` when binary.
+pub fn unified_data(repo: &gix::Repository, old: &[u8], new: &[u8]) -> Result<Option<BString>> {
+    let is_binary = |data: &[u8]| data[..data.len().min(8000)].contains(&0);
+    if is_binary(old) || is_binary(new) {
+        return Ok(None);
+    }
+    let input = InternedInput::new(old, new);
+    let patch = gix::diff::blob::diff(
+        repo.diff_algorithm().unwrap_or(Algorithm::Histogram),
+        &input,
+        UnifiedDiff::new(
+            &input,
+            Vec::new(),
+            NewlineSeparator::AfterHeaderAndWhenNeeded("\n"),
+            ContextSize::symmetrical(3),
+        ),
+    )?;
+    Ok(Some(patch.into()))
+}
+
+/// A stable identifier of the change introduced by commit `id`, ignoring whitespace and line
+/// numbers, in the spirit of `git patch-id`. Merges have none, nor commits changing blobs a
+/// partial clone left out.
+pub fn patch_id(repo: &gix::Repository, id: ObjectId) -> Result<Option<ObjectId>> {
+    if repo.find_commit(id)?.parent_ids().count() > 1 {
+        return Ok(None);
+    }
+    let mut hasher = gix::hash::hasher(repo.object_hash());
+    for file in commit_diff(repo, id)? {
+        if file.missing {
+            return Ok(None);
+        }
+        hasher.update(file.previous_path.as_ref().unwrap_or(&file.path));
+        hasher.update(&file.path);
+        match &file.patch {
+            Some(patch) => {
+                for line in patch.lines() {
+                    if line.starts_with(b"+") || line.starts_with(b"-") {
+                        let line: Vec<u8> = line
+                            .iter()
+                            .copied()
+                            .filter(|c| !c.is_ascii_whitespace())
+                            .collect();
+                        hasher.update(&line);
+                    }
+                }
+            }
+            None => {
+                hasher.update(file.previous_id.as_bytes());
+                hasher.update(file.id.as_bytes());
+            }
+        }
+    }
+    Ok(Some(hasher.try_finalize()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    const ALICE: (&str, &str) = ("Alice", "alice@example.com");
+    const BOB: (&str, &str) = ("Bob", "bob@example.com");
+
+    #[test]
+    fn patch_id_of_a_cherry_pick() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init(dir.path())?;
+        let base = fixture.commit(ALICE, "base", &[("a", "1\n2\n3\n"), ("b", "x\n")])?;
+        let fix = fixture.commit(ALICE, "fix", &[("a", "1\ntwo\n3\n")])?;
+        let other = fixture.commit(ALICE, "other", &[("a", "1\nzwei\n3\n")])?;
+        fixture.reset(base);
+        fixture.commit(BOB, "change b", &[("b", "y\n")])?;
+        // the same change, picked elsewhere with other whitespace
+        let picked = fixture.commit(BOB, "fix (backport)", &[("a", "1\n t w o\n3\n")])?;
+        let merge = fixture.merge(BOB, "merge", other)?;
+        let repo = fixture.root("").repo;
+
+        let fix = patch_id(&repo, fix)?;
+        assert!(fix.is_some());
+        assert_eq!(patch_id(&repo, picked)?, fix);
+        assert_ne!(patch_id(&repo, other)?, fix);
+        assert_eq!(patch_id(&repo, merge)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use color_eyre::{Result, eyre::eyre};
+use gix::{ObjectId, date::Time, object::tree::EntryKind, refs::transaction::PreviousValue};
+
+use crate::source::Root;
+
+/// Author time of the first commit of a fixture, 2024-01-01 00:00 UTC.
+const EPOCH: i64 = 1_704_067_200;
+
+/// A repository built commit by commit, for tests, its objects kept in memory.
+///
+/// Only references are written, to the directory it is created in, unless created with
+/// [`Fixture::init_on_disk`]; commits follow each other by an hour from 2024-01-01, so that the
+/// log always looks the same. Git commands can't see objects kept in memory.
+pub struct Fixture {
+    repo: gix::Repository,
+    dir: std::path::PathBuf,
+    head: Option<ObjectId>,
+    commits: i64,
+}
+
+impl Fixture {
+    /// An empty bare repository in `dir`.
+    pub fn init(dir: impl AsRef<Path>) -> Result<Fixture> {
+        let dir = dir.as_ref().to_path_buf();
+        let repo = gix::init_bare(&dir)?.with_object_memory();
+        Ok(Fixture {
+            repo,
+            dir,
+            head: None,
+            commits: 0,
+        })
+    }
+
+    /// An empty bare repository in `dir`, its objects written there too, for git commands.
+    pub fn init_on_disk(dir: impl AsRef<Path>) -> Result<Fixture> {
+        let dir = dir.as_ref().to_path_buf();
+        Ok(Fixture {
+            repo: gix::init_bare(&dir)?,
+            dir,
+            head: None,
+            commits: 0,
+        })
+    }
+
+    fn write(
+        &mut self,
+        author: (&str, &str),
+        message: &str,
+        parents: &[ObjectId],
+        tree: ObjectId,
+    ) -> Result<ObjectId> {
+        let signature = gix::actor::Signature {
+            name: author.0.into(),
+            email: author.1.into(),
+            time: Time::new(EPOCH + self.commits * 3_600, 0),
+        };
+        let commit = gix::objs::Commit {
+            tree,
+            parents: parents.iter().copied().collect(),
+            author: signature.clone(),
+            committer: signature,
+            encoding: None,
+            message: message.into(),
+            extra_headers: Vec::new(),
+        };
+        let id = self.repo.write_object(&commit)?.detach();
+        let branch = self
+            .repo
+            .head_name()?
+            .ok_or_else(|| eyre!("HEAD is detached"))?;
+        self.repo
+            .reference(branch, id, PreviousValue::Any, message)?;
+        self.head = Some(id);
+        self.commits += 1;
+        Ok(id)
+    }
+
+    fn tree(&self) -> Result<ObjectId> {
+        Ok(match self.head {
+            Some(head) => self.repo.find_commit(head)?.tree_id()?.detach(),
+            None => ObjectId::empty_tree(self.repo.object_hash()),
+        })
+    }
+
+    /// Commit `files`, as `(path, content)`, on top of HEAD, by `author` as `(name, email)`.
+    pub fn commit(
+        &mut self,
+        author: (&str, &str),
+        message: &str,
+        files: &[(&str, &str)],
+    ) -> Result<ObjectId> {
+        let mut editor = self.repo.edit_tree(self.tree()?)?;
+        for (path, content) in files {
+            let blob = self.repo.write_blob(content)?.detach();
+            editor.upsert(*path, EntryKind::Blob, blob)?;
+        }
+        let tree = editor.write()?.detach();
+        let parents: Vec<_> = self.head.into_iter().collect();
+        self.write(author, message, &parents, tree)
+    }
+
+    /// Merge commit `other` into HEAD, keeping the files of HEAD.
+    pub fn merge(
+        &mut self,
+        author: (&str, &str),
+        message: &str,
+        other: ObjectId,
+    ) -> Result<ObjectId> {
+        let tree = self.tree()?;
+        let parents: Vec<_> = self.head.into_iter().chain([other]).collect();
+        self.write(author, message, &parents, tree)
+    }
+
+    /// Commit on top of HEAD and of `missing`, a commit which isn't in the repository, like one
+    /// lost with a corrupt pack.
+    pub fn merge_missing(
+        &mut self,
+        author: (&str, &str),
+        message: &str,
+        missing: ObjectId,
+    ) -> Result<ObjectId> {
+        let tree = self.tree()?;
+        let parents: Vec<_> = self.head.into_iter().chain([missing]).collect();
+        self.write(author, message, &parents, tree)
+    }
+
+    /// Make the next commit a child of `id`, to start a side branch.
+    pub fn reset(&mut self, id: ObjectId) {
+        self.head = Some(id);
+    }
+
+    /// Replace commit `original` with `replacement`, like `git replace`.
+    pub fn replace(&self, original: ObjectId, replacement: ObjectId) -> Result<()> {
+        self.repo.reference(
+            format!("refs/replace/{original}"),
+            replacement,
+            PreviousValue::MustNotExist,
+            "replace",
+        )?;
+        Ok(())
+    }
+
+    /// The repository, to be listed under `name`.
+    pub fn root(self, name: &str) -> Root {
+        Root {
+            name: name.to_owned(),
+            repo: self.repo,
+            dir: self.dir,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::LazyLock;
+
+use color_eyre::Result;
+use gix::{ObjectId, bstr::ByteSlice};
+use regex::bytes::Regex;
+
+/// Hunk-header style patterns recognizing the start of a definition, per file extension.
+/// Unknown languages fall back to git's default: a line starting with a letter, `_` or `$`.
+fn header_pattern(path: &str) -> &'static Regex {
+    static RUST: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(concat!(
+            r#"^\s*(pub(\([^)]*\))?\s+)?((async|const|unsafe|extern(\s+"[^"]*")?)\s+)*"#,
+            r"((fn|struct|enum|union|trait|impl|mod|type|const|static)\b|macro_rules!)",
+        ))
+        .unwrap()
+    });
+    static PYTHON: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\s*(async\s+)?(def|class)\s").unwrap());
+    static DEFAULT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z_$]").unwrap());
+
+    match path.rsplit_once('.').map(|(_, ext)| ext) {
+        Some("rs") => &RUST,
+        Some("py") => &PYTHON,
+        _ => &DEFAULT,
+    }
+}
+
+fn indent(line: &[u8]) -> usize {
+    line.iter().take_while(|c| c.is_ascii_whitespace()).count()
+}
+
+/// Text of the definition of `name` in `data`: from its header line up to the next header
+/// at the same or a lower indentation, without trailing blank lines.
+pub fn extract<'a>(path: &str, name: &str, data: &'a [u8]) -> Option<Vec<&'a [u8]>> {
+    let header = header_pattern(path);
+    let name = Regex::new(&format!(r"\b{}\b", regex::escape(name))).ok()?;
+    let lines: Vec<&[u8]> = data.lines_with_terminator().collect();
+
+    let start = lines
+        .iter()
+        .position(|line| header.is_match(line) && name.is_match(line))?;
+    let level = indent(lines[start]);
+    let mut end = lines[start + 1..]
+        .iter()
+        .position(|line| !line.trim().is_empty() && header.is_match(line) && indent(line) <= level)
+        .map_or(lines.len(), |i| start + 1 + i);
+    while end > start + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    Some(lines[start..end].to_vec())
+}
+
+fn blob(repo: &gix::Repository, commit: ObjectId, path: &str) -> Result<Option<ObjectId>> {
+    let tree = repo.find_commit(commit)?.tree()?;
+    Ok(tree
+        .lookup_entry_by_path(path)?
+        .map(|entry| entry.object_id()))
+}
+
+fn definition(
+    repo: &gix::Repository,
+    blob: ObjectId,
+    path: &str,
+    name: &str,
+) -> Result<Option<Vec<u8>>> {
+    let data = repo.find_blob(blob)?.take_data();
+    Ok(extract(path, name, &data).map(|lines| lines.concat()))
+}
+
+/// Whether commit `id` changes the definition of `name` in `path` compared to its first parent,
+/// like `git log -L :name:path`.
+pub fn changes_function(
+    repo: &gix::Repository,
+    id: ObjectId,
+    parents: &[ObjectId],
+    path: &str,
+    name: &str,
+) -> Result<bool> {
+    let new = blob(repo, id, path)?;
+    let old = match parents.first() {
+        Some(parent) => blob(repo, *parent, path)?,
+        None => None,
+    };
+    // blobs are only read when the file changed, a partial clone missing most of the others
+    if old == new {
+        return Ok(false);
+    }
+    let definition = |blob: Option<ObjectId>| match blob {
+        Some(blob) => definition(repo, blob, path, name),
+        None => Ok(None),
+    };
+    Ok(match (definition(old)?, definition(new)?) {
+        (None, None) => false,
+        (Some(old), Some(new)) => old != new,
+        _ => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_headers_start_lines() {
+        let header = header_pattern("lib.rs");
+        for line in [
+            "fn parse() {",
+            "    pub(crate) async fn fetch() {",
+            "pub extern \"C\" fn callback() {",
+            "pub type Result<T> = std::result::Result<T, Error>;",
+            "pub const LIMIT: usize = 10;",
+            "static mut COUNT: usize = 0;",
+            "macro_rules! try_read {",
+        ] {
+            assert!(header.is_match(line.as_bytes()), "{line}");
+        }
+        for line in [
+            "    let f = |x| x; // fn",
+            "    parse(fn_name);",
+            "// a const fn",
+        ] {
+            assert!(!header.is_match(line.as_bytes()), "{line}");
+        }
+    }
+
+    #[test]
+    fn extract_up_to_the_next_item() {
+        let data = b"const A: u8 = 1;\n\nfn parse() {\n    lex();\n}\n\nstatic B: u8 = 2;\n";
+        let lines = extract("lib.rs", "parse", data).unwrap();
+        assert_eq!(lines.concat(), b"fn parse() {\n    lex();\n}\n");
+    }
+}
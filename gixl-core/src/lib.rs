@@ -0,0 +1,289 @@
+//! The log model of gixl: the histories of several git repositories and of their submodules,
+//! merged into one list of commits sorted by author or committer time.
+//!
+//! Repositories are opened as [`source::Root`]s, their submodules found with [`submodules`], and
+//! the commits of all of them gathered with [`collect`], each tagged with the [`source::Source`]
+//! it comes from. A repository which can't be read is skipped, the rest of the log being
+//! listed, and the reason returned as a [`Problem`].
+
+pub mod cache;
+pub mod diff;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fixture;
+pub mod funcname;
+pub mod log;
+pub mod partial;
+pub mod replace;
+pub mod source;
+pub mod stage;
+pub mod submodule;
+pub mod text;
+
+use std::cell::Cell;
+
+use color_eyre::{Result, eyre::eyre};
+
+use crate::{
+    log::{Filter, LogEntryInfo, Progress},
+    partial::PartialClone,
+    source::{Root, Source},
+};
+
+/// A commit of the merged log, with the repository it comes from.
+pub type Item<'repo> = (LogEntryInfo, Source<'repo>);
+
+/// Why commits of a repository are missing from the log.
+#[derive(Debug)]
+pub struct Problem {
+    /// Label of the repository, as [`Source::label`], or its path when unnamed.
+    pub source: String,
+    pub error: color_eyre::Report,
+}
+
+impl Problem {
+    fn new(source: &Source, error: color_eyre::Report) -> Problem {
+        let source = match source {
+            Source::Root(root) if root.name.is_empty() => {
+                let path = root.repo.workdir().unwrap_or(root.repo.git_dir());
+                let path = std::path::absolute(path).unwrap_or_else(|_| path.to_owned());
+                path.display().to_string()
+            }
+            source => source.label(),
+        };
+        Problem { source, error }
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.source, self.error)
+    }
+}
+
+/// The submodules of each of `roots`, to be listed as sources with [`sources`]. Those of a root
+/// whose `.gitmodules` can't be read are left out, with a problem.
+pub fn submodules<'repo>(
+    roots: &'repo [Root],
+    problems: &mut Vec<Problem>,
+) -> Vec<(&'repo Root, Vec<gix::Submodule<'repo>>)> {
+    let mut submodules = Vec::new();
+    for root in roots {
+        match root.repo.submodules() {
+            Ok(Some(sub)) => submodules.push((root, sub.collect())),
+            Ok(None) => {}
+            Err(err) => {
+                let error = match PartialClone::detect(&root.repo) {
+                    Some(partial) => {
+                        eyre!("submodules not looked for: {err}, not fetched in this {partial}")
+                    }
+                    None => err.into(),
+                };
+                problems.push(Problem::new(&Source::Root(root), error));
+            }
+        }
+    }
+    submodules
+}
+
+/// The submodules found by [`submodules`], as sources.
+pub fn sources<'repo>(
+    submodules: &'repo [(&'repo Root, Vec<gix::Submodule<'repo>>)],
+) -> Vec<Source<'repo>> {
+    submodules
+        .iter()
+        .flat_map(|(root, subs)| subs.iter().map(|sub| Source::Submodule(root, sub)))
+        .collect()
+}
+
+/// The commits reachable from HEAD, or [`Filter::contains`], in `roots` and in the checked out
+/// `submodules` matching `filter`, in the order they were walked: see [`log::sort`].
+///
+/// Functions of [`Filter::function`] are only looked for in the roots, the path being relative
+/// to them. Commits which can't be read are listed as placeholders, see [`log::collect`]; a
+/// repository which can't be walked at all is left out, with a problem, as are the commits of a
+/// partial clone which couldn't be checked against `filter`. With `defer`, their authors and
+/// messages are left to be read when needed, see [`log::LogEntryInfo::deferred`].
+///
+/// The walks are followed with `progress`, see [`log::collect`], stopping it stopping them all:
+/// the repositories are walked first, their submodules after. With `limit`, each repository is
+/// only walked until that many of its commits matched, the newest by commit time.
+pub fn collect<'repo>(
+    roots: &'repo [Root],
+    submodules: &[Source<'repo>],
+    filter: &Filter,
+    defer: bool,
+    limit: Option<usize>,
+    progress: &mut Progress,
+    problems: &mut Vec<Problem>,
+) -> Vec<Item<'repo>> {
+    let mut entries = Vec::new();
+    let stopped = Cell::new(false);
+    let mut progress = |source: &Source, commits: usize| {
+        stopped.set(!progress(source, commits));
+        !stopped.get()
+    };
+    let mut walk = |repo: &gix::Repository, source: Source<'repo>| -> Result<()> {
+        let (walked, unchecked) =
+            log::collect(repo, &source, "HEAD", filter, defer, limit, &mut progress)?;
+        for entry in walked {
+            entries.push((entry, source));
+        }
+        match unchecked {
+            Some(unchecked) => Err(eyre!(unchecked)),
+            None => Ok(()),
+        }
+    };
+    for root in roots {
+        if stopped.get() {
+            break;
+        }
+        if let Err(err) = walk(&root.repo, Source::Root(root)) {
+            problems.push(Problem::new(&Source::Root(root), err));
+        }
+    }
+    if filter.function.is_none() {
+        for source in submodules {
+            let Source::Submodule(_, submodule) = source else {
+                continue;
+            };
+            if stopped.get() {
+                break;
+            }
+            let walked = match source::open_submodule(submodule) {
+                Ok(Some(repo)) => walk(&repo, *source),
+                // not checked out
+                Ok(None) => Ok(()),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = walked {
+                problems.push(Problem::new(source, err));
+            }
+        }
+    }
+    entries
+}
+
+/// The revisions a line of the output of another tool names: its first word, like the ids of
+/// `git rev-list` or `git log --oneline`, or those marked by the commands of a `git bisect log`,
+/// whose comments name none.
+pub fn revisions(line: &str) -> Vec<String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [] => Vec::new(),
+        [comment, ..] if comment.starts_with('#') => Vec::new(),
+        [
+            "git",
+            "bisect",
+            "good" | "bad" | "old" | "new" | "skip",
+            marked @ ..,
+        ] => marked.iter().map(|rev| rev.to_string()).collect(),
+        ["git", "bisect", ..] => Vec::new(),
+        [rev, ..] => vec![rev.to_string()],
+    }
+}
+
+/// The commits of `revs` alone, without their ancestors, like `git log --no-walk`, matching
+/// `filter`, see [`Resolver::list`].
+pub fn listed<'repo>(
+    roots: &'repo [Root],
+    submodules: &[Source<'repo>],
+    revs: &[String],
+    filter: &Filter,
+) -> Result<Vec<Item<'repo>>> {
+    Resolver::new(roots, submodules).list(revs, filter)
+}
+
+/// Looks revisions up in `roots`, then in the checked out submodules, keeping those opened for
+/// the next revisions, like the ones read line by line from standard input.
+pub struct Resolver<'a, 'repo> {
+    roots: &'repo [Root],
+    /// Submodules opened so far, with the one they were opened from.
+    opened: Vec<(Source<'repo>, gix::Repository)>,
+    /// Submodules not opened yet.
+    unopened: std::slice::Iter<'a, Source<'repo>>,
+}
+
+impl<'a, 'repo> Resolver<'a, 'repo> {
+    pub fn new(roots: &'repo [Root], submodules: &'a [Source<'repo>]) -> Self {
+        Resolver {
+            roots,
+            opened: Vec::new(),
+            unopened: submodules.iter(),
+        }
+    }
+
+    /// The commits of `revs` alone, without their ancestors, like `git log --no-walk`, matching
+    /// `filter`: each revision is looked up in the roots, then in the checked out submodules, its
+    /// commit coming from the first it names one in. Revisions named twice are listed once; one
+    /// found nowhere is an error.
+    pub fn list(&mut self, revs: &[String], filter: &Filter) -> Result<Vec<Item<'repo>>> {
+        let roots = self.roots;
+        // the commits of each repository, roots first, in the order given
+        let mut commits: Vec<Vec<gix::ObjectId>> = Vec::new();
+        for rev in revs {
+            let commit = |repo: &gix::Repository| {
+                let object = repo.rev_parse_single(rev.as_str()).ok()?.object().ok()?;
+                Some(object.peel_to_commit().ok()?.id)
+            };
+            let mut known = (roots.iter().map(|root| &root.repo))
+                .chain(self.opened.iter().map(|(_, repo)| repo))
+                .enumerate();
+            let (i, id) = match known.find_map(|(i, repo)| Some((i, commit(repo)?))) {
+                Some(found) => found,
+                // submodules are only opened once a revision isn't in the repositories opened so far
+                None => loop {
+                    let Some(source) = self.unopened.next() else {
+                        return Err(eyre!("{rev}: no such commit in any repository"));
+                    };
+                    let Some(repo) = (source.submodule())
+                        .and_then(|submodule| source::open_submodule(submodule).ok().flatten())
+                    else {
+                        continue;
+                    };
+                    let id = commit(&repo);
+                    self.opened.push((*source, repo));
+                    if let Some(id) = id {
+                        break (roots.len() + self.opened.len() - 1, id);
+                    }
+                },
+            };
+            if commits.len() <= i {
+                commits.resize(i + 1, Vec::new());
+            }
+            if !commits[i].contains(&id) {
+                commits[i].push(id);
+            }
+        }
+        let repos = (roots.iter().map(|root| (Source::Root(root), &root.repo)))
+            .chain(self.opened.iter().map(|(source, repo)| (*source, repo)));
+        let mut entries = Vec::new();
+        for ((source, repo), ids) in repos.zip(commits) {
+            for entry in log::read_commits(repo, &ids)? {
+                if filter.matches(repo, &entry)? {
+                    entries.push((entry, source));
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revisions_of_lines() {
+        assert_eq!(revisions("a1b2c3d fix the frobnicator"), ["a1b2c3d"]);
+        assert_eq!(revisions("  v1.0^{}  "), ["v1.0^{}"]);
+        assert_eq!(revisions(""), Vec::<String>::new());
+        assert_eq!(revisions("# good: [a1b2c3d] subject"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn revisions_of_a_bisect_log() {
+        assert_eq!(revisions("git bisect start"), Vec::<String>::new());
+        assert_eq!(revisions("git bisect bad e4f5a6b"), ["e4f5a6b"]);
+        assert_eq!(revisions("git bisect skip a1 b2"), ["a1", "b2"]);
+    }
+}
@@ -0,0 +1,1002 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::Arc,
+};
+
+use color_eyre::Result;
+use gix::{
+    ObjectId,
+    bstr::{BStr, BString, ByteVec},
+    date::{
+        Time,
+        time::format::{self, ISO8601},
+    },
+    objs::commit::message::BodyRef,
+    revision::walk::Sorting,
+};
+
+use crate::{
+    Item, diff, funcname,
+    partial::{PartialClone, Unchecked},
+    replace,
+    source::Source,
+    text::{self, Decoding, Names, Packed, Shared},
+};
+
+/// Side of a comparison of two revisions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Whose name, email and time of a commit are used: who wrote the change, or who committed it,
+/// last when it was rebased or cherry-picked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Role {
+    #[default]
+    Author,
+    Committer,
+}
+
+impl Role {
+    /// `author` or `committer`.
+    pub fn parse(name: &str) -> Option<Role> {
+        match name {
+            "author" => Some(Role::Author),
+            "committer" => Some(Role::Committer),
+            _ => None,
+        }
+    }
+}
+
+/// A commit of the log, its names shared with the other commits of the same people and its
+/// message packed with those of the commits read with it, see [`text`].
+#[derive(Clone, Debug)]
+pub struct LogEntryInfo {
+    pub id: gix::ObjectId,
+    pub parents: Vec<gix::ObjectId>,
+    pub author: Shared,
+    pub email: Shared,
+    pub message: Packed,
+    pub author_time: Time,
+    pub committer: Shared,
+    pub committer_email: Shared,
+    pub committer_time: Time,
+    /// Side of a `--compare` this commit is exclusive to.
+    pub side: Option<Side>,
+    /// An equivalent patch exists on the other side of the comparison (`git cherry` semantics).
+    pub equivalent: bool,
+    /// Why this commit couldn't be read, this entry standing for it with only its id, a message
+    /// saying so, and the times of its child to be sorted next to it.
+    pub unreadable: Option<String>,
+    /// Position in the walk of its repository, children always coming before their parents:
+    /// orders commits of the same time topologically.
+    pub order: usize,
+    /// Only its id, parents and times were read, its author, committer and message being left
+    /// empty until [`LogEntryInfo::load`].
+    pub deferred: bool,
+    /// How its names and message were made UTF-8.
+    pub decoding: Decoding,
+    /// Left out of the range, listed as the parent of one of its commits where the range stops,
+    /// like with `git log --boundary`.
+    pub boundary: bool,
+}
+
+impl LogEntryInfo {
+    fn placeholder(
+        id: ObjectId,
+        (author_time, committer_time): (Time, Time),
+        order: usize,
+        error: color_eyre::Report,
+    ) -> LogEntryInfo {
+        LogEntryInfo {
+            id,
+            parents: Vec::new(),
+            author: Shared::default(),
+            email: Shared::default(),
+            message: format!("unreadable commit: {error}").into(),
+            author_time,
+            committer: Shared::default(),
+            committer_email: Shared::default(),
+            committer_time,
+            side: None,
+            equivalent: false,
+            unreadable: Some(error.to_string()),
+            order,
+            deferred: false,
+            decoding: Decoding::Utf8,
+            boundary: false,
+        }
+    }
+
+    /// Read the author, committer and message of this entry of `repo`, if deferred. A commit
+    /// which can't be read anymore is marked as such instead, see [`LogEntryInfo::fail`].
+    pub fn load(&mut self, repo: &gix::Repository) {
+        if !self.deferred {
+            return;
+        }
+        let commit = repo.find_commit(self.id).map_err(Into::into);
+        match commit.and_then(|commit| read_alone(commit, &mut Names::default())) {
+            Ok(entry) => {
+                *self = LogEntryInfo {
+                    side: self.side,
+                    equivalent: self.equivalent,
+                    order: self.order,
+                    boundary: self.boundary,
+                    ..entry
+                }
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+
+    /// Mark this entry as standing for a commit which can't be read because of `error`, like
+    /// placeholders.
+    pub fn fail(&mut self, error: color_eyre::Report) {
+        self.message = format!("unreadable commit: {error}").into();
+        self.unreadable = Some(error.to_string());
+        self.deferred = false;
+    }
+
+    /// The author time, formatted as ISO 8601.
+    pub fn time(&self) -> String {
+        self.author_time.format(ISO8601)
+    }
+
+    /// The time of `role`.
+    pub fn time_of(&self, role: Role) -> Time {
+        match role {
+            Role::Author => self.author_time,
+            Role::Committer => self.committer_time,
+        }
+    }
+
+    /// Whether this is a root commit, without parents, placeholders aside.
+    pub fn is_root(&self) -> bool {
+        self.parents.is_empty() && self.unreadable.is_none()
+    }
+}
+
+/// Told how many commits of a source were read so far, as its walk goes, returning whether to
+/// go on.
+pub type Progress<'a> = dyn FnMut(&Source, usize) -> bool + 'a;
+
+/// Commits walked and decoded between two reports of the progress of a walk.
+const DECODE_BATCH: usize = 16 * 1024;
+
+/// Walk `spec` in `repo`, the repository of `source`, keeping the entries matching `filter`,
+/// within its reachability criteria, see [`Filter::tips`].
+///
+/// A commit which can't be read, like a corrupt loose object or one missing from its pack, is
+/// listed as a placeholder, see [`LogEntryInfo::unreadable`], the walk going on without its
+/// ancestors.
+///
+/// In a partial clone, commits the filter can't be checked against without objects which weren't
+/// fetched are left out, and counted.
+///
+/// With `defer`, the entries are only read enough to be sorted and walked, see
+/// [`LogEntryInfo::deferred`], unless the filter matches their author, committer or message.
+///
+/// The walk is stopped once `progress` says so, only the commits read until then being listed,
+/// or once `limit` commits matched, the newest by commit time.
+///
+/// With [`Filter::boundary`], the commits left out that are parents of those listed follow them,
+/// see [`LogEntryInfo::boundary`].
+pub fn collect(
+    repo: &gix::Repository,
+    source: &Source,
+    spec: &str,
+    filter: &Filter,
+    defer: bool,
+    limit: Option<usize>,
+    progress: &mut Progress,
+) -> Result<(Vec<LogEntryInfo>, Option<Unchecked>)> {
+    let mut entries = Vec::new();
+    let defer = defer && !(filter.mine || filter.person.is_some() || filter.committer.is_some());
+    let Some((tip, hidden)) = filter.tips(repo, source, spec)? else {
+        return Ok((entries, None));
+    };
+    // only the edges of the range need to know which commits were walked
+    let mut walked = filter.boundary.then(HashSet::new);
+    let partial = PartialClone::detect(repo);
+    let mut unchecked = 0;
+    let mut failed = false;
+    let mut stopped = !progress(source, 0);
+    // with a commit-graph, the walk reads no commit, and they are decoded by batches on as many
+    // threads as there are cores; without one, it reads them all to go on, and they are decoded
+    // as they come out of it, from the object cache
+    let graph = replace::commit_graph(repo).ok().flatten();
+    let by_batches = graph.is_some();
+    let mut walker = repo.clone();
+    if !by_batches {
+        walker.object_cache_size_if_unset(WALK_CACHE);
+    }
+    let mut commits = walker
+        .rev_walk([tip])
+        .with_hidden(hidden.iter().copied())
+        .sorting(Sorting::ByCommitTime(Default::default()))
+        .with_commit_graph(graph)
+        .all()?;
+    let mut ids = Vec::with_capacity(DECODE_BATCH);
+    let mut decoded = 0;
+    let mut full = limit == Some(0);
+    while !stopped && !full {
+        // no more commits are read than could still match
+        let wanted = limit.map_or(DECODE_BATCH, |limit| limit - entries.len());
+        let batch = commits.by_ref().take(wanted.min(DECODE_BATCH));
+        let batch = if by_batches {
+            ids.clear();
+            ids.extend(batch.map(|info| info.ok().map(|info| info.id)));
+            decode(repo, decoded, &ids, defer)
+        } else {
+            decode_each(
+                batch.map(|info| -> Result<_> { Ok(info?.object()?) }),
+                decoded,
+                defer,
+            )
+        };
+        if batch.is_empty() {
+            break;
+        }
+        decoded += batch.len();
+        for entry in batch {
+            let Ok(entry) = entry else {
+                failed = true;
+                continue;
+            };
+            if let Some(walked) = &mut walked {
+                walked.insert(entry.id);
+            }
+            match filter.matches(repo, &entry) {
+                Ok(true) => entries.push(entry),
+                Ok(false) => {}
+                Err(_) if partial.is_some() => unchecked += 1,
+                Err(err) => return Err(err),
+            }
+        }
+        full = limit.is_some_and(|limit| entries.len() >= limit);
+        stopped = !progress(source, decoded);
+    }
+    // the messages of the commits left out are freed with their arenas
+    if entries.len() < decoded {
+        text::repack(entries.iter_mut().map(|entry| &mut entry.message));
+    }
+    let unchecked = partial.filter(|_| unchecked > 0).map(|clone| Unchecked {
+        clone,
+        commits: unchecked,
+    });
+    // the parents of the commits listed the walk never reached were left out of the range, or of
+    // the limit, like with `git log --boundary -n`, unless it stopped before them or they can't
+    // be read
+    if let Some(walked) = walked.filter(|_| !stopped) {
+        let mut names = Names::default();
+        let mut edges = HashSet::new();
+        let mut boundary = Vec::new();
+        for entry in &entries {
+            for parent in &entry.parents {
+                if walked.contains(parent) || !edges.insert(*parent) {
+                    continue;
+                }
+                if let Ok(commit) = repo.find_commit(*parent)
+                    && let Ok(edge) = read_alone(commit, &mut names)
+                {
+                    boundary.push(LogEntryInfo {
+                        order: decoded,
+                        boundary: true,
+                        ..edge
+                    });
+                }
+            }
+        }
+        entries.append(&mut boundary);
+    }
+    // the walk reaches the commits it can't read last, as if they were the oldest: once the limit
+    // is reached, the parents of the commits walked may be unreadable without it failing yet
+    if stopped || !(failed || full) {
+        return Ok((entries, unchecked));
+    }
+    // the walk doesn't tell which commits it couldn't read: it is done again to find them, as far
+    // as it went when the limit was reached, the commits past it being readable or not
+    let reached = full.then_some(decoded);
+    for placeholder in unreadable(repo, tip, hidden, decoded, reached)? {
+        if filter.matches(repo, &placeholder).unwrap_or(false) {
+            entries.push(placeholder);
+        }
+    }
+    entries.sort_by_key(|entry| newest_first(entry, Role::Author));
+    Ok((entries, unchecked))
+}
+
+/// Placeholders for the commits a walk from `tip` but not `hidden` couldn't read: parents of
+/// the commits it reached, the first `limit` of them, which it never reached itself, except
+/// those cut off from a shallow clone. Each has the times of its newest child, and is ordered
+/// after the `walked` commits.
+fn unreadable(
+    repo: &gix::Repository,
+    tip: ObjectId,
+    hidden: Vec<ObjectId>,
+    walked: usize,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntryInfo>> {
+    let shallow = repo.shallow_commits()?;
+    let mut reached = HashSet::new();
+    // the newest child of each parent is the first walked
+    let mut parents = HashMap::new();
+    let commits = repo
+        .rev_walk([tip])
+        .with_hidden(hidden)
+        .sorting(Sorting::ByCommitTime(Default::default()))
+        .all()?;
+    for info in commits
+        .take(limit.unwrap_or(usize::MAX))
+        .filter_map(Result::ok)
+    {
+        reached.insert(info.id);
+        if !shallow.as_ref().is_some_and(|s| s.contains(&info.id)) {
+            for parent in &info.parent_ids {
+                parents.entry(*parent).or_insert(info.id);
+            }
+        }
+    }
+    let mut placeholders = Vec::new();
+    for (id, child) in parents {
+        if reached.contains(&id) {
+            continue;
+        }
+        let commit = repo.find_commit(id).map_err(Into::into);
+        let read = |commit| read(commit, true, &mut Names::default(), &mut Vec::new());
+        if let Err(err) = commit.and_then(read) {
+            let (child, _) = read(repo.find_commit(child)?)?;
+            let times = (child.author_time, child.committer_time);
+            placeholders.push(LogEntryInfo::placeholder(id, times, walked, err));
+        }
+    }
+    Ok(placeholders)
+}
+
+/// Sort key of the entries of one repository: newest time of `role` first, then in walk order,
+/// then by id.
+fn newest_first(entry: &LogEntryInfo, role: Role) -> (Reverse<i64>, usize, ObjectId) {
+    (Reverse(entry.time_of(role).seconds), entry.order, entry.id)
+}
+
+/// Sort entries of all sources by the time of `role`, newest first unless `reverse`.
+///
+/// Commits of the same time, common with scripted imports and rebases, are ordered by source
+/// label, a superproject before its submodules, then topologically within a repository, then by
+/// id: the same log is always listed the same way, reversed exactly by `reverse`.
+pub fn sort(entries: &mut [Item], reverse: bool, role: Role) {
+    entries.sort_by_cached_key(|(entry, source)| {
+        let (time, order, id) = newest_first(entry, role);
+        (time, source.label(), order, id)
+    });
+    if reverse {
+        entries.reverse();
+    }
+}
+
+/// Keep the page of sorted `entries` past the first `skip` commits, of `count` commits at most,
+/// like `git log --skip -n`: the edges of `--boundary` aren't counted, and those of the commits
+/// left out go with them.
+pub fn page(entries: &mut Vec<Item>, skip: usize, count: Option<usize>) {
+    let mut listed = 0;
+    entries.retain(|(entry, _)| {
+        if entry.boundary {
+            return true;
+        }
+        listed += 1;
+        listed > skip && count.is_none_or(|count| listed <= skip + count)
+    });
+    let parents: HashSet<ObjectId> = (entries.iter())
+        .filter(|(entry, _)| !entry.boundary)
+        .flat_map(|(entry, _)| entry.parents.iter().copied())
+        .collect();
+    entries.retain(|(entry, _)| !entry.boundary || parents.contains(&entry.id));
+}
+
+/// Walk commits reachable from `tips` but not from `hidden`.
+pub fn walk<'a>(
+    repo: &'a gix::Repository,
+    tips: impl IntoIterator<Item = ObjectId>,
+    hidden: impl IntoIterator<Item = ObjectId>,
+) -> Result<Box<dyn Iterator<Item = Result<LogEntryInfo>> + 'a>> {
+    let mut names = Names::default();
+    Ok(Box::new(
+        repo.rev_walk(tips)
+            .with_hidden(hidden)
+            .sorting(Sorting::ByCommitTime(Default::default()))
+            .all()?
+            .enumerate()
+            .map(move |(order, info)| {
+                Ok(LogEntryInfo {
+                    order,
+                    ..read_alone(info?.object()?, &mut names)?
+                })
+            }),
+    ))
+}
+
+/// The entries of the commits `ids` of `repo` alone, in that order, like `git log --no-walk`.
+pub fn read_commits(repo: &gix::Repository, ids: &[ObjectId]) -> Result<Vec<LogEntryInfo>> {
+    let mut names = Names::default();
+    let mut entries = Vec::with_capacity(ids.len());
+    for (order, id) in ids.iter().enumerate() {
+        let entry = read_alone(repo.find_commit(*id)?, &mut names)?;
+        entries.push(LogEntryInfo { order, ..entry });
+    }
+    Ok(entries)
+}
+
+/// Commits decoded by a thread at least: fewer and starting it isn't worth it.
+const DECODE_CHUNK: usize = 4096;
+
+/// Size of the object cache of a walk without a commit-graph, enough for the commits it read
+/// to still be there when they come out of it.
+const WALK_CACHE: usize = 8 * 1024 * 1024;
+
+/// The entries of the commits `ids` of `repo`, in order, each ordered by its position after
+/// `start`, see [`LogEntryInfo::order`]. `None` stands for a commit the walk couldn't read.
+///
+/// The walk having found them in the commit-graph, the commits are read from the objects of
+/// `repo` for the first time here, by as many threads as there are cores, each with its own
+/// handle on them and without an object cache.
+fn decode(
+    repo: &gix::Repository,
+    start: usize,
+    ids: &[Option<ObjectId>],
+    defer: bool,
+) -> Vec<Result<LogEntryInfo>> {
+    let decode_chunk = |repo: &gix::Repository, start: usize, ids: &[Option<ObjectId>]| {
+        let commits = ids.iter().map(|id| -> Result<_> {
+            let Some(id) = id else {
+                color_eyre::eyre::bail!("unreadable commit");
+            };
+            Ok(repo.find_commit(*id)?)
+        });
+        decode_each(commits, start, defer)
+    };
+    let threads = std::thread::available_parallelism().map_or(1, usize::from);
+    let chunk = ids.len().div_ceil(threads).max(DECODE_CHUNK);
+    if ids.len() <= chunk {
+        return decode_chunk(repo, start, ids);
+    }
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = ids
+            .chunks(chunk)
+            .enumerate()
+            .map(|(i, ids)| {
+                let mut repo = repo.clone();
+                repo.object_cache_size(0);
+                scope.spawn(move || decode_chunk(&repo, start + i * chunk, ids))
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+/// The entries of `commits`, in order, each ordered by its position after `start`. With `defer`,
+/// only what [`LogEntryInfo::deferred`] entries have is kept. The entries share their names and
+/// the arena of their messages.
+fn decode_each<'repo>(
+    commits: impl Iterator<Item = Result<gix::Commit<'repo>>>,
+    start: usize,
+    defer: bool,
+) -> Vec<Result<LogEntryInfo>> {
+    let (mut names, mut messages) = (Names::default(), Vec::new());
+    let entries: Vec<_> = commits
+        .enumerate()
+        .map(|(i, commit)| {
+            let (entry, message) = read(commit?, defer, &mut names, &mut messages)?;
+            Ok((
+                LogEntryInfo {
+                    order: start + i,
+                    ..entry
+                },
+                message,
+            ))
+        })
+        .collect();
+    let arena = Arc::from(messages);
+    let entries = entries.into_iter().map(|entry| {
+        let (entry, message) = entry?;
+        Ok(LogEntryInfo {
+            message: Packed::new(&arena, message),
+            ..entry
+        })
+    });
+    entries.collect()
+}
+
+/// The commit `spec` resolves to in `repo`.
+fn commit_id(repo: &gix::Repository, spec: &str) -> Result<ObjectId> {
+    Ok(repo.rev_parse_single(spec)?.object()?.peel_to_commit()?.id)
+}
+
+/// The entry of `commit`, deferred with `defer`, see [`LogEntryInfo::deferred`], with the names
+/// of `names`. Its message is appended to `messages`, at the range returned, to be given to the
+/// entry once they are all packed, see [`Packed::new`]. Its names and message are converted to
+/// UTF-8 from the encoding of the commit, see [`text::decode`].
+fn read(
+    commit: gix::Commit,
+    defer: bool,
+    names: &mut Names,
+    messages: &mut Vec<u8>,
+) -> Result<(LogEntryInfo, Range<usize>)> {
+    let commit_ref = commit.decode()?;
+
+    let id = commit.id;
+    let parents = commit_ref.parents().collect();
+    let author_time = commit_ref.author.time()?;
+    let committer = commit_ref.committer();
+    let committer_time = committer.time()?;
+    let mut entry = LogEntryInfo {
+        id,
+        parents,
+        author: Shared::default(),
+        email: Shared::default(),
+        message: Packed::default(),
+        author_time,
+        committer: Shared::default(),
+        committer_email: Shared::default(),
+        committer_time,
+        side: None,
+        equivalent: false,
+        unreadable: None,
+        order: 0,
+        deferred: defer,
+        decoding: Decoding::Utf8,
+        boundary: false,
+    };
+    let start = messages.len();
+    if !defer {
+        let encoding = Decoding::encoding(commit_ref.encoding.map(AsRef::as_ref));
+        let mut lossy = false;
+        let mut intern = |text: &BStr| {
+            let (text, invalid) = text::decode(text, encoding);
+            lossy |= invalid;
+            names.intern(&text)
+        };
+        let author = commit_ref.author();
+        entry.author = intern(author.name);
+        entry.email = intern(author.email);
+        entry.committer = intern(committer.name);
+        entry.committer_email = intern(committer.email);
+        let (message, invalid) = text::decode(commit_ref.message, encoding);
+        messages.extend_from_slice(&message);
+        lossy |= invalid;
+        entry.decoding = match lossy {
+            true => Decoding::Lossy(encoding),
+            false if encoding == encoding_rs::UTF_8 => Decoding::Utf8,
+            false => Decoding::Converted(encoding),
+        };
+    }
+    Ok((entry, start..messages.len()))
+}
+
+/// The entry of `commit`, with the names of `names`, its message alone in its arena.
+fn read_alone(commit: gix::Commit, names: &mut Names) -> Result<LogEntryInfo> {
+    let mut message = Vec::new();
+    let (entry, _) = read(commit, false, names, &mut message)?;
+    Ok(LogEntryInfo {
+        message: Packed::from(&message[..]),
+        ..entry
+    })
+}
+
+/// How dates are shown in the list, after git's `log.date`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    #[default]
+    Iso,
+    Relative,
+    Short,
+    Rfc,
+    Default,
+}
+
+impl DateFormat {
+    /// The format of a `log.date` value.
+    pub fn parse(name: &str) -> Option<DateFormat> {
+        Some(match name {
+            "iso" | "iso8601" | "absolute" => DateFormat::Iso,
+            "relative" => DateFormat::Relative,
+            "short" => DateFormat::Short,
+            "rfc" | "rfc2822" => DateFormat::Rfc,
+            "default" => DateFormat::Default,
+            _ => return None,
+        })
+    }
+
+    pub fn format(self, time: Time) -> String {
+        match self {
+            DateFormat::Iso => time.format(ISO8601),
+            DateFormat::Relative => format!("{:<14}", relative_time(time)),
+            DateFormat::Short => time.format(format::SHORT),
+            DateFormat::Rfc => time.format(format::RFC2822),
+            DateFormat::Default => time.format(format::DEFAULT),
+        }
+    }
+}
+
+/// Age of `time`, like `3 days ago`, the way git shows relative dates.
+pub fn relative_time(time: Time) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let secs = now - time.seconds;
+    if secs < 0 {
+        return "in the future".into();
+    }
+    let (n, unit) = match secs {
+        0..90 => (secs, "second"),
+        90..5_400 => ((secs + 30) / 60, "minute"),
+        5_400..129_600 => ((secs + 1_800) / 3_600, "hour"),
+        129_600..1_209_600 => ((secs + 43_200) / 86_400, "day"),
+        1_209_600..5_184_000 => ((secs + 302_400) / 604_800, "week"),
+        5_184_000..31_536_000 => ((secs + 1_296_000) / 2_592_000, "month"),
+        _ => ((secs + 15_768_000) / 31_536_000, "year"),
+    };
+    let plural = if n == 1 { "" } else { "s" };
+    format!("{n} {unit}{plural} ago")
+}
+
+/// Criteria restricting which walked commits are listed.
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// Only commits adding or removing this blob or tree.
+    pub find_object: Option<ObjectId>,
+    /// Only commits changing the definition of a function, as `(name, path)`.
+    pub function: Option<(String, String)>,
+    /// Only commits authored with the email configured in their repository.
+    pub mine: bool,
+    /// Only commits whose author, or a person of their trailers, matches.
+    pub person: Option<regex::bytes::Regex>,
+    /// Only commits whose committer, as `Name <email>`, matches.
+    pub committer: Option<regex::bytes::Regex>,
+    /// Only commits reachable from this revision, instead of the one walked.
+    pub tip: Option<String>,
+    /// Only commits not reachable from any of these revisions, like `git log --not`.
+    pub not: Vec<String>,
+    /// Per source label, revisions whose ancestors are left out of that source only.
+    pub until: Vec<(String, String)>,
+    /// Also list the commits left out where the range stops, the parents of its commits, like
+    /// `git log --boundary`.
+    pub boundary: bool,
+}
+
+impl Filter {
+    /// The commit to walk from in `repo`, the repository of `source`, `spec` unless
+    /// [`Filter::tip`] is set, and those whose ancestors are left out, of [`Filter::not`].
+    ///
+    /// In a submodule, revisions stand for the commits its superproject records at them, like
+    /// the submodule commits of `next` not in `main`: `None` when there is none at the tip, the
+    /// submodule not being part of it. Those of [`Filter::until`] are first looked up in the
+    /// submodule itself, to stop at one of its own tags.
+    pub fn tips(
+        &self,
+        repo: &gix::Repository,
+        source: &Source,
+        spec: &str,
+    ) -> Result<Option<(ObjectId, Vec<ObjectId>)>> {
+        let resolve = |rev: &str| -> Result<Option<ObjectId>> {
+            match source {
+                Source::Root(root) => Ok(Some(commit_id(&root.repo, rev)?)),
+                Source::Submodule(root, submodule) => {
+                    let tree = root
+                        .repo
+                        .rev_parse_single(rev)?
+                        .object()?
+                        .peel_to_commit()?
+                        .tree()?;
+                    let entry = tree.lookup_entry_by_path(submodule.path()?.to_string())?;
+                    Ok(entry
+                        .filter(|entry| entry.mode().is_commit())
+                        .map(|entry| entry.object_id()))
+                }
+            }
+        };
+        let tip = match &self.tip {
+            Some(rev) => match resolve(rev)? {
+                Some(tip) => tip,
+                None => return Ok(None),
+            },
+            None => commit_id(repo, spec)?,
+        };
+        let mut hidden = Vec::new();
+        for rev in &self.not {
+            hidden.extend(resolve(rev)?);
+        }
+        let label = source.label();
+        for (_, rev) in self.until.iter().filter(|(until, _)| *until == label) {
+            let stop = match commit_id(repo, rev) {
+                Ok(stop) => Some(stop),
+                Err(_) => resolve(rev)?,
+            };
+            hidden.extend(stop);
+        }
+        Ok(Some((tip, hidden)))
+    }
+
+    /// Whether `entry` of `repo` meets every criterion.
+    pub fn matches(&self, repo: &gix::Repository, entry: &LogEntryInfo) -> Result<bool> {
+        if let Some(object) = self.find_object
+            && !diff::touches_object(repo, entry.id, object)?
+        {
+            return Ok(false);
+        }
+        if self.mine && !is_mine(repo, entry) {
+            return Ok(false);
+        }
+        if let Some(person) = &self.person
+            && !involves(entry, person)
+        {
+            return Ok(false);
+        }
+        if let Some(committer) = &self.committer
+            && !committer.is_match(&identity(&entry.committer, &entry.committer_email))
+        {
+            return Ok(false);
+        }
+        if let Some((name, path)) = &self.function
+            && !funcname::changes_function(repo, entry.id, &entry.parents, path, name)?
+        {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+/// Whether `entry` was authored with the `user.email` of `repo`.
+pub fn is_mine(repo: &gix::Repository, entry: &LogEntryInfo) -> bool {
+    repo.author()
+        .and_then(Result::ok)
+        .is_some_and(|me| me.email.eq_ignore_ascii_case(&entry.email))
+}
+
+/// People named by the `Co-authored-by`, `Signed-off-by`, `Acked-by`, `Reviewed-by` and
+/// `Tested-by` trailers of `message`, usually as `Name <email>`.
+pub fn trailer_people(message: &[u8]) -> impl Iterator<Item = &BStr> {
+    BodyRef::from_bytes(message)
+        .trailers()
+        .attributions()
+        .map(|trailer| trailer.value)
+}
+
+/// Whether `person` matches the author of `entry`, as `Name <email>`, or one of its trailer
+/// people.
+pub fn involves(entry: &LogEntryInfo, person: &regex::bytes::Regex) -> bool {
+    person.is_match(&identity(&entry.author, &entry.email))
+        || trailer_people(&entry.message).any(|p| person.is_match(p))
+}
+
+/// `name <email>`, as written in commits.
+pub fn identity(name: &[u8], email: &[u8]) -> BString {
+    let mut identity = BString::from(name);
+    identity.push_str(" <");
+    identity.push_str(email);
+    identity.push_str(">");
+    identity
+}
+
+/// Commits only in `a` or only in `b`, like `git log --left-right --cherry-mark a...b`.
+///
+/// In a partial clone, commits whose changes weren't fetched are never marked equivalent.
+pub fn compare(repo: &gix::Repository, a: ObjectId, b: ObjectId) -> Result<Vec<LogEntryInfo>> {
+    let mut entries = Vec::new();
+    for (side, tip, hidden) in [(Side::Left, a, b), (Side::Right, b, a)] {
+        for entry in walk(repo, [tip], [hidden])? {
+            entries.push(LogEntryInfo {
+                side: Some(side),
+                ..entry?
+            });
+        }
+    }
+
+    let partial = PartialClone::detect(repo).is_some();
+    let mut patch_ids = HashMap::<ObjectId, [bool; 2]>::new();
+    let mut ids = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let patch_id = match diff::patch_id(repo, entry.id) {
+            // trees left out
+            Err(_) if partial => None,
+            patch_id => patch_id?,
+        };
+        if let Some(patch_id) = patch_id {
+            patch_ids.entry(patch_id).or_default()[usize::from(entry.side == Some(Side::Right))] =
+                true;
+        }
+        ids.push(patch_id);
+    }
+    for (entry, patch_id) in entries.iter_mut().zip(ids) {
+        entry.equivalent = patch_id.is_some_and(|id| patch_ids[&id] == [true, true]);
+    }
+
+    entries.sort_by_key(|entry| newest_first(entry, Role::Author));
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixture::Fixture, source::Root};
+
+    use super::*;
+
+    const ALICE: (&str, &str) = ("Alice", "alice@example.com");
+
+    fn list(root: &Root, filter: &Filter) -> Result<Vec<LogEntryInfo>> {
+        let source = Source::Root(root);
+        let (entries, _) = collect(
+            &root.repo,
+            &source,
+            "HEAD",
+            filter,
+            false,
+            None,
+            &mut |_, _| true,
+        )?;
+        Ok(entries)
+    }
+
+    #[test]
+    fn missing_commits_are_placeholders() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init(dir.path())?;
+        let readme = fixture.commit(ALICE, "Add README", &[("README", "gixl\n")])?;
+        let missing = ObjectId::from_hex(b"0123456789abcdef0123456789abcdef01234567")?;
+        let merge = fixture.merge_missing(ALICE, "Merge what was lost", missing)?;
+        let root = fixture.root("");
+
+        let entries = list(&root, &Filter::default())?;
+        let listed: Vec<_> = (entries.iter())
+            .map(|entry| (entry.id, entry.unreadable.is_some()))
+            .collect();
+        assert_eq!(listed, [(merge, false), (missing, true), (readme, false)]);
+        // with the times of its child
+        assert_eq!(entries[1].committer_time, entries[0].committer_time);
+
+        // a placeholder has no author to match
+        let filter = Filter {
+            person: Some(regex::bytes::Regex::new("Alice")?),
+            ..Filter::default()
+        };
+        let listed: Vec<_> = list(&root, &filter)?.iter().map(|entry| entry.id).collect();
+        assert_eq!(listed, [merge, readme]);
+        Ok(())
+    }
+
+    #[test]
+    fn replaced_parents_are_walked_without_the_commit_graph() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init_on_disk(dir.path())?;
+        let readme = fixture.commit(ALICE, "Add README", &[("README", "gixl\n")])?;
+        let graft = fixture.commit(ALICE, "Add NEWS", &[("NEWS", "\n")])?;
+        fixture.reset(readme);
+        fixture.commit(ALICE, "Add LICENSE", &[("LICENSE", "MIT\n")])?;
+        let news = fixture.commit(ALICE, "Add NEWS", &[("NEWS", "\n")])?;
+        let changelog = fixture.commit(ALICE, "Add CHANGELOG", &[("CHANGELOG", "\n")])?;
+        // git writes no commit-graph once objects are replaced
+        let status = std::process::Command::new("git")
+            .args(["commit-graph", "write", "--reachable"])
+            .current_dir(dir.path())
+            .status()?;
+        assert!(status.success());
+        fixture.replace(news, graft)?;
+        let mut root = fixture.root("");
+        assert!(root.repo.commit_graph_if_enabled()?.is_some());
+        replace::apply(&mut root.repo)?;
+
+        let listed: Vec<_> = (list(&root, &Filter::default())?.iter())
+            .map(|entry| entry.id)
+            .collect();
+        assert_eq!(listed, [changelog, news, readme]);
+        Ok(())
+    }
+
+    #[test]
+    fn commits_of_the_same_time_are_sorted_by_source() -> Result<()> {
+        // two repositories committed to at the same times
+        let (app_dir, lib_dir) = (tempfile::tempdir()?, tempfile::tempdir()?);
+        let mut app = Fixture::init(app_dir.path())?;
+        let app_readme = app.commit(ALICE, "Add README to app", &[("README", "app\n")])?;
+        let app_news = app.commit(ALICE, "Add NEWS to app", &[("NEWS", "\n")])?;
+        let mut lib = Fixture::init(lib_dir.path())?;
+        let lib_readme = lib.commit(ALICE, "Add README to lib", &[("README", "lib\n")])?;
+        let lib_news = lib.commit(ALICE, "Add NEWS to lib", &[("NEWS", "\n")])?;
+        let (app, lib) = (app.root("app"), lib.root("lib"));
+
+        let mut items = Vec::new();
+        for root in [&lib, &app] {
+            let source = Source::Root(root);
+            let entries = list(root, &Filter::default())?;
+            items.extend(entries.into_iter().map(|entry| (entry, source)));
+        }
+        let sorted = |items: &mut [Item], reverse| {
+            sort(items, reverse, Role::Committer);
+            (items.iter())
+                .map(|(entry, source)| (source.label(), entry.id))
+                .collect::<Vec<_>>()
+        };
+        let newest_first = [
+            ("app".to_owned(), app_news),
+            ("lib".to_owned(), lib_news),
+            ("app".to_owned(), app_readme),
+            ("lib".to_owned(), lib_readme),
+        ];
+        assert_eq!(sorted(&mut items, false), newest_first);
+        // whatever the order they were listed in
+        items.reverse();
+        assert_eq!(sorted(&mut items, false), newest_first);
+        let mut oldest_first = newest_first.clone();
+        oldest_first.reverse();
+        assert_eq!(sorted(&mut items, true), oldest_first);
+        Ok(())
+    }
+
+    #[test]
+    fn pages_are_those_of_the_whole_log() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init(dir.path())?;
+        fixture.commit(ALICE, "Add README", &[("README", "gixl\n")])?;
+        fixture.commit(ALICE, "Add LICENSE", &[("LICENSE", "MIT\n")])?;
+        let missing = ObjectId::from_hex(b"0123456789abcdef0123456789abcdef01234567")?;
+        let merge = fixture.merge_missing(ALICE, "Merge what was lost", missing)?;
+        let news = fixture.commit(ALICE, "Add NEWS", &[("NEWS", "\n")])?;
+        let root = fixture.root("");
+        let source = Source::Root(&root);
+
+        // like `gixl --skip` and `-n`, walking only as far as the page
+        let page = |filter: &Filter, skip, count, limit| -> Result<Vec<(ObjectId, bool)>> {
+            let (entries, _) = collect(
+                &root.repo,
+                &source,
+                "HEAD",
+                filter,
+                false,
+                limit,
+                &mut |_, _| true,
+            )?;
+            let mut items: Vec<Item> = entries.into_iter().map(|entry| (entry, source)).collect();
+            sort(&mut items, false, Role::Committer);
+            super::page(&mut items, skip, Some(count));
+            Ok(items
+                .iter()
+                .map(|(entry, _)| (entry.id, entry.boundary))
+                .collect())
+        };
+        let filter = Filter::default();
+        for skip in 0..5 {
+            for count in 1..3 {
+                assert_eq!(
+                    page(&filter, skip, count, Some(skip + count))?,
+                    page(&filter, skip, count, None)?,
+                    "--skip {skip} -n {count}"
+                );
+            }
+        }
+        assert_eq!(
+            page(&filter, 1, 2, Some(3))?,
+            [(merge, false), (missing, false)]
+        );
+
+        // the parents of the page are its edges
+        let filter = Filter {
+            boundary: true,
+            ..Filter::default()
+        };
+        assert_eq!(
+            page(&filter, 0, 1, Some(1))?,
+            [(news, false), (merge, true)]
+        );
+        Ok(())
+    }
+}
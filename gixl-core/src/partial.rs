@@ -0,0 +1,149 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use color_eyre::{Result, eyre::bail};
+use gix::bstr::ByteSlice;
+
+/// The remote a partial clone fetches the objects it left out from, on demand.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialClone {
+    pub remote: String,
+    /// Which objects were left out, like `blob:none` or `tree:0`, as given to `git clone --filter`.
+    pub filter: Option<String>,
+}
+
+impl PartialClone {
+    /// The promisor remote of `repo`, if it is a partial clone.
+    pub fn detect(repo: &gix::Repository) -> Option<PartialClone> {
+        let config = repo.config_snapshot();
+        // set by older versions of git, the promisor flag of the remote by newer ones
+        let remote = config
+            .string("extensions.partialClone")
+            .map(|name| name.to_str_lossy().into_owned())
+            .or_else(|| {
+                repo.remote_names().into_iter().find_map(|name| {
+                    let key = format!("remote.{name}.promisor");
+                    config
+                        .boolean(&key)?
+                        .then(|| name.to_str_lossy().into_owned())
+                })
+            })?;
+        let filter = config
+            .string(format!("remote.{remote}.partialclonefilter"))
+            .map(|filter| filter.to_str_lossy().into_owned());
+        Some(PartialClone { remote, filter })
+    }
+
+    /// Fetch from the remote the objects reachable from `revs` which were left out, those of
+    /// `paths` only when some are given, like git does on demand but all at once. Each round
+    /// fetches the trees left out of the previous one, with their subtrees. Returns how many
+    /// objects were fetched.
+    pub fn fetch_missing(
+        &self,
+        repo: &gix::Repository,
+        revs: &[&str],
+        paths: &[&str],
+    ) -> Result<usize> {
+        let dir = repo.workdir().unwrap_or(repo.git_dir());
+        let mut fetched = 0;
+        let mut previous = Vec::new();
+        loop {
+            let output = Command::new("git")
+                .args(["rev-list", "--objects", "--missing=print"])
+                .args(revs)
+                .arg("--")
+                .args(paths)
+                .current_dir(dir)
+                .output()?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                bail!(
+                    "git rev-list: {}",
+                    stderr.lines().next().unwrap_or("failed")
+                );
+            }
+            let missing: Vec<_> = output
+                .stdout
+                .lines()
+                .filter_map(|line| line.strip_prefix(b"?"))
+                .map(|id| id.to_owned())
+                .collect();
+            if missing.is_empty() {
+                return Ok(fetched);
+            }
+            if missing == previous {
+                bail!(
+                    "{} objects could not be fetched from {}",
+                    missing.len(),
+                    self.remote
+                );
+            }
+            // the options git uses to fetch objects on demand
+            let mut child = Command::new("git")
+                .args([
+                    "-c",
+                    "fetch.negotiationAlgorithm=noop",
+                    "fetch",
+                    &self.remote,
+                ])
+                .args([
+                    "--no-tags",
+                    "--no-write-fetch-head",
+                    "--recurse-submodules=no",
+                ])
+                .args(["--filter=blob:none", "--stdin"])
+                .current_dir(dir)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .spawn()?;
+            let mut stdin = child.stdin.take().expect("piped");
+            for id in &missing {
+                stdin.write_all(id)?;
+                stdin.write_all(b"\n")?;
+            }
+            drop(stdin);
+            if !child.wait()?.success() {
+                bail!("git fetch {} failed", self.remote);
+            }
+            fetched += missing.len();
+            previous = missing;
+        }
+    }
+
+    /// Whether trees were left out too, not only blobs.
+    pub fn omits_trees(&self) -> bool {
+        self.filter
+            .as_deref()
+            .is_some_and(|f| f.starts_with("tree:"))
+    }
+}
+
+impl std::fmt::Display for PartialClone {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.filter {
+            Some(filter) => write!(f, "partial clone of {} ({filter})", self.remote),
+            None => write!(f, "partial clone of {}", self.remote),
+        }
+    }
+}
+
+/// Commits of a partial clone which couldn't be checked against a filter, like `-L` or
+/// `--find-object`, the objects it needs not having been fetched: they are left out of the log.
+#[derive(Clone, Debug)]
+pub struct Unchecked {
+    pub clone: PartialClone,
+    pub commits: usize,
+}
+
+impl std::fmt::Display for Unchecked {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let plural = if self.commits == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} commit{plural} not checked against the filter, their objects not fetched in this {}",
+            self.commits, self.clone
+        )
+    }
+}
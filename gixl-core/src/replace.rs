@@ -0,0 +1,120 @@
+use color_eyre::Result;
+use gix::ObjectId;
+
+/// The references replacing objects are under, `refs/replace/` unless set by
+/// `GIT_REPLACE_REF_BASE`.
+pub fn ref_base() -> String {
+    std::env::var("GIT_REPLACE_REF_BASE").unwrap_or_else(|_| "refs/replace/".into())
+}
+
+/// Whether objects are replaced, like git does unless `core.useReplaceRefs` is false or
+/// `GIT_NO_REPLACE_OBJECTS` is set, as by `--no-replace-objects`.
+pub fn enabled(repo: &gix::Repository) -> bool {
+    std::env::var_os("GIT_NO_REPLACE_OBJECTS").is_none()
+        && repo
+            .config_snapshot()
+            .boolean("core.useReplaceRefs")
+            .unwrap_or(true)
+}
+
+/// Objects of `repo` replaced with `git replace`, as `(original, replacement)`.
+pub fn replacements(repo: &gix::Repository) -> Result<Vec<(ObjectId, ObjectId)>> {
+    let base = ref_base();
+    let mut replacements = Vec::new();
+    for reference in repo.references()?.prefixed(base.as_str())? {
+        let Ok(reference) = reference else {
+            continue;
+        };
+        let name = reference.name().as_bstr();
+        if let (Ok(original), Some(replacement)) = (
+            ObjectId::from_hex(&name[base.len()..]),
+            reference.target().try_id(),
+        ) {
+            replacements.push((original, replacement.to_owned()));
+        }
+    }
+    Ok(replacements)
+}
+
+/// Whether `repo` reads objects through replacements, as set by [`apply`].
+pub fn replaced(repo: &gix::Repository) -> bool {
+    !repo.objects.ignore_replacements && repo.objects.store_ref().replacements().next().is_some()
+}
+
+/// The commit-graph of `repo` if enabled, unless objects are replaced: like git, their history
+/// is then read from the commits, as the graph records the parents they were written with.
+pub fn commit_graph(repo: &gix::Repository) -> Result<Option<gix::commitgraph::Graph>> {
+    if replaced(repo) {
+        return Ok(None);
+    }
+    Ok(repo.commit_graph_if_enabled()?)
+}
+
+/// Make `repo` read objects through their replacements when [`enabled`], so that commits are
+/// walked and decoded as git shows them, and as they were written otherwise. Returns how many
+/// objects are replaced.
+///
+/// gix reads replace refs when opening a repository, under `gitoxide.objects.replaceRefBase` as
+/// set by `GIT_REPLACE_REF_BASE`, but only once `core.useReplaceRefs` is false, which it takes
+/// for `GIT_NO_REPLACE_OBJECTS`: `repo` is opened again with it when anything is replaced.
+pub fn apply(repo: &mut gix::Repository) -> Result<usize> {
+    let count = match enabled(repo) {
+        true => replacements(repo)?.len(),
+        false => 0,
+    };
+    if count == 0 {
+        repo.objects.ignore_replacements = true;
+        return Ok(0);
+    }
+    let options = repo
+        .open_options()
+        .clone()
+        .config_overrides(["core.useReplaceRefs=false"]);
+    *repo = gix::open_opts(repo.workdir().unwrap_or(repo.git_dir()), options)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixture::Fixture;
+
+    use super::*;
+
+    const ALICE: (&str, &str) = ("Alice", "alice@example.com");
+
+    fn messages(repo: &gix::Repository) -> Result<Vec<String>> {
+        let head = repo.head_id()?.detach();
+        let mut messages = Vec::new();
+        for info in repo.rev_walk([head]).all()? {
+            messages.push(info?.object()?.message_raw()?.to_string());
+        }
+        Ok(messages)
+    }
+
+    #[test]
+    fn history_goes_through_replacements() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init_on_disk(dir.path())?;
+        let readme = fixture.commit(ALICE, "Add README", &[("README", "gixl\n")])?;
+        let replacement = fixture.commit(ALICE, "Add a license", &[("LICENSE", "MIT\n")])?;
+        fixture.reset(readme);
+        let license = fixture.commit(ALICE, "Add LICENSE", &[("LICENSE", "MIT\n")])?;
+        fixture.commit(ALICE, "Add NEWS", &[("NEWS", "\n")])?;
+        fixture.replace(license, replacement)?;
+
+        let mut repo = fixture.root("").repo;
+        assert_eq!(apply(&mut repo)?, 1);
+        assert_eq!(
+            messages(&repo)?,
+            ["Add NEWS", "Add a license", "Add README"]
+        );
+
+        // as written with core.useReplaceRefs=false, like with --no-replace-objects
+        let options =
+            gix::open::Options::isolated().config_overrides(["core.useReplaceRefs=false"]);
+        let mut repo = gix::open_opts(dir.path(), options)?;
+        assert_eq!(apply(&mut repo)?, 0);
+        assert_eq!(messages(&repo)?, ["Add NEWS", "Add LICENSE", "Add README"]);
+        Ok(())
+    }
+}
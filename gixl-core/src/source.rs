@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use color_eyre::{Result, eyre::eyre};
+use gix::bstr::ByteSlice;
+
+use crate::{cache, replace};
+
+/// A repository given on the command line.
+pub struct Root {
+    /// Shown in the source column, empty when a single repository is listed.
+    pub name: String,
+    pub repo: gix::Repository,
+    /// Directory git commands are run from.
+    pub dir: PathBuf,
+}
+
+/// Where an entry comes from: one of the listed repositories, or one of their submodules.
+#[derive(Clone, Copy)]
+pub enum Source<'repo> {
+    Root(&'repo Root),
+    Submodule(&'repo Root, &'repo gix::Submodule<'repo>),
+}
+
+impl<'repo> Source<'repo> {
+    /// The listed repository, the superproject of a submodule.
+    pub fn root(&self) -> &'repo Root {
+        match self {
+            Source::Root(root) | Source::Submodule(root, _) => root,
+        }
+    }
+
+    pub fn submodule(&self) -> Option<&'repo gix::Submodule<'repo>> {
+        match self {
+            Source::Root(_) => None,
+            Source::Submodule(_, submodule) => Some(submodule),
+        }
+    }
+
+    /// Name for the source column, like `repo`, `repo/submodule`, or `submodule` for a single
+    /// repository.
+    pub fn label(&self) -> String {
+        match self {
+            Source::Root(root) => root.name.clone(),
+            Source::Submodule(root, submodule) if root.name.is_empty() => {
+                submodule.name().to_str_lossy().into_owned()
+            }
+            Source::Submodule(root, submodule) => format!("{}/{}", root.name, submodule.name()),
+        }
+    }
+
+    /// The repository of this source; submodules must be checked out.
+    pub fn open(&self) -> Result<gix::Repository> {
+        match self {
+            Source::Root(root) => Ok(root.repo.clone()),
+            Source::Submodule(_, submodule) => {
+                open_submodule(submodule)?.ok_or_else(|| eyre!("submodule is not checked out"))
+            }
+        }
+    }
+
+    /// Directory to run git commands concerning this source from, the worktree when checked out.
+    pub fn dir(&self) -> PathBuf {
+        match self {
+            Source::Root(root) => root.dir.clone(),
+            Source::Submodule(_, submodule) => submodule
+                .work_dir()
+                .ok()
+                .filter(|dir| dir.join(".git").exists())
+                .unwrap_or_else(|| submodule.git_dir()),
+        }
+    }
+}
+
+/// The repository of `submodule` if checked out, its objects replaced like in git, see
+/// [`replace::apply`], and cached, see [`cache::apply`].
+pub fn open_submodule(submodule: &gix::Submodule) -> Result<Option<gix::Repository>> {
+    let Some(mut repo) = submodule.open()? else {
+        return Ok(None);
+    };
+    replace::apply(&mut repo)?;
+    cache::apply(&mut repo);
+    Ok(Some(repo))
+}
+
+impl PartialEq for Source<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.root(), other.root())
+            && self.submodule().map(|s| s.name()) == other.submodule().map(|s| s.name())
+    }
+}
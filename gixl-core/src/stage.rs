@@ -0,0 +1,374 @@
+//! Changes of the working tree and of the index, staging them hunk by hunk, or line by line, by
+//! writing the index like `git add -p` does, and committing the index.
+
+use color_eyre::{Result, eyre::bail};
+use gix::{
+    ObjectId,
+    bstr::{BStr, BString, ByteSlice},
+    index::entry::{Flags, Mode, Stage, Stat},
+};
+
+use crate::diff;
+
+/// A file whose content in the working tree differs from the index, or in the index from HEAD.
+#[derive(Clone, Debug)]
+pub struct Change {
+    pub path: BString,
+    /// Whether the change is between HEAD and the index, rather than the index and the working
+    /// tree.
+    pub staged: bool,
+    /// Hunks in unified format, `None` for binary files.
+    pub patch: Option<BString>,
+}
+
+fn blob_data(repo: &gix::Repository, id: ObjectId) -> Result<Vec<u8>> {
+    Ok(repo.find_blob(id)?.take_data())
+}
+
+/// Files of the index changed in the working tree, then those changed by the index, both
+/// ordered by path. Only files present on both sides count: added, removed and conflicted
+/// files, symbolic links and submodules are left to git, as are content filters.
+pub fn changes(repo: &gix::Repository) -> Result<Vec<Change>> {
+    let Some(workdir) = repo.workdir() else {
+        bail!("no working tree");
+    };
+    let index = repo.index_or_empty()?;
+    let head = repo.head_tree().ok();
+    let mut unstaged = Vec::new();
+    let mut staged = Vec::new();
+    for entry in index.entries() {
+        if entry.stage() != Stage::Unconflicted
+            || !matches!(entry.mode, Mode::FILE | Mode::FILE_EXECUTABLE)
+        {
+            continue;
+        }
+        let path = entry.path(&index);
+        let file = workdir.join(gix::path::from_bstr(path));
+        if let Ok(metadata) = gix::index::fs::Metadata::from_path_no_follow(&file)
+            && metadata.is_file()
+        {
+            let stat = Stat::from_fs(&metadata)?;
+            // unchanged since it was staged, without reading it
+            if stat.size != entry.stat.size || stat.mtime != entry.stat.mtime {
+                let old = blob_data(repo, entry.id)?;
+                let new = std::fs::read(&file)?;
+                if old != new {
+                    unstaged.push(Change {
+                        path: path.to_owned(),
+                        staged: false,
+                        patch: diff::unified_data(repo, &old, &new)?,
+                    });
+                }
+            }
+        }
+        let committed = head
+            .as_ref()
+            .and_then(|tree| tree.lookup_entry_by_path(gix::path::from_bstr(path)).ok()?)
+            .filter(|committed| committed.mode().is_blob());
+        if let Some(committed) = committed
+            && committed.object_id() != entry.id
+        {
+            staged.push(Change {
+                path: path.to_owned(),
+                staged: true,
+                patch: diff::unified(repo, committed.object_id(), entry.id)?,
+            });
+        }
+    }
+    unstaged.extend(staged);
+    Ok(unstaged)
+}
+
+/// Hunk `hunk` of the patch of `change`, from its `@@` header on.
+pub fn hunk(change: &Change, hunk: usize) -> Option<&[u8]> {
+    let patch = change.patch.as_ref()?;
+    let mut starts = patch
+        .lines_with_terminator()
+        .scan(0, |offset, line| {
+            let start = *offset;
+            *offset += line.len();
+            Some((start, line))
+        })
+        .filter(|(_, line)| line.starts_with(b"@@"))
+        .map(|(start, _)| start)
+        .skip(hunk);
+    let start = starts.next()?;
+    let end = starts.next().unwrap_or(patch.len());
+    Some(&patch[start..end])
+}
+
+/// Start and length of one side of a hunk header, as in `-12,3` or `+4`.
+fn range(side: &[u8]) -> Option<(usize, usize)> {
+    let side = side.to_str().ok()?;
+    let (start, len) = side[1..].split_once(',').unwrap_or((&side[1..], "1"));
+    Some((start.parse().ok()?, len.parse().ok()?))
+}
+
+/// `base` with `hunk` of a diff against it applied, or reverted when `reverse`, the diff then
+/// being against the result. With `line`, the index of a changed line after the header, only
+/// that line is applied, the others being left as they are in `base`.
+pub fn apply(base: &[u8], hunk: &[u8], line: Option<usize>, reverse: bool) -> Result<Vec<u8>> {
+    let mut lines = hunk.lines_with_terminator();
+    let header = lines.next().unwrap_or_default();
+    let sides: Vec<_> = header.split_str(" ").take(3).collect();
+    let side = match reverse {
+        true => sides.get(2),
+        false => sides.get(1),
+    };
+    let Some((start, len)) = side.and_then(|side| range(side)) else {
+        bail!("not a hunk: {}", header.trim_end().as_bstr());
+    };
+    let (removed, added) = match reverse {
+        true => (b'+', b'-'),
+        false => (b'-', b'+'),
+    };
+    let base_lines: Vec<_> = base.lines_with_terminator().collect();
+    // an empty range starts after the line given
+    let from = if len == 0 {
+        start
+    } else {
+        start.saturating_sub(1)
+    };
+    if from + len > base_lines.len() {
+        bail!("the hunk doesn't apply, the file changed");
+    }
+    if let Some(line) = line
+        && !lines
+            .clone()
+            .nth(line)
+            .is_some_and(|l| l.starts_with(&[removed]) || l.starts_with(&[added]))
+    {
+        bail!("not a changed line");
+    }
+    let mut result: Vec<u8> = base_lines[..from].concat();
+    let mut at = from;
+    for (i, text) in lines.enumerate() {
+        let applied = line.is_none_or(|line| line == i);
+        let Some((&tag, content)) = text.split_first() else {
+            continue;
+        };
+        if tag == b' ' || tag == removed {
+            match base_lines.get(at) {
+                Some(old)
+                    if old.trim_end_with(|c| c == '\n') == content.trim_end_with(|c| c == '\n') =>
+                {
+                    if tag == b' ' || !applied {
+                        result.extend_from_slice(old);
+                    }
+                    at += 1;
+                }
+                _ => bail!("the hunk doesn't apply, the file changed"),
+            }
+        } else if tag == added && applied {
+            result.extend_from_slice(content);
+        }
+    }
+    if at != from + len {
+        bail!("the hunk doesn't apply, the file changed");
+    }
+    result.extend(base_lines[at..].concat());
+    Ok(result)
+}
+
+/// Replace the content of `path` in the index by what `edit` makes of it.
+fn update(
+    repo: &gix::Repository,
+    path: &BStr,
+    edit: impl FnOnce(&[u8]) -> Result<Vec<u8>>,
+) -> Result<()> {
+    let mut index = repo.open_index()?;
+    if index.link().is_some() || index.is_sparse() {
+        bail!("split and sparse indexes are not supported");
+    }
+    let Some(i) = index.entry_index_by_path_and_stage(path, Stage::Unconflicted) else {
+        bail!("{path} is not in the index");
+    };
+    let old = blob_data(repo, index.entries()[i].id)?;
+    let new = repo.write_blob(edit(&old)?)?.detach();
+    let entry = &mut index.entries_mut()[i];
+    entry.id = new;
+    // git compares the content again
+    entry.stat = Stat::default();
+    // the trees cached no longer match
+    index.remove_tree();
+    index.write(Default::default())?;
+    Ok(())
+}
+
+/// Stage hunk `hunk` of an unstaged change, or unstage it from a staged one, or only one of its
+/// lines with `line`, as in [`apply`].
+pub fn toggle(
+    repo: &gix::Repository,
+    change: &Change,
+    hunk: usize,
+    line: Option<usize>,
+) -> Result<()> {
+    let Some(text) = self::hunk(change, hunk) else {
+        bail!("{}: no such hunk", change.path);
+    };
+    update(repo, change.path.as_ref(), |old| {
+        apply(old, text, line, change.staged)
+    })
+}
+
+/// The tree of the index, like `git write-tree`, `None` when it is the one of HEAD: there is
+/// nothing to commit. Files only intended to be added are left out.
+pub fn index_tree(repo: &gix::Repository) -> Result<Option<ObjectId>> {
+    let index = repo.index_or_empty()?;
+    let mut editor = repo.edit_tree(ObjectId::empty_tree(repo.object_hash()))?;
+    for entry in index.entries() {
+        let path = entry.path(&index);
+        if entry.stage() != Stage::Unconflicted {
+            bail!("{path} is not merged");
+        }
+        if entry.flags.contains(Flags::INTENT_TO_ADD) {
+            continue;
+        }
+        let Some(mode) = entry.mode.to_tree_entry_mode() else {
+            bail!("{path}: unknown mode {:o}", entry.mode.bits());
+        };
+        editor.upsert(path, mode.kind(), entry.id)?;
+    }
+    let tree = editor.write()?.detach();
+    let head = repo.head_tree_id_or_empty()?.detach();
+    Ok((tree != head).then_some(tree))
+}
+
+/// The message left of `text` once edited, like `git commit` cleans it: without comments,
+/// trailing spaces and blank lines around paragraphs.
+pub fn cleanup(text: &str) -> String {
+    let mut message = String::new();
+    let mut blank = false;
+    for line in text.lines().filter(|line| !line.starts_with('#')) {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank = !message.is_empty();
+            continue;
+        }
+        if blank {
+            message.push('\n');
+            blank = false;
+        }
+        message.push_str(line);
+        message.push('\n');
+    }
+    message
+}
+
+/// Hooks `git commit` runs.
+const COMMIT_HOOKS: [&str; 4] = [
+    "pre-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+];
+
+/// What committing in `repo` takes `git commit` for, as [`commit`] does neither: signing with
+/// `commit.gpgSign`, or running a hook.
+pub fn needs_git(repo: &gix::Repository) -> Option<String> {
+    let config = repo.config_snapshot();
+    if config.boolean("commit.gpgSign").unwrap_or(false) {
+        return Some("commit.gpgSign".into());
+    }
+    let hooks = match config.trusted_path("core.hooksPath") {
+        Some(Ok(path)) => repo.workdir().unwrap_or(repo.git_dir()).join(path),
+        _ => repo.common_dir().join("hooks"),
+    };
+    let hook = COMMIT_HOOKS
+        .iter()
+        .find(|hook| hooks.join(hook).is_file())?;
+    Some(format!("the {hook} hook"))
+}
+
+/// Commit `tree` on top of HEAD with `message`, moving the branch checked out, like
+/// `git commit` when it has nothing more to do: no operation is in progress, nor anything
+/// [`needs_git`].
+pub fn commit(repo: &gix::Repository, tree: ObjectId, message: &str) -> Result<ObjectId> {
+    if repo.state().is_some() {
+        bail!("an operation is in progress, to conclude with git commit");
+    }
+    if let Some(what) = needs_git(repo) {
+        bail!("{what} needs git commit");
+    }
+    let parent = repo.head_id().ok().map(|id| id.detach());
+    Ok(repo.commit("HEAD", message, tree, parent)?.detach())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &[u8] = b"one\ntwo\nthree\nfour\n";
+    const HUNK: &[u8] = b"@@ -1,4 +1,4 @@\n one\n-two\n-three\n+2\n+3\n four\n";
+
+    #[test]
+    fn apply_a_hunk() {
+        let applied = apply(BASE, HUNK, None, false).unwrap();
+        assert_eq!(applied.as_bstr(), "one\n2\n3\nfour\n");
+        let reverted = apply(&applied, HUNK, None, true).unwrap();
+        assert_eq!(reverted.as_bstr(), BASE.as_bstr());
+    }
+
+    #[test]
+    fn apply_a_line() {
+        // the removal of `two`, then the addition of `3`
+        let applied = apply(BASE, HUNK, Some(1), false).unwrap();
+        assert_eq!(applied.as_bstr(), "one\nthree\nfour\n");
+        let applied = apply(BASE, HUNK, Some(4), false).unwrap();
+        assert_eq!(applied.as_bstr(), "one\ntwo\nthree\n3\nfour\n");
+        let error = apply(BASE, HUNK, Some(0), false).unwrap_err();
+        assert_eq!(error.to_string(), "not a changed line");
+    }
+
+    #[test]
+    fn apply_an_addition_to_an_empty_range() {
+        let hunk = b"@@ -2,0 +3 @@\n+2.5\n";
+        let applied = apply(BASE, hunk, None, false).unwrap();
+        assert_eq!(applied.as_bstr(), "one\ntwo\n2.5\nthree\nfour\n");
+    }
+
+    #[test]
+    fn apply_refuses_a_changed_file() {
+        let error = apply(b"one\n2\nthree\nfour\n", HUNK, None, false).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the hunk doesn't apply, the file changed"
+        );
+        let error = apply(b"one\n", HUNK, None, false).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the hunk doesn't apply, the file changed"
+        );
+        let error = apply(BASE, b"one\n", None, false).unwrap_err();
+        assert_eq!(error.to_string(), "not a hunk: one");
+    }
+
+    #[test]
+    fn cleanup_like_git_commit() {
+        let text =
+            "\n\nSubject  \n\n\n\nBody\nwrapped\t\n\n# Please enter the commit message\n#\n\n";
+        assert_eq!(cleanup(text), "Subject\n\nBody\nwrapped\n");
+        assert_eq!(cleanup("# only comments\n\n"), "");
+    }
+
+    #[test]
+    fn hooks_need_git_commit() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = crate::fixture::Fixture::init(dir.path())?;
+        fixture.commit(
+            ("Alice", "alice@example.com"),
+            "Add README",
+            &[("README", "gixl\n")],
+        )?;
+        let repo = fixture.root("").repo;
+        assert_eq!(needs_git(&repo), None);
+
+        std::fs::create_dir_all(dir.path().join("hooks"))?;
+        std::fs::write(dir.path().join("hooks/commit-msg"), "#!/bin/sh\n")?;
+        assert_eq!(needs_git(&repo).as_deref(), Some("the commit-msg hook"));
+        let tree = repo.head_tree_id_or_empty()?.detach();
+        let error = commit(&repo, tree, "Commit without the hook\n").unwrap_err();
+        assert_eq!(error.to_string(), "the commit-msg hook needs git commit");
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use color_eyre::Result;
+use gix::{ObjectId, bstr::BString};
+
+use crate::{
+    diff,
+    log::{self, LogEntryInfo},
+    replace, source,
+};
+
+/// A gitlink change in a superproject commit.
+#[derive(Clone, Debug)]
+pub struct Bump {
+    pub path: BString,
+    /// Null when the submodule was added.
+    pub old: ObjectId,
+    /// Null when the submodule was removed.
+    pub new: ObjectId,
+    /// Submodule commits in `old..new`, if the submodule is checked out.
+    pub commits: Option<Vec<LogEntryInfo>>,
+}
+
+/// Submodule pointers moved by superproject commit `id`.
+pub fn bumps(repo: &gix::Repository, id: ObjectId) -> Result<Vec<Bump>> {
+    gitlinks(repo, diff::commit_diff(repo, id)?)
+}
+
+/// Submodule pointers moved from superproject commit `from` to `to`, like the commits in between
+/// squashed into one.
+pub fn bumps_between(repo: &gix::Repository, from: ObjectId, to: ObjectId) -> Result<Vec<Bump>> {
+    gitlinks(repo, diff::range_diff(repo, from, to)?)
+}
+
+/// The gitlinks among the changed `files` of `repo`.
+fn gitlinks(repo: &gix::Repository, files: Vec<diff::FileDiff>) -> Result<Vec<Bump>> {
+    let mut bumps = Vec::new();
+    for file in files {
+        let is_gitlink =
+            |mode: Option<gix::object::tree::EntryMode>| mode.is_some_and(|m| m.is_commit());
+        if !is_gitlink(file.mode) && !is_gitlink(file.previous_mode) {
+            continue;
+        }
+        let commits = match find(repo, &file.path)? {
+            Some(sub) if !file.id.is_null() => {
+                let hidden = Some(file.previous_id).filter(|id| !id.is_null());
+                log::walk(&sub, [file.id], hidden)
+                    .and_then(|walk| walk.collect::<Result<Vec<_>>>())
+                    .ok()
+            }
+            _ => None,
+        };
+        bumps.push(Bump {
+            path: file.path,
+            old: file.previous_id,
+            new: file.id,
+            commits,
+        });
+    }
+    Ok(bumps)
+}
+
+/// Open the checked out submodule of `repo` at `path`.
+pub fn find(repo: &gix::Repository, path: &BString) -> Result<Option<gix::Repository>> {
+    let Some(submodules) = repo.submodules()? else {
+        return Ok(None);
+    };
+    for submodule in submodules {
+        if submodule.path()?.as_ref() == path.as_slice() {
+            return source::open_submodule(&submodule);
+        }
+    }
+    Ok(None)
+}
+
+/// The first of the superproject `commits` (oldest first) whose gitlink at `path` includes
+/// submodule commit `id`, i.e. when that change landed in the superproject.
+pub fn landing(
+    repo: &gix::Repository,
+    sub: &gix::Repository,
+    path: &BString,
+    id: ObjectId,
+    commits: impl IntoIterator<Item = ObjectId>,
+) -> Result<Option<ObjectId>> {
+    let cache = replace::commit_graph(sub)?;
+    let mut graph = sub.revision_graph(cache.as_ref());
+    for commit in commits {
+        let tree = repo.find_commit(commit)?.tree()?;
+        let Some(entry) = tree.lookup_entry_by_path(path.to_string())? else {
+            continue;
+        };
+        if !entry.mode().is_commit() {
+            continue;
+        }
+        let gitlink = entry.object_id();
+        if gitlink == id
+            || sub
+                .merge_base_with_graph(id, gitlink, &mut graph)
+                .is_ok_and(|base| base.detach() == id)
+        {
+            return Ok(Some(commit));
+        }
+    }
+    Ok(None)
+}
+
+/// State of a submodule checkout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Health {
+    Clean,
+    /// Not cloned or not checked out; its history can't be listed.
+    Uninitialized,
+    /// The checked out commit differs from the gitlink recorded in the superproject index.
+    OutOfSync,
+    /// Local modifications in the submodule worktree.
+    Dirty,
+}
+
+impl Health {
+    pub fn label(self) -> &'static str {
+        match self {
+            Health::Clean => "clean",
+            Health::Uninitialized => "uninitialized",
+            Health::OutOfSync => "out of sync",
+            Health::Dirty => "dirty",
+        }
+    }
+}
+
+/// State of the checkout of `submodule`, like `git submodule status` shows it.
+pub fn health(submodule: &gix::Submodule) -> Result<Health> {
+    let ignore = submodule.ignore()?.unwrap_or_default();
+    let status = submodule.status(ignore, true)?;
+    Ok(
+        if !status.state.repository_exists || !status.state.worktree_checkout {
+            Health::Uninitialized
+        } else if status.checked_out_head_id != status.index_id {
+            Health::OutOfSync
+        } else if status.is_dirty() == Some(true) {
+            Health::Dirty
+        } else {
+            Health::Clean
+        },
+    )
+}
@@ -0,0 +1,195 @@
+//! The text of log entries, stored compactly for histories of millions of commits: the names
+//! and emails of people once for all their commits, see [`Names`], and messages packed together
+//! in arenas shared by many entries, see [`Packed`]. Texts are stored as UTF-8, converted from
+//! the encoding of their commit, see [`decode`].
+
+use std::{
+    borrow::{Borrow, Cow},
+    collections::HashSet,
+    fmt,
+    ops::Deref,
+    ops::Range,
+    sync::Arc,
+};
+
+use encoding_rs::{Encoding, UTF_8};
+use gix::bstr::{BStr, ByteSlice};
+
+/// How the texts of a commit were made UTF-8, from the encoding its `encoding` header names,
+/// UTF-8 without one, as git assumes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Decoding {
+    #[default]
+    Utf8,
+    /// Converted from this encoding.
+    Converted(&'static Encoding),
+    /// Not valid in this encoding: what couldn't be read was replaced by U+FFFD.
+    Lossy(&'static Encoding),
+}
+
+impl Decoding {
+    /// The encoding named by `label`, the `encoding` header of a commit, if known, UTF-8
+    /// otherwise.
+    pub fn encoding(label: Option<&[u8]>) -> &'static Encoding {
+        label.and_then(Encoding::for_label).unwrap_or(UTF_8)
+    }
+}
+
+/// `text` in `encoding` as UTF-8, and whether some of it wasn't valid and was replaced.
+pub fn decode<'a>(text: &'a [u8], encoding: &'static Encoding) -> (Cow<'a, [u8]>, bool) {
+    if encoding == UTF_8 {
+        return match String::from_utf8_lossy(text) {
+            Cow::Borrowed(text) => (Cow::Borrowed(text.as_bytes()), false),
+            Cow::Owned(text) => (Cow::Owned(text.into_bytes()), true),
+        };
+    }
+    let (text, lossy) = encoding.decode_without_bom_handling(text);
+    let text = match text {
+        Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+        Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+    };
+    (text, lossy)
+}
+
+/// Text shared by every entry having it, like the name of an author.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Shared(Arc<[u8]>);
+
+impl Deref for Shared {
+    type Target = BStr;
+
+    fn deref(&self) -> &BStr {
+        self.0.as_bstr()
+    }
+}
+
+impl Borrow<[u8]> for Shared {
+    fn borrow(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Display for Shared {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.deref(), f)
+    }
+}
+
+impl fmt::Debug for Shared {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.deref(), f)
+    }
+}
+
+/// The names and emails seen so far, each stored once.
+#[derive(Default)]
+pub struct Names(HashSet<Shared>);
+
+impl Names {
+    /// `name` as already seen, or stored for the next times.
+    pub fn intern(&mut self, name: &[u8]) -> Shared {
+        if let Some(shared) = self.0.get(name) {
+            return shared.clone();
+        }
+        let shared = Shared(name.into());
+        self.0.insert(shared.clone());
+        shared
+    }
+}
+
+/// Text at a range of an arena, the texts of other entries being around it.
+#[derive(Clone, Default)]
+pub struct Packed {
+    arena: Arc<[u8]>,
+    start: usize,
+    end: usize,
+}
+
+impl Packed {
+    /// The text at `range` of `arena`, made of the texts appended to it before.
+    pub fn new(arena: &Arc<[u8]>, range: Range<usize>) -> Packed {
+        Packed {
+            arena: arena.clone(),
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+/// Pack `texts` again together in an arena of their own, for the arenas they were in to be freed
+/// with the texts no longer needed.
+pub fn repack<'a>(texts: impl IntoIterator<Item = &'a mut Packed>) {
+    let texts: Vec<_> = texts.into_iter().collect();
+    let mut arena = Vec::new();
+    let ranges: Vec<_> = texts
+        .iter()
+        .map(|text| {
+            let start = arena.len();
+            arena.extend_from_slice(text);
+            start..arena.len()
+        })
+        .collect();
+    let arena = Arc::from(arena);
+    for (text, range) in texts.into_iter().zip(ranges) {
+        *text = Packed::new(&arena, range);
+    }
+}
+
+impl From<&[u8]> for Packed {
+    /// `text` alone in its arena.
+    fn from(text: &[u8]) -> Packed {
+        Packed {
+            arena: text.into(),
+            start: 0,
+            end: text.len(),
+        }
+    }
+}
+
+impl From<String> for Packed {
+    fn from(text: String) -> Packed {
+        Packed::from(text.as_bytes())
+    }
+}
+
+impl Deref for Packed {
+    type Target = BStr;
+
+    fn deref(&self) -> &BStr {
+        self.arena[self.start..self.end].as_bstr()
+    }
+}
+
+impl fmt::Display for Packed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.deref(), f)
+    }
+}
+
+impl fmt::Debug for Packed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.deref(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_converts_from_the_commit_encoding() {
+        let latin1 = Decoding::encoding(Some("ISO-8859-1".as_bytes()));
+        assert_eq!(
+            decode(b"caf\xe9", latin1),
+            (Cow::Borrowed("café".as_bytes()), false)
+        );
+        assert_eq!(
+            Decoding::encoding(Some("no-such-encoding".as_bytes())),
+            UTF_8
+        );
+        assert_eq!(
+            decode(b"caf\xe9", UTF_8),
+            (Cow::Borrowed("caf\u{fffd}".as_bytes()), true)
+        );
+    }
+}
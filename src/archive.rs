@@ -0,0 +1,210 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read, Write},
+};
+
+use color_eyre::{Result, eyre::eyre};
+use gix::{
+    ObjectId,
+    bstr::{BStr, BString, ByteVec},
+    worktree::{archive, stream},
+};
+
+/// Kind of archive, like those of `git archive`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Tar,
+    #[value(name = "tar.gz", alias = "tgz")]
+    TarGz,
+    Zip,
+}
+
+impl Format {
+    /// Guessed from the extension of a file name.
+    pub fn from_path(path: &str) -> Option<Format> {
+        if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+            Some(Format::TarGz)
+        } else if path.ends_with(".tar") {
+            Some(Format::Tar)
+        } else if path.ends_with(".zip") {
+            Some(Format::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<Format> for archive::Format {
+    fn from(format: Format) -> archive::Format {
+        match format {
+            Format::Tar => archive::Format::Tar,
+            Format::TarGz => archive::Format::TarGz {
+                compression_level: None,
+            },
+            Format::Zip => archive::Format::Zip {
+                compression_level: None,
+            },
+        }
+    }
+}
+
+/// Write the tree of `commit` as an archive to `out`, its paths starting with `prefix`.
+///
+/// Submodules are archived from their checkouts with `submodules`, and are empty directories
+/// otherwise, as with `git archive`.
+pub fn write(
+    repo: &gix::Repository,
+    commit: ObjectId,
+    prefix: &str,
+    submodules: bool,
+    format: Format,
+    mut out: impl Write,
+) -> Result<()> {
+    let commit = repo.find_commit(commit)?;
+    let tree = commit.tree_id()?.detach();
+    let (mut stream, _) = repo.worktree_stream(tree)?;
+    if submodules {
+        add_submodules(repo, tree, "".into(), &mut stream)?;
+    }
+    let options = archive::Options {
+        format: format.into(),
+        tree_prefix: (!prefix.is_empty()).then(|| prefix.into()),
+        modification_time: commit.time()?.seconds,
+    };
+    match format {
+        Format::Tar | Format::TarGz => {
+            archive::write_stream(&mut stream, stream::Stream::next_entry, &mut out, options)?
+        }
+        Format::Zip => {
+            // the central directory is written by seeking back, so in memory first
+            let mut zip = Cursor::new(Vec::new());
+            archive::write_stream_seek(&mut stream, stream::Stream::next_entry, &mut zip, options)?;
+            out.write_all(zip.get_ref())?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Add the files of the submodules of `tree` to `stream` from their checkouts, their paths
+/// starting with `base`, then those of their own submodules.
+fn add_submodules(
+    repo: &gix::Repository,
+    tree: ObjectId,
+    base: &BStr,
+    stream: &mut stream::Stream,
+) -> Result<()> {
+    let gitlinks: Vec<_> = (repo.find_tree(tree)?.traverse().breadthfirst.files()?)
+        .into_iter()
+        .filter(|entry| entry.mode.is_commit())
+        .collect();
+    if gitlinks.is_empty() {
+        return Ok(());
+    }
+    let mut checkouts: HashMap<BString, gix::Submodule<'_>> = (repo.submodules()?)
+        .into_iter()
+        .flatten()
+        .filter_map(|sub| Some((sub.path().ok()?.into_owned(), sub)))
+        .collect();
+    for gitlink in gitlinks {
+        let sub_repo = checkouts
+            .remove(&gitlink.filepath)
+            .ok_or_else(|| eyre!("{}: not a known submodule", gitlink.filepath))?
+            .open()?
+            .ok_or_else(|| eyre!("{}: submodule not checked out", gitlink.filepath))?;
+        let mut path = BString::from(base);
+        path.push_str(&gitlink.filepath);
+        path.push_byte(b'/');
+        let sub_tree = sub_repo.find_commit(gitlink.oid)?.tree_id()?.detach();
+        let (mut entries, _) = sub_repo.worktree_stream(sub_tree)?;
+        while let Some(mut entry) = entries.next_entry()? {
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            let mut relative_path = path.clone();
+            relative_path.push_str(entry.relative_path());
+            stream.add_entry(stream::AdditionalEntry {
+                id: entry.id,
+                mode: entry.mode,
+                relative_path,
+                source: stream::entry::Source::Memory(data),
+            });
+        }
+        add_submodules(&sub_repo, sub_tree, path.as_ref(), stream)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gixl_core::fixture::Fixture;
+
+    use super::*;
+
+    const ALICE: (&str, &str) = ("Alice", "alice@example.com");
+
+    /// A commit with a file at the root and one in a directory.
+    fn project(dir: &std::path::Path) -> Result<(gix::Repository, ObjectId)> {
+        let mut fixture = Fixture::init(dir)?;
+        fixture.commit(ALICE, "Add README", &[("README", "gixl\n")])?;
+        let id = fixture.commit(ALICE, "Add the lexer", &[("src/lexer.rs", "fn lex() {}\n")])?;
+        Ok((fixture.root("").repo, id))
+    }
+
+    #[test]
+    fn tar_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (repo, id) = project(dir.path())?;
+        let mut out = Vec::new();
+        write(&repo, id, "gixl/", false, Format::Tar, &mut out)?;
+
+        let mut files = Vec::new();
+        for entry in tar::Archive::new(out.as_slice()).entries()? {
+            let mut entry = entry?;
+            if entry.header().entry_type().is_dir() {
+                continue;
+            }
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            // 2024-01-01 01:00, the time of the commit
+            assert_eq!(entry.header().mtime()?, 1_704_070_800);
+            files.push((path, content));
+        }
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                ("gixl/README".into(), "gixl\n".into()),
+                ("gixl/src/lexer.rs".into(), "fn lex() {}\n".into()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn zip_round_trip() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (repo, id) = project(dir.path())?;
+        let mut out = Vec::new();
+        write(&repo, id, "", false, Format::Zip, &mut out)?;
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(out))?;
+        let mut content = String::new();
+        zip.by_name("src/lexer.rs")?.read_to_string(&mut content)?;
+        assert_eq!(content, "fn lex() {}\n");
+        let mut names: Vec<_> = (zip.file_names())
+            .filter(|name| !name.ends_with('/'))
+            .collect();
+        names.sort();
+        assert_eq!(names, ["README", "src/lexer.rs"]);
+        Ok(())
+    }
+
+    #[test]
+    fn format_from_path() {
+        assert_eq!(Format::from_path("a.tgz"), Some(Format::TarGz));
+        assert_eq!(Format::from_path("a.tar.gz"), Some(Format::TarGz));
+        assert_eq!(Format::from_path("a.zip"), Some(Format::Zip));
+        assert_eq!(Format::from_path("a.txt"), None);
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+
+use gix::ObjectId;
+
+/// Bisection state, reimplemented on top of the walked commit graph rather than driving
+/// `git bisect`.
+///
+/// Marks only make sense within a single history, so the first mark pins the source
+/// (a repository or a submodule) and commits from other sources are ignored.
+#[derive(Debug, Default)]
+pub struct Bisect {
+    /// Label of the source being bisected.
+    pub source: Option<String>,
+    pub bad: Option<ObjectId>,
+    pub good: HashSet<ObjectId>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Both a bad and at least one good commit are needed.
+    NeedMarks,
+    /// Test `next`; about `remaining` revisions are left after it, roughly `steps` more steps.
+    Next {
+        next: ObjectId,
+        remaining: usize,
+        steps: u32,
+    },
+    /// The first bad commit was found.
+    Found(ObjectId),
+}
+
+impl Bisect {
+    pub fn mark(&mut self, source: &str, id: ObjectId, good: bool) -> bool {
+        if self.bad.is_none() && self.good.is_empty() {
+            self.source = Some(source.to_owned());
+        } else if self.source.as_deref() != Some(source) {
+            return false;
+        }
+        if good {
+            self.good.insert(id);
+        } else {
+            self.bad = Some(id);
+        }
+        true
+    }
+
+    /// Compute the next commit to test, given `(id, parents)` of every walked commit of the
+    /// bisected source.
+    pub fn step<'a>(&self, graph: impl IntoIterator<Item = (ObjectId, &'a [ObjectId])>) -> Step {
+        let Some(bad) = self.bad else {
+            return Step::NeedMarks;
+        };
+        if self.good.is_empty() {
+            return Step::NeedMarks;
+        }
+        let parents: HashMap<ObjectId, &[ObjectId]> = graph.into_iter().collect();
+        let ancestors = |tips: &mut dyn Iterator<Item = ObjectId>| {
+            let mut seen = HashSet::new();
+            let mut stack: Vec<_> = tips.collect();
+            while let Some(id) = stack.pop() {
+                if seen.insert(id)
+                    && let Some(p) = parents.get(&id)
+                {
+                    stack.extend(p.iter().copied());
+                }
+            }
+            seen
+        };
+
+        let excluded = ancestors(&mut self.good.iter().copied());
+        let candidates: HashSet<_> = ancestors(&mut std::iter::once(bad))
+            .difference(&excluded)
+            .copied()
+            .collect();
+        if candidates.len() <= 1 {
+            return Step::Found(bad);
+        }
+
+        // Count the candidates each one reaches, parents first like git: a commit with a single
+        // candidate parent reaches one more than it, only merges need a walk of their own.
+        let candidate_parents = |id: &ObjectId| {
+            parents
+                .get(id)
+                .into_iter()
+                .flat_map(|p| p.iter())
+                .filter(|&p| candidates.contains(p))
+        };
+        let mut order = Vec::with_capacity(candidates.len());
+        let mut visited = HashSet::new();
+        for &tip in &candidates {
+            let mut stack = vec![(tip, false)];
+            while let Some((id, done)) = stack.pop() {
+                if done {
+                    order.push(id);
+                } else if visited.insert(id) {
+                    stack.push((id, true));
+                    stack.extend(candidate_parents(&id).map(|&p| (p, false)));
+                }
+            }
+        }
+        let mut reach: HashMap<ObjectId, usize> = HashMap::with_capacity(order.len());
+        for &id in &order {
+            let mut within = candidate_parents(&id);
+            let count = match (within.next(), within.next()) {
+                (None, _) => 1,
+                (Some(p), None) => reach[p] + 1,
+                _ => ancestors(&mut std::iter::once(id))
+                    .intersection(&candidates)
+                    .count(),
+            };
+            reach.insert(id, count);
+        }
+
+        // Pick the candidate whose reachable candidate set splits the range most evenly.
+        let total = candidates.len();
+        let mut best = (0, bad);
+        for &id in &candidates {
+            if id == bad {
+                continue;
+            }
+            let reach = reach[&id];
+            let score = reach.min(total - reach);
+            if score > best.0 || (score == best.0 && id < best.1) {
+                best = (score, id);
+            }
+        }
+        let remaining = total / 2;
+        Step::Next {
+            next: best.1,
+            remaining,
+            steps: usize::BITS - remaining.leading_zeros(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u8) -> ObjectId {
+        ObjectId::from_bytes_or_panic(&[n; 20])
+    }
+
+    /// Commits 1 to `n`, each the parent of the next.
+    fn line(n: u8) -> Vec<(ObjectId, Vec<ObjectId>)> {
+        (1..=n)
+            .map(|i| (id(i), (i > 1).then(|| id(i - 1)).into_iter().collect()))
+            .collect()
+    }
+
+    fn step(bisect: &Bisect, graph: &[(ObjectId, Vec<ObjectId>)]) -> Step {
+        bisect.step(graph.iter().map(|(id, parents)| (*id, parents.as_slice())))
+    }
+
+    #[test]
+    fn needs_a_bad_and_a_good_commit() {
+        let mut bisect = Bisect::default();
+        assert_eq!(step(&bisect, &line(4)), Step::NeedMarks);
+        bisect.mark("repo", id(4), false);
+        assert_eq!(step(&bisect, &line(4)), Step::NeedMarks);
+    }
+
+    #[test]
+    fn marks_stay_in_one_source() {
+        let mut bisect = Bisect::default();
+        assert!(bisect.mark("repo", id(4), false));
+        assert!(!bisect.mark("lib", id(1), true));
+        assert!(bisect.mark("repo", id(1), true));
+    }
+
+    #[test]
+    fn halves_a_line() {
+        let mut bisect = Bisect::default();
+        bisect.mark("repo", id(8), false);
+        bisect.mark("repo", id(1), true);
+        let next = Step::Next {
+            next: id(4),
+            remaining: 3,
+            steps: 2,
+        };
+        assert_eq!(step(&bisect, &line(8)), next);
+        bisect.mark("repo", id(4), true);
+        bisect.mark("repo", id(6), true);
+        bisect.mark("repo", id(7), true);
+        assert_eq!(step(&bisect, &line(8)), Step::Found(id(8)));
+    }
+
+    #[test]
+    fn counts_both_sides_of_a_merge() {
+        // 1 - 2 - 3 - 5 - 6
+        //  \         /
+        //   ` - 4 - '
+        let graph = vec![
+            (id(6), vec![id(5)]),
+            (id(5), vec![id(3), id(4)]),
+            (id(4), vec![id(1)]),
+            (id(3), vec![id(2)]),
+            (id(2), vec![id(1)]),
+            (id(1), vec![]),
+        ];
+        let mut bisect = Bisect::default();
+        bisect.mark("repo", id(6), false);
+        bisect.mark("repo", id(1), true);
+        // 5 reaches 4 of the 5 candidates, 3 and 2 are on one side only
+        let next = Step::Next {
+            next: id(3),
+            remaining: 2,
+            steps: 2,
+        };
+        assert_eq!(step(&bisect, &graph), next);
+        bisect.mark("repo", id(3), true);
+        let next = Step::Next {
+            next: id(4),
+            remaining: 1,
+            steps: 1,
+        };
+        assert_eq!(step(&bisect, &graph), next);
+    }
+}
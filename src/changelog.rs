@@ -0,0 +1,172 @@
+//! What changed between two superproject commits, in the superprojects and in each submodule
+//! whose gitlink moved, with `--changelog`.
+
+use std::io::Write;
+
+use color_eyre::Result;
+use gix::ObjectId;
+use ratatui::{prelude::*, text::Line};
+
+use crate::{
+    config::Config,
+    log::Role,
+    output,
+    source::{Root, Source},
+    submodule,
+    view::Item,
+};
+
+/// The range of one repository.
+#[derive(Clone, Debug)]
+pub struct Delta {
+    /// The label of its source, see [`Source::label`].
+    pub label: String,
+    /// `None` for a submodule added in between.
+    pub old: Option<ObjectId>,
+    /// `None` for a submodule removed in between.
+    pub new: Option<ObjectId>,
+}
+
+/// The ranges of the roots and their submodules, from revision `from` to `to`.
+#[derive(Clone, Debug)]
+pub struct Changelog {
+    pub from: String,
+    pub to: String,
+    pub deltas: Vec<Delta>,
+}
+
+impl Changelog {
+    /// The ranges of each of `roots` from `from` to `to`, followed by those of their submodules
+    /// whose gitlinks differ, labelled like `submodules` for those still listed.
+    pub fn new(roots: &[Root], submodules: &[Source], from: &str, to: &str) -> Result<Changelog> {
+        let mut deltas = Vec::new();
+        for root in roots {
+            let commit = |rev| -> Result<ObjectId> {
+                Ok(root
+                    .repo
+                    .rev_parse_single(rev)?
+                    .object()?
+                    .peel_to_commit()?
+                    .id)
+            };
+            let (old, new) = (commit(from)?, commit(to)?);
+            deltas.push(Delta {
+                label: Source::Root(root).label(),
+                old: Some(old),
+                new: Some(new),
+            });
+            for bump in submodule::bumps_between(&root.repo, old, new)? {
+                let source = submodules.iter().find(|source| match source {
+                    Source::Submodule(r, submodule) => {
+                        std::ptr::eq(*r, root)
+                            && submodule.path().is_ok_and(|path| *path == bump.path)
+                    }
+                    Source::Root(_) => false,
+                });
+                let label = match source {
+                    Some(source) => source.label(),
+                    None if root.name.is_empty() => bump.path.to_string(),
+                    None => format!("{}/{}", root.name, bump.path),
+                };
+                deltas.push(Delta {
+                    label,
+                    old: Some(bump.old).filter(|id| !id.is_null()),
+                    new: Some(bump.new).filter(|id| !id.is_null()),
+                });
+            }
+        }
+        Ok(Changelog {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            deltas,
+        })
+    }
+
+    /// The entries of `delta`, among `entries`.
+    fn entries<'a, 'repo>(delta: &'a Delta, entries: &'a [Item<'repo>]) -> Vec<&'a Item<'repo>> {
+        entries
+            .iter()
+            .filter(|(_, source)| source.label() == delta.label)
+            .collect()
+    }
+
+    /// Print the report of `entries`, the commits listed, to `out`: a section of Markdown for
+    /// each repository, or a JSON object per line, with the times of `time`.
+    pub fn print(
+        &self,
+        entries: &[Item],
+        format: output::Format,
+        config: &Config,
+        time: Role,
+        mut out: impl Write,
+    ) -> Result<()> {
+        if format == output::Format::Markdown {
+            writeln!(out, "# Changes from {} to {}", self.from, self.to)?;
+        }
+        for delta in &self.deltas {
+            let commits = Changelog::entries(delta, entries);
+            match format {
+                output::Format::Markdown => {
+                    writeln!(out, "\n## {}\n", describe(delta, config.abbrev, true))?;
+                    for (entry, _) in commits {
+                        writeln!(out, "{}", output::markdown(entry, config, time))?;
+                    }
+                }
+                _ => {
+                    let id = |id: Option<ObjectId>| match id {
+                        Some(id) => format!("\"{id}\""),
+                        None => "null".to_owned(),
+                    };
+                    let commits: Vec<_> = commits
+                        .into_iter()
+                        .map(|(entry, source)| output::json(entry, source, config))
+                        .collect();
+                    writeln!(
+                        out,
+                        "{{\"source\":{},\"old\":{},\"new\":{},\"commits\":[{}]}}",
+                        output::json_string(&delta.label),
+                        id(delta.old),
+                        id(delta.new),
+                        commits.join(","),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The ranges, with the number of commits of each in `entries`, boundary commits aside, for
+    /// the changelog popup.
+    pub fn lines(&self, entries: &[Item], abbrev: usize) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from(format!("{} → {}", self.from, self.to)),
+            Line::default(),
+        ];
+        for delta in &self.deltas {
+            let commits = Changelog::entries(delta, entries);
+            let commits = commits.iter().filter(|(entry, _)| !entry.boundary).count();
+            let plural = if commits == 1 { "" } else { "s" };
+            lines.push(Line::from(vec![
+                Span::raw(describe(delta, abbrev, false)),
+                Span::styled(format!("  {commits} commit{plural}"), Style::new().gray()),
+            ]));
+        }
+        lines
+    }
+}
+
+/// `delta` as its label and range, ids as Markdown `code`.
+fn describe(delta: &Delta, abbrev: usize, code: bool) -> String {
+    let quote = if code { "`" } else { "" };
+    let label = match delta.label.as_str() {
+        "" => "superproject",
+        label => label,
+    };
+    let short = |id: ObjectId| format!("{quote}{}{quote}", id.to_hex_with_len(abbrev));
+    match (delta.old, delta.new) {
+        (Some(old), Some(new)) => format!("{label} {}..{}", short(old), short(new)),
+        (None, Some(new)) => format!("{label}, added at {}", short(new)),
+        (Some(old), None) => format!("{label}, removed from {}", short(old)),
+        (None, None) => label.to_owned(),
+    }
+}
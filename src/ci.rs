@@ -0,0 +1,330 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc::{Receiver, Sender, channel},
+    time::{Duration, Instant},
+};
+
+use gix::ObjectId;
+
+use crate::{
+    forge::{Forge, ForgeKind},
+    json::{self, Value},
+    source::Source,
+};
+
+/// Time before asking again for the status of a commit whose checks are pending.
+const POLL: Duration = Duration::from_secs(30);
+
+/// Outcome of the checks of a commit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Success,
+    Failure,
+    Pending,
+}
+
+impl Status {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Status::Success => "✓",
+            Status::Failure => "✗",
+            Status::Pending => "●",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Success => "success",
+            Status::Failure => "failure",
+            Status::Pending => "pending",
+        }
+    }
+
+    fn parse(label: &str) -> Option<Status> {
+        match label {
+            "success" => Some(Status::Success),
+            "failure" => Some(Status::Failure),
+            "pending" => Some(Status::Pending),
+            _ => None,
+        }
+    }
+}
+
+/// Combined status of the GitHub check runs of a commit.
+fn github_status(json: &Value) -> Option<Status> {
+    let runs = json.get("check_runs")?.as_array()?;
+    if runs.is_empty() {
+        return None;
+    }
+    let is = |run: &Value, key: &str, values: &[&str]| {
+        (run.get(key).and_then(Value::as_str)).is_some_and(|value| values.contains(&value))
+    };
+    let failures = [
+        "failure",
+        "timed_out",
+        "cancelled",
+        "action_required",
+        "startup_failure",
+    ];
+    Some(if runs.iter().any(|run| is(run, "conclusion", &failures)) {
+        Status::Failure
+    } else if runs.iter().any(|run| !is(run, "status", &["completed"])) {
+        Status::Pending
+    } else {
+        Status::Success
+    })
+}
+
+/// Status of the last GitLab pipeline of a commit.
+fn gitlab_status(json: &Value) -> Option<Status> {
+    Some(match json.get("last_pipeline")?.get("status")?.as_str()? {
+        "success" => Status::Success,
+        "failed" | "canceled" => Status::Failure,
+        "skipped" | "manual" => return None,
+        _ => Status::Pending,
+    })
+}
+
+/// `https://host/org/repo` split into host and path.
+fn host_path(forge: &Forge) -> Option<(&str, &str)> {
+    forge.base.strip_prefix("https://")?.split_once('/')
+}
+
+/// API endpoint for the checks of `id`, with the token read from the environment.
+fn endpoint(forge: &Forge, id: ObjectId) -> Option<(String, Option<String>)> {
+    let (host, path) = host_path(forge)?;
+    let token = |vars: &[&str]| vars.iter().find_map(|var| std::env::var(var).ok());
+    match forge.kind {
+        ForgeKind::GitHub => {
+            let api = match host {
+                "github.com" => "https://api.github.com".to_owned(),
+                host => format!("https://{host}/api/v3"),
+            };
+            Some((
+                format!("{api}/repos/{path}/commits/{id}/check-runs?per_page=100"),
+                token(&["GITHUB_TOKEN", "GH_TOKEN"]),
+            ))
+        }
+        ForgeKind::GitLab => Some((
+            format!(
+                "https://{host}/api/v4/projects/{}/repository/commits/{id}",
+                path.replace('/', "%2F")
+            ),
+            token(&["GITLAB_TOKEN"]),
+        )),
+        ForgeKind::Other => None,
+    }
+}
+
+/// Ask the forge for the status of `id` with `curl`, the token being passed on its standard
+/// input so that it doesn't show in the process list.
+fn fetch(forge: &Forge, id: ObjectId) -> Result<Option<Status>, String> {
+    let Some((url, token)) = endpoint(forge, id) else {
+        return Ok(None);
+    };
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--header", "@-", &url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("curl: {err}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let header = match (forge.kind, token) {
+            (ForgeKind::GitLab, Some(token)) => format!("PRIVATE-TOKEN: {token}\n"),
+            (_, Some(token)) => format!("Authorization: Bearer {token}\n"),
+            (_, None) => String::new(),
+        };
+        stdin
+            .write_all(header.as_bytes())
+            .map_err(|err| err.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|err| err.to_string())?;
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        return Err(err.trim().to_owned());
+    }
+    let json = json::parse(&String::from_utf8_lossy(&output.stdout))
+        .map_err(|err| format!("{url}: {err}"))?;
+    Ok(match forge.kind {
+        ForgeKind::GitLab => gitlab_status(&json),
+        _ => github_status(&json),
+    })
+}
+
+/// `$XDG_CACHE_HOME/gixl/ci`, defaulting to `~/.cache/gixl/ci`.
+fn cache_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CACHE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::home_dir()?.join(".cache"),
+    };
+    Some(dir.join("gixl/ci"))
+}
+
+/// Statuses which can't change anymore, from previous runs.
+fn load_cache() -> HashMap<ObjectId, Status> {
+    let Some(text) = cache_path().and_then(|path| std::fs::read_to_string(path).ok()) else {
+        return HashMap::new();
+    };
+    text.lines()
+        .filter_map(|line| {
+            let (id, status) = line.split_once(' ')?;
+            Some((id.parse().ok()?, Status::parse(status)?))
+        })
+        .collect()
+}
+
+fn append_cache(id: ObjectId, status: Status) -> std::io::Result<()> {
+    let Some(path) = cache_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::File::options()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{id} {}", status.label())
+}
+
+type Reply = (ObjectId, Result<Option<Status>, String>);
+
+/// Statuses fetched in the background, as commits get shown.
+pub struct Ci {
+    requests: Sender<(Forge, ObjectId)>,
+    replies: Receiver<Reply>,
+    requested: HashSet<ObjectId>,
+    /// When to ask again for the statuses of commits whose checks are pending.
+    polls: HashMap<ObjectId, Instant>,
+    /// Forge of each source by label, `None` when it has no known API.
+    forges: HashMap<String, Option<Forge>>,
+    /// Known statuses, `None` for commits without checks.
+    pub statuses: HashMap<ObjectId, Option<Status>>,
+}
+
+impl Ci {
+    pub fn new() -> Ci {
+        let (requests, worker_requests) = channel::<(Forge, ObjectId)>();
+        let (worker_replies, replies) = channel();
+        // one request at a time, to be gentle with rate limits
+        std::thread::spawn(move || {
+            for (forge, id) in worker_requests {
+                let reply = fetch(&forge, id);
+                if let Ok(Some(status @ (Status::Success | Status::Failure))) = reply {
+                    let _ = append_cache(id, status);
+                }
+                if worker_replies.send((id, reply)).is_err() {
+                    break;
+                }
+            }
+        });
+        let statuses: HashMap<_, _> = load_cache()
+            .into_iter()
+            .map(|(id, status)| (id, Some(status)))
+            .collect();
+        Ci {
+            requests,
+            replies,
+            requested: statuses.keys().copied().collect(),
+            polls: HashMap::new(),
+            forges: HashMap::new(),
+            statuses,
+        }
+    }
+
+    /// Fetch the status of `id`, a commit of `source`, unless it was already, or was pending
+    /// for long enough to ask again.
+    pub fn request(&mut self, source: &Source, id: ObjectId) {
+        if self.polls.get(&id).is_some_and(|at| *at <= Instant::now()) {
+            self.polls.remove(&id);
+            self.requested.remove(&id);
+            self.statuses.remove(&id);
+        }
+        if self.requested.contains(&id) {
+            return;
+        }
+        let forge = self.forges.entry(source.label()).or_insert_with(|| {
+            let repo = source.open().ok()?;
+            Forge::from_repo(&repo).filter(|forge| forge.kind != ForgeKind::Other)
+        });
+        if let Some(forge) = forge {
+            self.requested.insert(id);
+            let _ = self.requests.send((forge.clone(), id));
+        }
+    }
+
+    /// The statuses known so far, for commits having checks.
+    pub fn known(&self) -> HashMap<ObjectId, Status> {
+        self.statuses
+            .iter()
+            .filter_map(|(id, status)| Some((*id, (*status)?)))
+            .collect()
+    }
+
+    /// Whether replies are still expected, or pending statuses are to be asked again.
+    pub fn waiting(&self) -> bool {
+        self.requested.len() > self.statuses.len() || !self.polls.is_empty()
+    }
+
+    /// Replies received since the last call, keeping their statuses.
+    pub fn receive(&mut self) -> Vec<Reply> {
+        let replies: Vec<_> = self.replies.try_iter().collect();
+        for (id, reply) in &replies {
+            let status = reply.as_ref().ok().copied().flatten();
+            if status == Some(Status::Pending) {
+                self.polls.insert(*id, Instant::now() + POLL);
+            }
+            self.statuses.insert(*id, status);
+        }
+        replies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn github(runs: &[(&str, Option<&str>)]) -> Option<Status> {
+        let runs: Vec<_> = (runs.iter())
+            .map(|(status, conclusion)| {
+                Value::Object(vec![
+                    ("status".into(), Value::String((*status).into())),
+                    (
+                        "conclusion".into(),
+                        conclusion.map_or(Value::Null, |c| Value::String(c.into())),
+                    ),
+                ])
+            })
+            .collect();
+        let reply = Value::Object(vec![("check_runs".into(), Value::Array(runs))]);
+        github_status(&json::parse(&reply.to_json()).unwrap())
+    }
+
+    #[test]
+    fn github_checks() {
+        assert_eq!(github(&[]), None);
+        let success = ("completed", Some("success"));
+        assert_eq!(github(&[success, success]), Some(Status::Success));
+        assert_eq!(
+            github(&[success, ("in_progress", None)]),
+            Some(Status::Pending)
+        );
+        let failure = ("completed", Some("timed_out"));
+        assert_eq!(github(&[failure, ("queued", None)]), Some(Status::Failure));
+    }
+
+    #[test]
+    fn gitlab_pipelines() {
+        let status = |reply: &str| gitlab_status(&json::parse(reply).unwrap());
+        assert_eq!(status(r#"{"id": "1", "last_pipeline": null}"#), None);
+        let failed = r#"{"last_pipeline": {"id": 7, "status": "failed"}, "status": "success"}"#;
+        assert_eq!(status(failed), Some(Status::Failure));
+        let running = r#"{"last_pipeline": {"status": "running"}}"#;
+        assert_eq!(status(running), Some(Status::Pending));
+    }
+}
@@ -0,0 +1,344 @@
+use crossterm::event::KeyCode;
+
+/// Commands of the `:` command line which take arguments, with the completions of their first one.
+const WITH_ARGS: &[(&str, &[&str])] = &[
+    ("goto", &[]),
+    ("filter", &["author=", "committer=", "message=", "person="]),
+    (
+        "set",
+        &[
+            "date=relative",
+            "date=iso",
+            "date=short",
+            "date=rfc",
+            "date=default",
+            "time=author",
+            "time=committer",
+            "sort=author",
+            "sort=committer",
+        ],
+    ),
+    ("export", &["patches", "archive"]),
+    ("rebase", &[]),
+    ("tab", &[]),
+    ("revert", &["squash"]),
+    ("save", &[]),
+    ("delete-branch", &[]),
+    ("rename-branch", &[]),
+    ("delete-tag", &[]),
+    ("push-tag", &[]),
+    (
+        "remote",
+        &["add", "remove", "rename", "set-url", "set-push-url"],
+    ),
+    (
+        "send-email",
+        &["--to=", "--cc=", "--annotate", "--cover-letter"],
+    ),
+];
+
+/// Keys of the list bound to a built-in command, which `[commands]` of the configuration can't
+/// take over.
+pub const BOUND: &str = "qWNalfMs!=*m[]LDTOPyYEgbBcvpVeIdwZiRtHUSoxrACXuFjk:123456789";
+
+/// Commands standing for a key binding.
+pub const KEYS: &[(&str, KeyCode)] = &[
+    ("show", KeyCode::Enter),
+    ("diff-worktree", KeyCode::Char('W')),
+    ("permalink", KeyCode::Char('y')),
+    ("copy-patch", KeyCode::Char('Y')),
+    ("good", KeyCode::Char('g')),
+    ("bad", KeyCode::Char('b')),
+    ("bisect-reset", KeyCode::Char('B')),
+    ("compare", KeyCode::Char('c')),
+    ("range", KeyCode::Char('v')),
+    ("cherry-pick", KeyCode::Char('p')),
+    ("squash", KeyCode::Char('Z')),
+    ("reword", KeyCode::Char('w')),
+    ("drop", KeyCode::Char('d')),
+    ("rebase-interactive", KeyCode::Char('I')),
+    ("details", KeyCode::Char('i')),
+    ("files", KeyCode::Char('f')),
+    ("diff", KeyCode::Char('l')),
+    ("stage", KeyCode::Char('a')),
+    ("commit", KeyCode::Char('N')),
+    ("refs", KeyCode::Char('R')),
+    ("refs-panel", KeyCode::Char('L')),
+    ("group", KeyCode::Char('t')),
+    ("hide-submodules", KeyCode::Char('H')),
+    ("stats", KeyCode::Char('s')),
+    ("errors", KeyCode::Char('!')),
+    ("changelog", KeyCode::Char('=')),
+    ("duplicate", KeyCode::Char('*')),
+    ("minimap", KeyCode::Char('m')),
+    ("next-mark", KeyCode::Char(']')),
+    ("previous-mark", KeyCode::Char('[')),
+    ("update-submodules", KeyCode::Char('U')),
+    ("landing", KeyCode::Char('S')),
+    ("pull-request", KeyCode::Char('P')),
+    ("mine", KeyCode::Char('M')),
+    ("branches", KeyCode::Char('D')),
+    ("tags", KeyCode::Char('T')),
+    ("remotes", KeyCode::Char('O')),
+    ("open", KeyCode::Char('o')),
+    ("close", KeyCode::Char('x')),
+    ("switch", KeyCode::Char('r')),
+    ("fixup", KeyCode::Char('F')),
+    ("autosquash", KeyCode::Char('A')),
+    ("continue", KeyCode::Char('C')),
+    ("abort", KeyCode::Char('X')),
+    ("undo", KeyCode::Char('u')),
+    ("quit", KeyCode::Char('q')),
+];
+
+pub enum Command<'a> {
+    Goto(&'a str),
+    /// Filter on `author`, `committer`, `message` or `person` (author or trailers) with a regex.
+    Filter(&'a str, &'a str),
+    Set(&'a str, &'a str),
+    ExportPatches(Option<&'a str>),
+    /// Write the files of an entry to an archive, with those of the submodules when asked.
+    ExportArchive(Option<&'a str>, bool),
+    /// Preview a rebase of HEAD onto a revision.
+    Rebase(&'a str),
+    /// Open a tab with the history of a revision, like a branch or a tag.
+    Tab(&'a str),
+    /// Revert the selected range of entries, in one commit when squashed.
+    Revert(bool),
+    /// Write the patch of the selected entry, or what the prompt was opened for, to a file.
+    Save(&'a str),
+    /// Send the selected range of entries with `git send-email`, with its options and
+    /// recipients.
+    SendEmail(Vec<&'a str>),
+    /// Delete a local branch, named `repo:branch` in a named repository.
+    DeleteBranch(&'a str),
+    /// Rename a local branch, named as for [`Command::DeleteBranch`].
+    RenameBranch(&'a str, &'a str),
+    /// Delete a tag, named as for [`Command::DeleteBranch`].
+    DeleteTag(&'a str),
+    /// Push a tag to a remote, the default one for pushing if not given.
+    PushTag(&'a str, Option<&'a str>),
+    Remote(RemoteEdit<'a>),
+    Key(KeyCode),
+}
+
+/// A change to the remotes of a repository, remotes being named as branches are.
+pub enum RemoteEdit<'a> {
+    /// Add a remote with its URL, to the repository named as in `repo:name`.
+    Add(&'a str, &'a str),
+    Remove(&'a str),
+    Rename(&'a str, &'a str),
+    /// Set the URL of a remote, the one pushed to when asked.
+    SetUrl(&'a str, &'a str, bool),
+}
+
+pub fn parse(input: &str) -> Result<Command<'_>, String> {
+    let input = input.trim();
+    let (name, arg) = input.split_once(' ').unwrap_or((input, ""));
+    let arg = arg.trim();
+    let expected = |arg: &'static str| format!("{name}: expected {arg}");
+    match name {
+        "goto" if !arg.is_empty() => Ok(Command::Goto(arg)),
+        "goto" => Err(expected("a revision")),
+        "rebase" if !arg.is_empty() => Ok(Command::Rebase(arg)),
+        "rebase" => Err(expected("a revision")),
+        "tab" if !arg.is_empty() => Ok(Command::Tab(arg)),
+        "tab" => Err(expected("a revision")),
+        "send-email" => Ok(Command::SendEmail(arg.split_whitespace().collect())),
+        "save" if !arg.is_empty() => Ok(Command::Save(arg)),
+        "save" => Err(expected("a file")),
+        "delete-branch" if !arg.is_empty() => Ok(Command::DeleteBranch(arg)),
+        "delete-branch" => Err(expected("a branch")),
+        "rename-branch" => match arg.split_whitespace().collect::<Vec<_>>()[..] {
+            [old, new] => Ok(Command::RenameBranch(old, new)),
+            _ => Err(expected("a branch and its new name")),
+        },
+        "delete-tag" if !arg.is_empty() => Ok(Command::DeleteTag(arg)),
+        "delete-tag" => Err(expected("a tag")),
+        "push-tag" => match arg.split_whitespace().collect::<Vec<_>>()[..] {
+            [tag] => Ok(Command::PushTag(tag, None)),
+            [tag, remote] => Ok(Command::PushTag(tag, Some(remote))),
+            _ => Err(expected("a tag and a remote")),
+        },
+        "remote" => match arg.split_whitespace().collect::<Vec<_>>()[..] {
+            ["add", name, url] => Ok(Command::Remote(RemoteEdit::Add(name, url))),
+            ["remove", name] => Ok(Command::Remote(RemoteEdit::Remove(name))),
+            ["rename", old, new] => Ok(Command::Remote(RemoteEdit::Rename(old, new))),
+            ["set-url", name, url] => Ok(Command::Remote(RemoteEdit::SetUrl(name, url, false))),
+            ["set-push-url", name, url] => Ok(Command::Remote(RemoteEdit::SetUrl(name, url, true))),
+            _ => Err(expected(
+                "add NAME URL, remove NAME, rename OLD NEW, set-url NAME URL or set-push-url NAME URL",
+            )),
+        },
+        "revert" => match arg {
+            "" => Ok(Command::Revert(false)),
+            "squash" => Ok(Command::Revert(true)),
+            _ => Err(expected("squash or nothing")),
+        },
+        "filter" => match arg.split_once('=') {
+            Some((key @ ("author" | "committer" | "message" | "person"), value)) => {
+                Ok(Command::Filter(key, value))
+            }
+            _ => Err(expected(
+                "author=REGEX, committer=REGEX, message=REGEX or person=REGEX",
+            )),
+        },
+        "set" => match arg.split_once('=') {
+            Some((key, value)) => Ok(Command::Set(key, value)),
+            None => Err(expected("KEY=VALUE")),
+        },
+        "export" => match arg.split_once(' ').unwrap_or((arg, "")) {
+            ("patches", dir) => Ok(Command::ExportPatches(
+                Some(dir.trim()).filter(|d| !d.is_empty()),
+            )),
+            ("archive", rest) => {
+                let rest = rest.trim();
+                let (submodules, file) = match rest.strip_prefix("--recurse-submodules") {
+                    Some(file) => (true, file.trim()),
+                    None => (false, rest),
+                };
+                Ok(Command::ExportArchive(
+                    Some(file).filter(|f| !f.is_empty()),
+                    submodules,
+                ))
+            }
+            _ => Err(expected(
+                "patches [DIR] or archive [--recurse-submodules] [FILE]",
+            )),
+        },
+        _ => KEYS
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, code)| Command::Key(*code))
+            .ok_or_else(|| format!("unknown command: {name}")),
+    }
+}
+
+/// Completions of `input` as whole command lines.
+pub fn complete(input: &str) -> Vec<String> {
+    match input.split_once(' ') {
+        None => WITH_ARGS
+            .iter()
+            .map(|(name, _)| format!("{name} "))
+            .chain(KEYS.iter().map(|(name, _)| name.to_string()))
+            .filter(|name| name.starts_with(input))
+            .collect(),
+        Some((name, arg)) => WITH_ARGS
+            .iter()
+            .filter(|(n, _)| *n == name)
+            .flat_map(|(_, args)| args.iter())
+            .filter(|a| a.starts_with(arg))
+            .map(|a| format!("{name} {a}"))
+            .collect(),
+    }
+}
+
+/// Longest prefix shared by all `candidates`.
+pub fn common_prefix(candidates: &[String]) -> Option<&str> {
+    let first = candidates.first()?;
+    let len = candidates[1..]
+        .iter()
+        .map(|c| {
+            first
+                .chars()
+                .zip(c.chars())
+                .take_while(|(a, b)| a == b)
+                .count()
+        })
+        .min()
+        .unwrap_or(first.chars().count());
+    let end = first
+        .char_indices()
+        .nth(len)
+        .map_or(first.len(), |(i, _)| i);
+    Some(&first[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands_with_arguments() {
+        assert!(matches!(parse(" goto  v1.0 "), Ok(Command::Goto("v1.0"))));
+        assert!(matches!(
+            parse("filter message=fix: .*"),
+            Ok(Command::Filter("message", "fix: .*"))
+        ));
+        assert!(matches!(
+            parse("export patches"),
+            Ok(Command::ExportPatches(None))
+        ));
+        assert!(matches!(
+            parse("export patches out"),
+            Ok(Command::ExportPatches(Some("out")))
+        ));
+        assert!(matches!(parse("revert squash"), Ok(Command::Revert(true))));
+    }
+
+    #[test]
+    fn parse_key_commands() {
+        assert!(matches!(
+            parse("quit"),
+            Ok(Command::Key(KeyCode::Char('q')))
+        ));
+        assert!(matches!(parse("show"), Ok(Command::Key(KeyCode::Enter))));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            parse("goto").err().as_deref(),
+            Some("goto: expected a revision")
+        );
+        assert_eq!(
+            parse("filter date=today").err().as_deref(),
+            Some("filter: expected author=REGEX, committer=REGEX, message=REGEX or person=REGEX")
+        );
+        assert_eq!(
+            parse("frobnicate").err().as_deref(),
+            Some("unknown command: frobnicate")
+        );
+    }
+
+    #[test]
+    fn complete_names_then_arguments() {
+        assert_eq!(complete("ex"), ["export "]);
+        assert_eq!(
+            complete("re"),
+            [
+                "rebase ",
+                "revert ",
+                "rename-branch ",
+                "remote ",
+                "reword",
+                "rebase-interactive",
+                "refs",
+                "refs-panel",
+                "remotes"
+            ]
+        );
+        assert_eq!(
+            complete("set date=r"),
+            ["set date=relative", "set date=rfc"]
+        );
+        assert_eq!(complete("goto ma"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn common_prefix_of_candidates() {
+        let candidates = complete("g");
+        assert_eq!(common_prefix(&candidates), Some("g"));
+        assert_eq!(common_prefix(&complete("bisect")), Some("bisect-reset"));
+        assert_eq!(common_prefix(&[]), None);
+    }
+
+    #[test]
+    fn bound_keys_include_those_of_commands() {
+        for (name, code) in KEYS {
+            if let KeyCode::Char(c) = code {
+                assert!(BOUND.contains(*c), "{name}: {c} isn't in BOUND");
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use std::path::PathBuf;
+
+use color_eyre::{Result, eyre::eyre};
+use gix::bstr::ByteSlice;
+use ratatui::style::{Color, Style};
+
+use crate::{
+    command,
+    hooks::Hook,
+    lint::Lint,
+    log::{DateFormat, LogEntryInfo},
+    source::Source,
+    theme::{Background, Depth},
+    tui::StartView,
+};
+
+/// Settings read from `$XDG_CONFIG_HOME/gixl/config`, a small subset of TOML:
+///
+/// ```toml
+/// [general]
+/// read-only = true
+/// screen-reader = true
+/// view = "branches"
+///
+/// [commands]
+/// K = "firefox https://ci.example.com/commit/%H"
+///
+/// [hooks]
+/// on-select = "notify-send gixl"
+///
+/// [columns]
+/// ticket = '\b([A-Z]+-\d+)\b'
+/// ticket.color = "yellow"
+/// kind.script = 'if parents.len() > 1 { "merge" }'
+/// review.script-file = "review.rhai"
+///
+/// [lint]
+/// subject-length = 72
+/// imperative = true
+/// signed-off-by = true
+/// conventional = true
+///
+/// [release-notes]
+/// Security = '(?m)^Security:'
+///
+/// [theme]
+/// background = "light"
+/// colors = 256
+/// high-contrast = false
+///
+/// [theme.light]
+/// yellow = "#b08800"
+/// ```
+///
+/// Defaults come from the git configuration of the repository.
+///
+/// Columns show the first match of their regex in each commit message, or of its first group
+/// when it has one, or what their Rhai script makes of each commit, see [`crate::script`], its
+/// file being looked up next to the configuration. Release notes have a section for each regex,
+/// of the commits whose message it matches.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// External commands bound to keys, with placeholders for the selected entry.
+    pub commands: Vec<(char, String)>,
+    /// Scripts run in the background at some moments, given the entries concerned.
+    pub hooks: Vec<(Hook, String)>,
+    pub columns: Vec<Column>,
+    /// Checks of the commit messages, marking those failing them.
+    pub lint: Lint,
+    /// Sections of `gixl release-notes`, with the regex of the messages of their commits.
+    pub release_notes: Vec<(String, regex::bytes::Regex)>,
+    /// Default of `--read-only`.
+    pub read_only: bool,
+    /// Default of `--screen-reader`.
+    pub screen_reader: bool,
+    /// Default of `--view`.
+    pub view: StartView,
+    /// `log.date`
+    pub date: DateFormat,
+    /// Length of abbreviated commit ids, `core.abbrev`.
+    pub abbrev: usize,
+    /// `color.ui`
+    pub color: bool,
+    pub background: Background,
+    /// Reverse video instead of colors, implied by `screen-reader`.
+    pub high_contrast: bool,
+    /// Colors the terminal can display, guessed from the environment when unset.
+    pub depth: Option<Depth>,
+    /// Replacements of named colors on light and dark backgrounds.
+    pub light: Vec<(Color, Color)>,
+    pub dark: Vec<(Color, Color)>,
+}
+
+/// A column of text picked from commit messages, or computed from commits by a script.
+#[derive(Clone, Debug)]
+pub struct Column {
+    pub name: String,
+    pub picker: Picker,
+    pub color: Color,
+}
+
+/// How a column finds its text.
+#[derive(Clone, Debug)]
+pub enum Picker {
+    Regex(regex::bytes::Regex),
+    #[cfg(feature = "scripting")]
+    Script(std::sync::Arc<crate::script::Script>),
+}
+
+impl Column {
+    /// Text of the column for `entry` of `source`, with its style.
+    #[cfg_attr(not(feature = "scripting"), allow(unused_variables))]
+    pub fn cell(&self, entry: &LogEntryInfo, source: &Source) -> Option<(String, Style)> {
+        let style = Style::new().fg(self.color);
+        match &self.picker {
+            Picker::Regex(pattern) => {
+                let captures = pattern.captures(&entry.message)?;
+                let text = captures.get(1).or_else(|| captures.get(0))?;
+                Some((String::from_utf8_lossy(text.as_bytes()).into_owned(), style))
+            }
+            #[cfg(feature = "scripting")]
+            Picker::Script(script) => script.cell(entry, source, style),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            commands: Vec::new(),
+            hooks: Vec::new(),
+            columns: Vec::new(),
+            lint: Lint::default(),
+            release_notes: Vec::new(),
+            read_only: false,
+            screen_reader: false,
+            view: StartView::default(),
+            date: DateFormat::default(),
+            abbrev: 7,
+            color: true,
+            background: Background::default(),
+            high_contrast: false,
+            depth: None,
+            light: Vec::new(),
+            dark: Vec::new(),
+        }
+    }
+}
+
+/// Defaults matching the git setup of `repo`.
+pub fn from_git(repo: &gix::Repository) -> Config {
+    let mut config = Config::default();
+    let snapshot = repo.config_snapshot();
+    if let Some(date) = snapshot.string("log.date")
+        && let Some(date) = DateFormat::parse(&date.to_str_lossy())
+    {
+        config.date = date;
+    }
+    match snapshot
+        .string("core.abbrev")
+        .as_deref()
+        .map(|v| v.to_str_lossy())
+    {
+        Some(v) if v == "no" || v == "false" => config.abbrev = 40,
+        Some(v) if v != "auto" => {
+            config.abbrev = v.parse().map_or(7, |len: usize| len.clamp(4, 40));
+        }
+        _ => config.abbrev = auto_abbrev(repo),
+    }
+    if let Some(color) = snapshot.string("color.ui") {
+        config.color = !matches!(color.to_str_lossy().as_ref(), "never" | "false");
+    }
+    config
+}
+
+/// Length of abbreviated ids with `core.abbrev=auto`, the default: as git does, one more hex digit
+/// every time the number of objects in packs quadruples, at least 7.
+fn auto_abbrev(repo: &gix::Repository) -> usize {
+    let objects = repo.objects.packed_object_count().unwrap_or(0);
+    (u64::BITS - objects.leading_zeros()).div_ceil(2).max(7) as usize
+}
+
+/// `$XDG_CONFIG_HOME/gixl/config`, defaulting to `~/.config/gixl/config`.
+fn path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::home_dir()?.join(".config"),
+    };
+    Some(dir.join("gixl/config"))
+}
+
+/// `value` without its quotes, double or single as for TOML literal strings, escapes being left
+/// as they are.
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q)?.strip_suffix(*q))
+        .unwrap_or(value)
+}
+
+/// The configuration, or the defaults from the git configuration of `repo` when there is no
+/// configuration file.
+pub fn load(repo: &gix::Repository) -> Result<Config> {
+    let mut config = from_git(repo);
+    let Some(path) = path() else {
+        return Ok(config);
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(config),
+        Err(err) => return Err(err.into()),
+    };
+    let mut section = "";
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = name.trim();
+            continue;
+        }
+        let error = |what: &str| eyre!("{}:{}: {what}", path.display(), n + 1);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected KEY = VALUE"))?;
+        let (key, value) = (unquote(key.trim()), unquote(value.trim()));
+        match section {
+            "general" if key == "read-only" => {
+                config.read_only = value.parse().map_err(|_| error("expected true or false"))?;
+            }
+            "general" if key == "screen-reader" => {
+                config.screen_reader =
+                    value.parse().map_err(|_| error("expected true or false"))?;
+            }
+            "general" if key == "view" => {
+                config.view =
+                    StartView::parse(value).ok_or_else(|| error("expected log or branches"))?;
+            }
+            "theme" if key == "high-contrast" => {
+                config.high_contrast =
+                    value.parse().map_err(|_| error("expected true or false"))?;
+            }
+            "theme" if key == "background" => {
+                config.background = Background::parse(value)
+                    .ok_or_else(|| error("expected auto, light or dark"))?;
+            }
+            "theme" if key == "colors" => {
+                config.depth = Some(
+                    Depth::parse(value).ok_or_else(|| error("expected 16, 256 or truecolor"))?,
+                );
+            }
+            "theme.light" | "theme.dark" => {
+                let name = match key.parse() {
+                    Ok(Color::Rgb(..) | Color::Indexed(_) | Color::Reset) | Err(_) => {
+                        return Err(error("expected a color name"));
+                    }
+                    Ok(name) => name,
+                };
+                let color = value
+                    .parse()
+                    .map_err(|_| error("expected #RRGGBB, a color index or name"))?;
+                match section {
+                    "theme.light" => config.light.push((name, color)),
+                    _ => config.dark.push((name, color)),
+                }
+            }
+            "commands" => {
+                let mut chars = key.chars();
+                let (Some(key), None) = (chars.next(), chars.next()) else {
+                    return Err(error("command keys are single characters"));
+                };
+                if command::BOUND.contains(key) {
+                    return Err(error(&format!(
+                        "{key} is already bound to a built-in command"
+                    )));
+                }
+                config.commands.push((key, value.to_owned()));
+            }
+            "columns" => {
+                let (name, attribute) = key.split_once('.').unwrap_or((key, ""));
+                let picker = match attribute {
+                    "color" => {
+                        let column = config
+                            .columns
+                            .iter_mut()
+                            .find(|c| c.name == name)
+                            .ok_or_else(|| error("color of a column not defined above"))?;
+                        column.color = value
+                            .parse()
+                            .map_err(|_| error("expected #RRGGBB, a color index or name"))?;
+                        continue;
+                    }
+                    "" => Picker::Regex(
+                        regex::bytes::Regex::new(value)
+                            .map_err(|err| error(&format!("invalid regex: {err}")))?,
+                    ),
+                    #[cfg(feature = "scripting")]
+                    "script" | "script-file" => {
+                        let code = match attribute {
+                            "script" => value.to_owned(),
+                            _ => {
+                                let dir = path.parent().unwrap_or(&path);
+                                std::fs::read_to_string(dir.join(value))
+                                    .map_err(|err| error(&format!("{value}: {err}")))?
+                            }
+                        };
+                        let script = crate::script::Script::compile(&code)
+                            .map_err(|err| error(&format!("invalid script: {err}")))?;
+                        Picker::Script(std::sync::Arc::new(script))
+                    }
+                    #[cfg(not(feature = "scripting"))]
+                    "script" | "script-file" => {
+                        return Err(error("scripts need gixl built with the scripting feature"));
+                    }
+                    _ => return Err(error("unknown setting")),
+                };
+                config.columns.retain(|c| c.name != name);
+                config.columns.push(Column {
+                    name: name.to_owned(),
+                    picker,
+                    color: Color::Cyan,
+                });
+            }
+            "lint" if key == "subject-length" => {
+                config.lint.subject_length = Some(
+                    value
+                        .parse()
+                        .map_err(|_| error("expected a number of columns"))?,
+                );
+            }
+            "lint" => {
+                let enabled = value.parse().map_err(|_| error("expected true or false"))?;
+                match key {
+                    "imperative" => config.lint.imperative = enabled,
+                    "signed-off-by" => config.lint.signed_off_by = enabled,
+                    "conventional" => config.lint.conventional = enabled,
+                    _ => return Err(error("unknown setting")),
+                }
+            }
+            "release-notes" => {
+                let pattern = regex::bytes::Regex::new(value)
+                    .map_err(|err| error(&format!("invalid regex: {err}")))?;
+                config.release_notes.retain(|(name, _)| name != key);
+                config.release_notes.push((key.to_owned(), pattern));
+            }
+            "hooks" => {
+                let hook = Hook::parse(key)
+                    .ok_or_else(|| error("expected on-select, on-open or on-load-complete"))?;
+                config.hooks.retain(|(h, _)| *h != hook);
+                config.hooks.push((hook, value.to_owned()));
+            }
+            _ => return Err(error("unknown setting")),
+        }
+    }
+    Ok(config)
+}
@@ -0,0 +1,181 @@
+use ratatui::{prelude::*, widgets::*};
+
+/// Score of `text` for a fuzzy `query`: its characters must appear in order (ignoring case).
+/// Consecutive matches and matches at the start of words rank higher, spread ones lower.
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut chars = text.char_indices();
+    // byte offset just after the previous match
+    let mut end = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let (i, c) = chars.find(|(_, c)| c.to_lowercase().eq(q.to_lowercase()))?;
+        score += match end {
+            Some(end) if end == i => 8,
+            Some(end) => -((i - end) as i64).min(8),
+            None => -(i as i64).min(8),
+        };
+        if text[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|p| !p.is_alphanumeric())
+        {
+            score += 6;
+        }
+        end = Some(i + c.len_utf8());
+    }
+    Some(score)
+}
+
+/// A popup narrowing down `candidates` as a query is typed, with a preview of the highlighted one.
+pub struct Finder<T> {
+    pub title: String,
+    pub query: String,
+    candidates: Vec<(String, T)>,
+    /// Indices of the candidates matching the query, best first.
+    matches: Vec<usize>,
+    state: ListState,
+}
+
+impl<T> Finder<T> {
+    pub fn new(title: impl Into<String>, candidates: Vec<(String, T)>) -> Finder<T> {
+        let mut finder = Finder {
+            title: title.into(),
+            query: String::new(),
+            candidates,
+            matches: Vec::new(),
+            state: ListState::default(),
+        };
+        finder.update();
+        finder
+    }
+
+    fn update(&mut self) {
+        let mut scored: Vec<_> = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (text, _))| Some((score(&self.query, text)?, i)))
+            .collect();
+        // stable, so equal scores keep the candidates order
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.state.select((!self.matches.is_empty()).then_some(0));
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.update();
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.update();
+    }
+
+    pub fn next(&mut self) {
+        if let Some(i) = self.state.selected()
+            && i + 1 < self.matches.len()
+        {
+            self.state.select(Some(i + 1));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    /// The candidates matching the query, best first.
+    pub fn matching(&self) -> impl Iterator<Item = &T> {
+        self.matches.iter().map(|i| &self.candidates[*i].1)
+    }
+
+    pub fn selected(&self) -> Option<&T> {
+        let i = self.matches.get(self.state.selected()?)?;
+        Some(&self.candidates[*i].1)
+    }
+
+    pub fn render(&mut self, f: &mut Frame, preview: Vec<Line<'static>>) {
+        let area = f.area();
+        let width = (area.width * 4 / 5).max(20).min(area.width);
+        let height = (area.height * 4 / 5).max(5).min(area.height);
+        let area = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        f.render_widget(Clear, area);
+        let block = Block::bordered().title(format!(
+            " {} ({}/{}) ",
+            self.title,
+            self.matches.len(),
+            self.candidates.len()
+        ));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [input, results] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+        f.render_widget(
+            Line::from(vec![Span::raw(format!("> {}", self.query)), Span::raw("█")]),
+            input,
+        );
+        let [list, preview_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(results);
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|i| ListItem::new(self.candidates[*i].0.clone()))
+            .collect();
+        let list_widget = List::new(items)
+            .highlight_style(Style::new().bg(Color::LightGreen).bold())
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list_widget, list, &mut self.state);
+        let preview = Paragraph::new(preview)
+            .block(Block::new().borders(Borders::LEFT))
+            .wrap(Wrap { trim: false });
+        f.render_widget(preview, preview_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_needs_every_character_in_order() {
+        assert_eq!(score("xyz", "main"), None);
+        assert_eq!(score("nm", "main"), None);
+        assert!(score("MAIN", "main").is_some());
+        assert_eq!(score("", "main"), Some(0));
+    }
+
+    #[test]
+    fn score_ranks_consecutive_and_word_starts_higher() {
+        let score = |query| score(query, "origin/feature-branch").unwrap();
+        assert!(score("feat") > score("fatr"));
+        assert!(score("fb") > score("fu"));
+        assert!(score("ori") > score("rig"));
+    }
+
+    #[test]
+    fn finder_keeps_the_best_first() {
+        let candidates = ["fixup-branch", "main", "feature"].map(|name| (name.to_owned(), name));
+        let mut finder = Finder::new("branches", candidates.into());
+        for c in "fea".chars() {
+            finder.push(c);
+        }
+        assert_eq!(finder.selected(), Some(&"feature"));
+        // equal scores keep the order of the candidates
+        finder.pop();
+        finder.pop();
+        assert_eq!(finder.selected(), Some(&"fixup-branch"));
+        finder.next();
+        assert_eq!(finder.selected(), Some(&"feature"));
+        finder.next();
+        assert_eq!(finder.selected(), Some(&"feature"));
+    }
+}
@@ -0,0 +1,115 @@
+use std::process::{Command, Stdio};
+
+use gix::{bstr::ByteSlice, remote::Direction};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+    /// Unknown hosting, assumed to follow the GitHub URL layout.
+    Other,
+}
+
+/// A pull request, or a merge request of GitLab, of a forge of this kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Request {
+    pub kind: ForgeKind,
+    pub number: u64,
+}
+
+impl std::fmt::Display for Request {
+    /// How the request is referred to: `!n` for a GitLab merge request, `#n` otherwise.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.kind {
+            ForgeKind::GitLab => write!(f, "!{}", self.number),
+            ForgeKind::GitHub | ForgeKind::Other => write!(f, "#{}", self.number),
+        }
+    }
+}
+
+/// A web frontend for a repository, derived from its default remote URL.
+#[derive(Clone, Debug)]
+pub struct Forge {
+    pub kind: ForgeKind,
+    /// `https://host/org/repo`, without trailing slash or `.git` suffix.
+    pub base: String,
+}
+
+impl Forge {
+    pub fn from_repo(repo: &gix::Repository) -> Option<Forge> {
+        let remote = repo
+            .find_default_remote(Direction::Fetch)
+            .and_then(Result::ok)?;
+        Forge::from_url(remote.url(Direction::Fetch)?)
+    }
+
+    pub fn from_url(url: &gix::Url) -> Option<Forge> {
+        let host = url.host()?;
+        let path = url.path.to_str().ok()?;
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        if path.is_empty() {
+            return None;
+        }
+
+        let kind = if host.contains("github") {
+            ForgeKind::GitHub
+        } else if host.contains("gitlab") {
+            ForgeKind::GitLab
+        } else {
+            ForgeKind::Other
+        };
+        Some(Forge {
+            kind,
+            base: format!("https://{host}/{path}"),
+        })
+    }
+
+    fn sep(&self) -> &'static str {
+        match self.kind {
+            ForgeKind::GitLab => "/-",
+            ForgeKind::GitHub | ForgeKind::Other => "",
+        }
+    }
+
+    pub fn tree_url(&self, sha: &str) -> String {
+        format!("{}{}/tree/{sha}", self.base, self.sep())
+    }
+
+    pub fn request_url(&self, n: u64) -> String {
+        match self.kind {
+            ForgeKind::GitLab => format!("{}/-/merge_requests/{n}", self.base),
+            ForgeKind::GitHub | ForgeKind::Other => format!("{}/pull/{n}", self.base),
+        }
+    }
+
+    /// Permalink to `path` as of `sha`, optionally anchored at a 1-based `line`.
+    pub fn blob_url(&self, sha: &str, path: &str, line: Option<usize>) -> String {
+        let mut url = format!("{}{}/blob/{sha}/{path}", self.base, self.sep());
+        if let Some(line) = line {
+            url.push_str(&format!("#L{line}"));
+        }
+        url
+    }
+}
+
+/// Open `url` with the desktop's browser, without waiting for it.
+pub fn open_in_browser(url: &str) -> std::io::Result<()> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/c", "start", ""]);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = cmd
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
@@ -0,0 +1,150 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Child, Command, Stdio},
+};
+
+use color_eyre::Result;
+use gix::bstr::ByteSlice;
+
+use crate::{config::Config, log::LogEntryInfo, output, source::Source, view::Item};
+
+/// Moments at which user scripts can be run, configured in the `[hooks]` section.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hook {
+    /// Another entry was selected.
+    Select,
+    /// An entry was shown with Enter.
+    Open,
+    /// The log was loaded, at startup.
+    LoadComplete,
+}
+
+impl Hook {
+    pub fn parse(name: &str) -> Option<Hook> {
+        match name {
+            "on-select" => Some(Hook::Select),
+            "on-open" => Some(Hook::Open),
+            "on-load-complete" => Some(Hook::LoadComplete),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::Select => "on-select",
+            Hook::Open => "on-open",
+            Hook::LoadComplete => "on-load-complete",
+        }
+    }
+}
+
+/// Start `command`, split like a shell would, in `dir` with `env` and `input` on its standard
+/// input. It runs in the background, its output discarded not to garble the screen.
+fn spawn(
+    command: &str,
+    dir: &Path,
+    env: Vec<(&str, String)>,
+    input: String,
+) -> Result<Option<Child>> {
+    let args = shell_words::split(command)?;
+    let Some((program, args)) = args.split_first() else {
+        return Ok(None);
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(env)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let stdin = child.stdin.take();
+    // scripts are free not to read their input, which is closed once written
+    std::thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+    });
+    Ok(Some(child))
+}
+
+/// Wait for `script` on a thread of its own, when nothing is to be done with it once started.
+pub fn detach(script: Option<Child>) {
+    if let Some(mut script) = script {
+        std::thread::spawn(move || {
+            let _ = script.wait();
+        });
+    }
+}
+
+/// Run the `on-select` or `on-open` script with `entry`, as JSON on its standard input and in
+/// `GIXL_*` variables. It is returned while it runs, to be stopped or given to [`detach`].
+pub fn entry(
+    hook: Hook,
+    config: &Config,
+    entry: &LogEntryInfo,
+    source: &Source,
+) -> Result<Option<Child>> {
+    let Some((_, command)) = config.hooks.iter().find(|(h, _)| *h == hook) else {
+        return Ok(None);
+    };
+    let subject = entry.message.lines().next().unwrap_or_default();
+    let env = vec![
+        ("GIXL_HOOK", hook.name().to_owned()),
+        ("GIXL_COMMIT", entry.id.to_string()),
+        ("GIXL_AUTHOR", entry.author.to_string()),
+        ("GIXL_EMAIL", entry.email.to_string()),
+        ("GIXL_SUBJECT", subject.to_str_lossy().into_owned()),
+        ("GIXL_SOURCE", source.label()),
+    ];
+    let input = output::json(entry, source, config) + "\n";
+    spawn(command, &source.dir(), env, input)
+}
+
+/// Run the `on-load-complete` script with every entry of `items`, as JSON lines on its standard
+/// input, and their number in `GIXL_COUNT`.
+pub fn load_complete(config: &Config, items: &[Item], dir: &Path) -> Result<()> {
+    let Some((_, command)) = config.hooks.iter().find(|(h, _)| *h == Hook::LoadComplete) else {
+        return Ok(());
+    };
+    let env = vec![
+        ("GIXL_HOOK", Hook::LoadComplete.name().to_owned()),
+        ("GIXL_COUNT", items.len().to_string()),
+    ];
+    let input: String = items
+        .iter()
+        .map(|(entry, source)| output::json(entry, source, config) + "\n")
+        .collect();
+    spawn(command, dir, env, input).map(detach)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        for hook in [Hook::Select, Hook::Open, Hook::LoadComplete] {
+            assert_eq!(Hook::parse(hook.name()), Some(hook));
+        }
+        assert_eq!(Hook::parse("on-exit"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn scripts_get_their_input_and_environment() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let env = vec![("GIXL_HOOK", "on-open".to_owned())];
+        let command = r#"sh -c 'cat > input; echo "$GIXL_HOOK" > hook'"#;
+        let script = spawn(command, dir.path(), env, "{}\n".into())?;
+        script.expect("a script is started").wait()?;
+        assert_eq!(std::fs::read_to_string(dir.path().join("input"))?, "{}\n");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("hook"))?,
+            "on-open\n"
+        );
+        assert!(spawn("", dir.path(), Vec::new(), String::new())?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,241 @@
+use crate::output::json_string;
+
+/// A JSON document, enough for the requests of the `--listen` server and the replies of forges.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        match self {
+            Value::Null => "null".into(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => json_string(s),
+            Value::Array(values) => {
+                let values: Vec<_> = values.iter().map(Value::to_json).collect();
+                format!("[{}]", values.join(","))
+            }
+            Value::Object(members) => {
+                let members: Vec<_> = members
+                    .iter()
+                    .map(|(k, v)| format!("{}:{}", json_string(k), v.to_json()))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            }
+        }
+    }
+}
+
+/// How deep arrays and objects may nest, so that a hostile request can't overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, what: &str) -> Result<T, String> {
+        Err(format!("{what} at offset {}", self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.text[self.pos..].starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some('{' | '[') if self.depth == MAX_DEPTH => return self.error("nested too deeply"),
+            Some('{') => self.nested(Self::object)?,
+            Some('[') => self.nested(Self::array)?,
+            Some('"') => Value::String(self.string()?),
+            Some('-' | '0'..='9') => self.number()?,
+            _ if self.eat("null") => Value::Null,
+            _ if self.eat("true") => Value::Bool(true),
+            _ if self.eat("false") => Value::Bool(false),
+            _ => return self.error("expected a value"),
+        };
+        self.skip_whitespace();
+        Ok(value)
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, String>) -> Result<Value, String> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.eat("{");
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.eat("}") {
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return self.error("expected a key");
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if !self.eat(":") {
+                return self.error("expected ':'");
+            }
+            members.push((key, self.value()?));
+            if self.eat("}") {
+                return Ok(Value::Object(members));
+            }
+            if !self.eat(",") {
+                return self.error("expected ',' or '}'");
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.eat("[");
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.eat("]") {
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            if self.eat("]") {
+                return Ok(Value::Array(values));
+            }
+            if !self.eat(",") {
+                return self.error("expected ',' or ']'");
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.eat("\"");
+        let mut out = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('u') => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            let code = u32::from_str_radix(&hex, 16).ok();
+                            // surrogate pairs are rare enough in requests to be replaced
+                            code.and_then(char::from_u32).unwrap_or('\u{fffd}')
+                        }
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        _ => return self.error("invalid escape"),
+                    };
+                    out.push(escaped);
+                }
+                c => out.push(c),
+            }
+        }
+        self.error("unterminated string")
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let rest = &self.text[self.pos..];
+        let len = rest
+            .find(|c: char| !matches!(c, '-' | '+' | '.' | 'e' | 'E' | '0'..='9'))
+            .unwrap_or(rest.len());
+        match rest[..len].parse() {
+            Ok(n) => {
+                self.pos += len;
+                Ok(Value::Number(n))
+            }
+            Err(_) => self.error("invalid number"),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    if parser.pos < text.len() {
+        return parser.error("trailing characters");
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nesting_is_bounded() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert_eq!(
+            parse(&nested(MAX_DEPTH + 1)),
+            Err(format!("nested too deeply at offset {MAX_DEPTH}"))
+        );
+        assert!(parse(&"[".repeat(100_000)).is_err());
+    }
+}
@@ -0,0 +1,36 @@
+//! The terminal frontend of gixl, browsing the log of [`gixl_core`].
+
+pub mod archive;
+mod bisect;
+pub mod changelog;
+mod ci;
+mod clipboard;
+mod command;
+pub mod config;
+mod finder;
+mod forge;
+mod hooks;
+mod json;
+mod lint;
+pub mod notes;
+mod operation;
+pub mod output;
+mod pane;
+mod popup;
+mod pr;
+pub mod profile;
+mod rebase;
+mod refs;
+#[cfg(feature = "scripting")]
+mod script;
+pub mod server;
+pub mod session;
+mod sidebar;
+mod task;
+mod theme;
+mod todo;
+pub mod tui;
+mod view;
+mod width;
+
+use gixl_core::{diff, log, partial, replace, source, stage, submodule, text};
@@ -0,0 +1,103 @@
+//! Checks of commit messages, configured in the `[lint]` section, to audit incoming branches:
+//! the commits failing them are marked in the list.
+
+use gix::bstr::ByteSlice;
+
+use crate::{notes::Conventional, width};
+
+/// Words ending like a verb which isn't in the imperative mood, yet are.
+const IMPERATIVE: &[&str] = &[
+    "bring", "ping", "ring", "sing", "spring", "string", "swing", "wring",
+];
+
+/// The checks enabled.
+#[derive(Clone, Debug, Default)]
+pub struct Lint {
+    /// Longest subject allowed, in columns.
+    pub subject_length: Option<usize>,
+    /// Subjects starting with a verb in the imperative mood, like "Fix" rather than "Fixed" or
+    /// "Fixes", as far as its ending tells.
+    pub imperative: bool,
+    /// A `Signed-off-by` trailer.
+    pub signed_off_by: bool,
+    /// Subjects written as conventional commits, `type(scope): description`.
+    pub conventional: bool,
+}
+
+impl Lint {
+    pub fn enabled(&self) -> bool {
+        self.subject_length.is_some() || self.imperative || self.signed_off_by || self.conventional
+    }
+
+    /// What `message` fails, each told in a few words.
+    pub fn check(&self, message: &[u8]) -> Vec<String> {
+        let mut failures = Vec::new();
+        let subject = message.lines().next().unwrap_or_default().to_str_lossy();
+        if let Some(length) = self.subject_length
+            && width::width(&subject) > length
+        {
+            failures.push(format!("subject longer than {length}"));
+        }
+        let conventional = Conventional::parse(&subject);
+        if self.conventional && conventional.is_none() {
+            failures.push("not a conventional commit".into());
+        }
+        let description = conventional.map_or(&*subject, |c| c.description);
+        if self.imperative
+            && let Some(verb) = description.split_whitespace().next()
+            && !imperative(&verb.to_lowercase())
+        {
+            failures.push(format!("{verb:?} is not imperative"));
+        }
+        if self.signed_off_by
+            && !message.lines().any(|line| {
+                line.get(..14)
+                    .is_some_and(|token| token.eq_ignore_ascii_case(b"signed-off-by:"))
+            })
+        {
+            failures.push("no Signed-off-by".into());
+        }
+        failures
+    }
+}
+
+/// Whether `verb`, in lowercase, may be in the imperative mood: not ending like a past tense, a
+/// gerund or a third person.
+fn imperative(verb: &str) -> bool {
+    if IMPERATIVE.contains(&verb) {
+        return true;
+    }
+    let third_person = verb.ends_with('s')
+        && !["ss", "us", "is"]
+            .iter()
+            .any(|ending| verb.ends_with(ending));
+    let past = verb.ends_with("ed") && !verb.ends_with("eed");
+    !(past || verb.ends_with("ing") || third_person)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_of_a_message() {
+        let lint = Lint {
+            subject_length: Some(20),
+            imperative: true,
+            signed_off_by: true,
+            conventional: false,
+        };
+        let signed = b"Fix the walk\n\nSigned-off-by: A U Thor <a@example.com>\n";
+        assert!(lint.check(signed).is_empty());
+        let failures = lint.check(b"Fixed the walk of every submodule");
+        assert_eq!(
+            failures,
+            [
+                "subject longer than 20",
+                "\"Fixed\" is not imperative",
+                "no Signed-off-by"
+            ]
+        );
+        assert!(imperative("bring") && imperative("address") && !imperative("adds"));
+    }
+}
@@ -1,28 +1,369 @@
-mod tui;
-
-use std::path::{Path, PathBuf};
+use std::{
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
 
 use clap::{ArgAction, Parser};
-use color_eyre::Result;
-use gix::{date::time::format::ISO8601, revision::walk::Sorting};
+use color_eyre::{Result, eyre::WrapErr};
+use gixl::{
+    archive, changelog::Changelog, config, notes, output, profile::Profile, server, session, tui,
+};
+use gixl_core::{Problem, cache, log, partial::PartialClone, replace, source};
+use log::{Filter, Role, compare};
+use source::{Root, Source};
 #[allow(unused)]
 use tracing::debug;
-use tui::LogEntryInfo;
 
 #[derive(Debug, clap::Parser)]
 #[clap(name = "log", about = "git log example", version = option_env!("GIX_VERSION"))]
+#[clap(args_conflicts_with_subcommands = true)]
 struct Args {
-    /// Directory to use (git directory)
+    #[clap(subcommand)]
+    command: Option<Subcommand>,
+    /// Directories to use (git directories); their histories are merged
     #[clap(name = "dir")]
-    dir: Option<PathBuf>,
+    dirs: Vec<PathBuf>,
+    /// Run as if started in DIR, like `git -C`; several are applied in order.
+    #[clap(short = 'C', value_name = "DIR")]
+    chdir: Vec<PathBuf>,
+    /// Also list every git repository found below this directory.
+    #[clap(long, value_name = "DIR")]
+    workspace: Option<PathBuf>,
     /// Reverse the commit sort order.
     #[clap(short, long)]
     reverse: bool,
+    /// Sort commits by author or committer time, the one shown in the date column.
+    #[clap(long, value_name = "author|committer", value_parser = parse_role, default_value = "author")]
+    sort: Role,
+    /// Leave out the first N commits, like `git log --skip`, to page through history with
+    /// --max-count.
+    #[clap(long, value_name = "N", default_value_t = 0)]
+    skip: usize,
+    /// Show at most N commits, like `git log -n`. Sorted by committer time and not reversed,
+    /// only the commits needed are read.
+    #[clap(short = 'n', long, value_name = "N")]
+    max_count: Option<usize>,
+    /// Only show commits reachable from either A or B but not both, tagged by side.
+    #[clap(long, num_args = 2, value_names = ["A", "B"])]
+    compare: Option<Vec<String>>,
+    /// Report what changed from A to B, superproject revisions: the commits in between, with
+    /// those of each submodule between the commits recorded at A and B, grouped by repository
+    /// with --format md or json, and summed up in a popup when browsing, shown again with `=`.
+    #[clap(long, num_args = 2, value_names = ["A", "B"], conflicts_with_all = ["compare", "tip", "not"])]
+    changelog: Option<Vec<String>>,
+    /// Show exactly the commits REV, without their ancestors, like `git log --no-walk`, to look at
+    /// what other tools list: `-` reads them from standard input, like the output of
+    /// `git rev-list` or `git bisect log`. May be repeated.
+    #[clap(long, value_name = "REV", conflicts_with_all = ["compare", "changelog", "tip", "not"])]
+    no_walk: Vec<String>,
+    /// Read revisions from standard input as they come, one per line like the output of
+    /// `git rev-list --grep=CVE`, and show exactly their commits, the list starting with the
+    /// first found. With --format, each is printed as soon as it is read, unsorted.
+    #[clap(long, conflicts_with_all = ["compare", "changelog", "tip", "not", "no_walk", "listen"])]
+    stdin: bool,
+    /// Only show commits reachable from REV instead of HEAD, like `git log REV`, not those
+    /// containing it as `git branch --contains` does; in submodules, from the commit the
+    /// superproject records at REV.
+    #[clap(long, value_name = "REV", conflicts_with = "compare")]
+    tip: Option<String>,
+    /// Leave out commits reachable from REV, like "what's in next but not in main" with
+    /// `--tip next --not main`; in submodules, from the commit the superproject records at
+    /// REV. May be repeated.
+    #[clap(long, value_name = "REV", conflicts_with = "compare")]
+    not: Vec<String>,
+    /// Leave out commits reachable from REV of the repository labelled SOURCE only, as in the
+    /// list, an empty label standing for the superproject: REV is one of its own, or else the
+    /// commit the superproject records at REV, like the changes of each component since
+    /// different releases. May be repeated.
+    #[clap(long, value_name = "SOURCE=REV", value_parser = parse_until, conflicts_with = "compare")]
+    until_ref: Vec<(String, String)>,
+    /// Also list the commits where a range given with --not, --until-ref or --changelog stops,
+    /// left out of it but parents of its commits, marked `-`, like `git log --boundary`.
+    #[clap(long, conflicts_with_all = ["compare", "no_walk", "stdin"])]
+    boundary: bool,
+    /// Show the nearest preceding tag of the selected commit, like `git describe --tags`.
+    #[clap(long)]
+    describe: bool,
+    /// Only show commits adding or removing the given blob or tree id, in any source.
+    #[clap(long, value_name = "OID")]
+    find_object: Option<gix::ObjectId>,
+    /// Only show commits changing a function, given as `:funcname:file` (superproject only).
+    #[clap(short = 'L', value_name = ":FUNCNAME:FILE", value_parser = parse_function)]
+    function: Option<(String, String)>,
+    /// Only show commits authored with the configured `user.email`, in every repository.
+    #[clap(long)]
+    mine: bool,
+    /// Only show commits whose author or a person of their Co-authored-by, Signed-off-by,
+    /// Acked-by, Reviewed-by or Tested-by trailers matches REGEX, like `Name <email>`.
+    #[clap(long, value_name = "REGEX")]
+    person: Option<regex::bytes::Regex>,
+    /// Only show commits whose committer matches REGEX, like `Name <email>`.
+    #[clap(long, value_name = "REGEX")]
+    committer: Option<regex::bytes::Regex>,
+    /// In a partial clone, first fetch the objects left out of the history, only those of the
+    /// file of -L when given: otherwise commits -L and --find-object can't check are left out,
+    /// and changes which weren't fetched are marked as such.
+    #[clap(long)]
+    fetch_missing: bool,
+    /// Show commits and files as they were written, ignoring their replacements made with
+    /// `git replace`, grafts included, like `git --no-replace-objects`.
+    #[clap(long)]
+    no_replace_objects: bool,
+    /// Keep up to SIZE of decoded objects in memory for each repository, like `512k`, `64m` or
+    /// `1g`, `0` disabling the cache, instead of an amount sized after its files, from 4 MiB to
+    /// 256 MiB, or `gitoxide.objects.cacheLimit`. The delta bases of packs follow
+    /// `core.deltaBaseCacheLimit`, the last 64 being kept by default.
+    #[clap(long, value_name = "SIZE", value_parser = parse_size)]
+    object_cache: Option<usize>,
+    /// Report on standard error how long each step of starting took: discovering the
+    /// repositories, walking each of them and their submodules, sorting, and drawing the list
+    /// first, or printing it.
+    #[clap(long)]
+    profile: bool,
+    /// Print the commit selected with Enter and exit, to pick commits from scripts.
+    #[clap(long)]
+    pick: bool,
+    /// What `--pick` prints, with placeholders %H, %h, %an, %s and %D (repository directory).
+    #[clap(long, value_name = "TEMPLATE", requires = "pick", default_value = "%H")]
+    pick_format: String,
+    /// Disable every action changing references or checkouts, to use gixl purely as a viewer.
+    /// The commands of the configuration still run, as they are.
+    #[clap(long)]
+    read_only: bool,
+    /// Describe the selected commit on the status line, under the cursor, and use reverse video
+    /// instead of colors.
+    #[clap(long)]
+    screen_reader: bool,
+    /// Draw with ASCII only, for terminals and fonts lacking box drawing and other symbols.
+    #[clap(long)]
+    ascii: bool,
+    /// Show the status of the checks of commits, fetched from the GitHub or GitLab API with
+    /// `curl`, authenticated with $GITHUB_TOKEN or $GITLAB_TOKEN when set.
+    #[clap(long)]
+    ci: bool,
+    /// Show the pull or merge request which introduced each commit, found from merge commits.
+    #[clap(long)]
+    prs: bool,
+    /// Don't restore the selection and filters saved when these repositories were last listed.
+    #[clap(long)]
+    fresh: bool,
+    /// Draw in N lines below the prompt instead of taking over the whole screen.
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u16).range(3..))]
+    inline: Option<u16>,
+    /// Answer JSON-RPC requests about the log on the Unix socket SOCKET instead of browsing it,
+    /// for editors: `log`, `resolve` and `diff`, one request per line.
+    #[clap(long, value_name = "SOCKET", conflicts_with_all = ["pick", "inline", "format"])]
+    listen: Option<PathBuf>,
+    /// What to show first, instead of the log.
+    #[clap(long, value_enum)]
+    view: Option<tui::StartView>,
+    /// Print the commits instead of browsing them; plain is the default when stdout is not a
+    /// terminal.
+    #[clap(long, value_enum, conflicts_with_all = ["pick", "inline"])]
+    format: Option<output::Format>,
     /// Whether to include submodules (default to true)
     #[clap(default_value_t = true, long = "no-submodules", action = ArgAction::SetFalse)]
     submodules: bool,
 }
 
+#[derive(Debug, clap::Subcommand)]
+enum Subcommand {
+    /// Write a snapshot of the files of a commit, like `git archive`.
+    Archive {
+        /// Commit to archive.
+        rev: String,
+        /// File to write, its extension giving the format; standard output by default.
+        #[clap(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Format of the archive, tar by default unless given by the output file.
+        #[clap(long, value_enum)]
+        format: Option<archive::Format>,
+        /// Prepend PREFIX to every path of the archive.
+        #[clap(long, default_value = "")]
+        prefix: String,
+        /// Include the files of the checked out submodules instead of empty directories.
+        #[clap(long)]
+        recurse_submodules: bool,
+    },
+    /// Print the release notes of the commits of a range as Markdown, those of the submodules
+    /// bumped in it included, grouped by conventional commit type or by the sections of the
+    /// configuration.
+    ReleaseNotes {
+        /// Commits reachable from TO but not FROM, TO being HEAD when left out.
+        #[clap(value_name = "FROM..TO", value_parser = parse_range)]
+        range: (String, String),
+    },
+}
+
+/// `gixl archive`, in the repository of the current directory.
+fn archive(
+    rev: &str,
+    output: Option<&Path>,
+    format: Option<archive::Format>,
+    prefix: &str,
+    submodules: bool,
+) -> Result<()> {
+    let mut repo =
+        gix::ThreadSafeRepository::discover_with_environment_overrides(".")?.to_thread_local();
+    replace::apply(&mut repo)?;
+    cache::apply(&mut repo);
+    let commit = repo.rev_parse_single(rev)?.object()?.peel_to_commit()?.id;
+    let format = format
+        .or_else(|| archive::Format::from_path(&output?.to_string_lossy()))
+        .unwrap_or(archive::Format::Tar);
+    match output {
+        Some(path) => {
+            let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            archive::write(&repo, commit, prefix, submodules, format, file)
+        }
+        None if std::io::stdout().is_terminal() => {
+            color_eyre::eyre::bail!("not writing an archive to a terminal, use --output")
+        }
+        None => archive::write(
+            &repo,
+            commit,
+            prefix,
+            submodules,
+            format,
+            std::io::stdout().lock(),
+        ),
+    }
+}
+
+fn parse_role(arg: &str) -> std::result::Result<Role, String> {
+    Role::parse(arg).ok_or_else(|| "expected author or committer".into())
+}
+
+fn parse_size(arg: &str) -> std::result::Result<usize, String> {
+    let number = arg.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = match arg[number.len()..].to_ascii_lowercase().as_str() {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        _ => return Err("expected a size like 512k, 64m or 1g".into()),
+    };
+    number
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| number.checked_mul(unit))
+        .ok_or_else(|| "expected a size like 512k, 64m or 1g".into())
+}
+
+/// The revisions of `--no-walk`, those of `-` read from standard input, see
+/// [`gixl_core::revisions`].
+fn no_walk_revs(args: &[String]) -> Result<Vec<String>> {
+    let mut revs = Vec::new();
+    for arg in args {
+        if arg != "-" {
+            revs.push(arg.clone());
+            continue;
+        }
+        for line in std::io::stdin().lines() {
+            revs.extend(gixl_core::revisions(&line?));
+        }
+    }
+    Ok(revs)
+}
+
+fn parse_range(arg: &str) -> std::result::Result<(String, String), String> {
+    arg.split_once("..")
+        .filter(|(from, to)| !from.is_empty() && !to.starts_with('.'))
+        .map(|(from, to)| {
+            (
+                from.to_owned(),
+                if to.is_empty() { "HEAD" } else { to }.to_owned(),
+            )
+        })
+        .ok_or_else(|| "expected FROM..TO".to_owned())
+}
+
+fn parse_until(arg: &str) -> std::result::Result<(String, String), String> {
+    arg.split_once('=')
+        .filter(|(_, rev)| !rev.is_empty())
+        .map(|(label, rev)| (label.to_owned(), rev.to_owned()))
+        .ok_or_else(|| "expected SOURCE=REV".to_owned())
+}
+
+fn parse_function(arg: &str) -> std::result::Result<(String, String), String> {
+    arg.strip_prefix(':')
+        .and_then(|rest| rest.split_once(':'))
+        .filter(|(name, path)| !name.is_empty() && !path.is_empty())
+        .map(|(name, path)| (name.to_owned(), path.to_owned()))
+        .ok_or_else(|| "expected :funcname:file".to_owned())
+}
+
+/// `gixl release-notes`, in the repository of the current directory and its submodules.
+fn release_notes(from: &str, to: &str) -> Result<()> {
+    let mut repo =
+        gix::ThreadSafeRepository::discover_with_environment_overrides(".")?.to_thread_local();
+    replace::apply(&mut repo)?;
+    cache::apply(&mut repo);
+    for rev in [from, to] {
+        repo.rev_parse_single(rev)
+            .wrap_err_with(|| rev.to_owned())?;
+    }
+    let roots = [Root {
+        name: String::new(),
+        repo,
+        dir: PathBuf::from("."),
+    }];
+    // in submodules, the commits between those the superproject records at both ends
+    let filter = Filter {
+        tip: Some(to.to_owned()),
+        not: vec![from.to_owned()],
+        ..Filter::default()
+    };
+    let mut problems = Vec::new();
+    let submodules = gixl_core::submodules(&roots, &mut problems);
+    let submodules = gixl_core::sources(&submodules);
+    let mut entries = gixl_core::collect(
+        &roots,
+        &submodules,
+        &filter,
+        false,
+        None,
+        &mut |_, _| true,
+        &mut problems,
+    );
+    log::sort(&mut entries, false, Role::Author);
+    let config = match config::load(&roots[0].repo) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("warning: {err}");
+            config::from_git(&roots[0].repo)
+        }
+    };
+    for problem in &problems {
+        eprintln!("warning: {problem}");
+    }
+    notes::write(&entries, &config, from, to, std::io::stdout().lock())
+}
+
+/// Working trees and bare repositories below `dir`, not descending into repositories or hidden
+/// directories.
+fn find_repositories(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        if dir.join(".git").exists() || gix::discover::is_git(&dir).is_ok() {
+            found.push(dir);
+            continue;
+        }
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !hidden && entry.file_type().is_ok_and(|t| t.is_dir()) {
+                stack.push(entry.path());
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
     tracing_subscriber::fmt::init();
@@ -32,68 +373,360 @@ fn main() -> Result<()> {
 }
 
 fn run(args: Args) -> Result<()> {
-    let mut entries = Vec::new();
-    let git_dir = args.dir.as_deref().unwrap_or(Path::new("."));
-    let repo = gix::discover(git_dir)?;
-
-    let submodules;
-    if args.submodules
-        && let Some(sub) = repo.submodules()?
+    let mut profile = args.profile.then(|| Profile::new("discover repositories"));
+    if args.no_replace_objects {
+        // SAFETY: no other thread runs yet; git commands run later inherit it, like with git
+        unsafe { std::env::set_var("GIT_NO_REPLACE_OBJECTS", "1") };
+    }
+    if let Some(bytes) = args.object_cache {
+        // SAFETY: as above; gix reads it as `gitoxide.objects.cacheLimit`, git ignores it
+        unsafe { std::env::set_var("GIX_OBJECT_CACHE_MEMORY", bytes.to_string()) };
+    }
+    for dir in &args.chdir {
+        std::env::set_current_dir(dir)
+            .map_err(|err| color_eyre::eyre::eyre!("cannot change to {}: {err}", dir.display()))?;
+    }
+    if let Some(Subcommand::Archive {
+        rev,
+        output,
+        format,
+        prefix,
+        recurse_submodules,
+    }) = &args.command
     {
-        submodules = sub.collect::<Vec<_>>();
-        for submodule in &submodules {
-            if let Some(repo) = submodule.open()? {
-                let log_iter = get_log_iter(&repo, "HEAD")?;
-                for entry in log_iter {
-                    entries.push((entry?, Some(submodule)));
+        return archive(rev, output.as_deref(), *format, prefix, *recurse_submodules);
+    }
+    if let Some(Subcommand::ReleaseNotes { range: (from, to) }) = &args.command {
+        return release_notes(from, to);
+    }
+    let mut dirs: Vec<(PathBuf, Option<String>)> =
+        args.dirs.iter().map(|dir| (dir.clone(), None)).collect();
+    if let Some(workspace) = &args.workspace {
+        for dir in find_repositories(workspace)? {
+            let name = dir.strip_prefix(workspace).unwrap_or(&dir);
+            let name = name.to_string_lossy().into_owned();
+            dirs.push((dir, Some(name)));
+        }
+    } else if dirs.is_empty() {
+        dirs.push((PathBuf::from("."), None));
+    }
+    let multiple = dirs.len() > 1 || args.workspace.is_some();
+    let roots = dirs
+        .into_iter()
+        .map(|(dir, name)| {
+            // GIT_DIR and GIT_WORK_TREE take precedence over discovery, like for git
+            let mut repo = gix::ThreadSafeRepository::discover_with_environment_overrides(&dir)?
+                .to_thread_local();
+            replace::apply(&mut repo)?;
+            cache::apply(&mut repo);
+            let name = match name {
+                Some(name) => name,
+                None if multiple => {
+                    let path = repo.workdir().unwrap_or(repo.git_dir());
+                    path.file_name()
+                        .unwrap_or(path.as_os_str())
+                        .to_string_lossy()
+                        .into_owned()
                 }
+                None => String::new(),
+            };
+            Ok(Root { name, repo, dir })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if roots.is_empty() {
+        color_eyre::eyre::bail!("no repository found");
+    }
+    let format = args.format.or_else(|| {
+        // a picker draws on stderr, its output being captured
+        (!args.pick && !std::io::stdout().is_terminal()).then_some(output::Format::Plain)
+    });
+    let browse = args.listen.is_none() && format.is_none();
+    let session_path = session::path(&roots);
+    // printed lists don't depend on what was last browsed
+    let mut restore = match &session_path {
+        Some(path) if browse && !args.fresh => session::load(path),
+        _ => session::Session::default(),
+    };
+    let mut filter = Filter {
+        find_object: args.find_object,
+        function: args.function.clone(),
+        mine: args.mine,
+        person: args.person.clone(),
+        committer: args.committer.clone(),
+        tip: args.tip.clone(),
+        not: args.not.clone(),
+        until: args.until_ref.clone(),
+        boundary: args.boundary,
+    };
+    if let Some(revs) = &args.changelog {
+        filter.tip = Some(revs[1].clone());
+        filter.not = vec![revs[0].clone()];
+    }
+    // saved filters only apply when none is given on the command line
+    if filter.find_object.is_none() && filter.function.is_none() {
+        filter.find_object = restore.find_object.take();
+        filter.function = restore.function.take();
+    }
+    // unknown revisions are a mistake, not a problem of a submodule they are also looked up in,
+    // nor of the roots without them, listed as such
+    let revs = args.tip.iter().map(|rev| ("--tip", rev));
+    for (flag, rev) in revs.chain(args.not.iter().map(|rev| ("--not", rev))) {
+        let mut errors = (roots.iter())
+            .map(|root| root.repo.rev_parse_single(rev.as_str()).err())
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default();
+        if !errors.is_empty() {
+            return Err(errors.remove(0)).wrap_err_with(|| format!("{flag} {rev}"));
+        }
+    }
+    if args.fetch_missing {
+        let revs: Vec<String> = match &args.compare {
+            Some(revs) => revs.clone(),
+            None => std::iter::once(filter.tip.clone().unwrap_or_else(|| "HEAD".into()))
+                .chain(filter.not.iter().map(|rev| format!("^{rev}")))
+                .collect(),
+        };
+        let revs: Vec<&str> = revs.iter().map(String::as_str).collect();
+        let paths: Vec<&str> = match &filter {
+            Filter {
+                function: Some((_, path)),
+                find_object: None,
+                ..
+            } => vec![path.as_str()],
+            _ => Vec::new(),
+        };
+        for root in &roots {
+            if let Some(partial) = PartialClone::detect(&root.repo) {
+                partial
+                    .fetch_missing(&root.repo, &revs, &paths)
+                    .wrap_err_with(|| format!("fetching the objects left out of this {partial}"))?;
             }
         }
     }
 
-    let log_iter = get_log_iter(&repo, "HEAD")?;
-    for entry in log_iter {
-        entries.push((entry?, None));
+    if let Some(profile) = &mut profile {
+        profile.start("list submodules");
     }
-    if args.reverse {
-        entries.sort_by_key(|(entry, _)| entry.author_time);
+    let mut problems = Vec::new();
+    let submodules = if args.submodules {
+        gixl_core::submodules(&roots, &mut problems)
     } else {
-        entries.sort_by_key(|(entry, _)| std::cmp::Reverse(entry.author_time));
+        Vec::new()
+    };
+    let submodules = gixl_core::sources(&submodules);
+    // a misspelled label would silently stop nothing
+    let sources = roots
+        .iter()
+        .map(Source::Root)
+        .chain(submodules.iter().copied());
+    let labels: Vec<_> = sources.map(|source| source.label()).collect();
+    for (label, rev) in &args.until_ref {
+        if !labels.contains(label) {
+            color_eyre::eyre::bail!("--until-ref {label}={rev}: no repository labelled {label:?}");
+        }
     }
+    let changelog = match &args.changelog {
+        Some(revs) => {
+            Some(Changelog::new(&roots, &submodules, &revs[0], &revs[1]).wrap_err("--changelog")?)
+        }
+        None => None,
+    };
+    if let Some(profile) = &mut profile {
+        profile.start("read configuration");
+    }
+    let (config, config_problem) = match config::load(&roots[0].repo) {
+        Ok(config) => (config, None),
+        Err(err) => (config::from_git(&roots[0].repo), Some(err.to_string())),
+    };
+    // the UI takes the terminal first, to show how far loading goes
+    let mut screen = match browse {
+        true => Some(tui::Screen::open(args.pick, args.inline)?),
+        false => None,
+    };
+    // the list only reads the messages of the entries it shows
+    let defer = screen.is_some() && tui::defers(&config, args.prs);
+    // the walks go newest commit first, so a page of their order is all there is to read
+    let limit = args
+        .max_count
+        .filter(|_| args.sort == Role::Committer && !args.reverse)
+        .map(|count| args.skip + count);
+    let mut entries = match &args.compare {
+        Some(revs) => {
+            if let Some(profile) = &mut profile {
+                profile.start("compare");
+            }
+            let root = &roots[0];
+            // tags name the commits they point to
+            let peel = |rev: &str| -> Result<_> {
+                Ok(root
+                    .repo
+                    .rev_parse_single(rev)?
+                    .object()?
+                    .peel_to_commit()?
+                    .id)
+            };
+            let (a, b) = (peel(&revs[0])?, peel(&revs[1])?);
+            let mut entries = Vec::new();
+            for entry in compare(&root.repo, a, b)? {
+                if filter.matches(&root.repo, &entry)? {
+                    entries.push((entry, Source::Root(root)));
+                }
+            }
+            entries
+        }
+        // printed as they come, without waiting for the others to sort them
+        None if args.stdin && screen.is_none() => {
+            let format = format.unwrap_or(output::Format::Plain);
+            let mut resolver = gixl_core::Resolver::new(&roots, &submodules);
+            for line in std::io::stdin().lines() {
+                for rev in gixl_core::revisions(&line?) {
+                    match resolver.list(&[rev], &filter) {
+                        Ok(entries) => output::print(&entries, format, &config, args.sort)?,
+                        Err(err) => eprintln!("warning: {err}"),
+                    }
+                }
+            }
+            return Ok(());
+        }
+        // the rest is read once browsing
+        None if args.stdin => {
+            let mut entries = Vec::new();
+            let mut lines = std::io::stdin().lines();
+            let mut resolver = gixl_core::Resolver::new(&roots, &submodules);
+            while entries.is_empty()
+                && let Some(line) = lines.next()
+            {
+                for rev in gixl_core::revisions(&line?) {
+                    match resolver.list(&[rev], &filter) {
+                        Ok(listed) => entries.extend(listed),
+                        Err(error) => problems.push(Problem {
+                            source: "stdin".into(),
+                            error,
+                        }),
+                    }
+                }
+            }
+            entries
+        }
+        None if !args.no_walk.is_empty() => {
+            if let Some(profile) = &mut profile {
+                profile.start("read commits");
+            }
+            let revs = no_walk_revs(&args.no_walk)?;
+            gixl_core::listed(&roots, &submodules, &revs, &filter)?
+        }
+        None => gixl_core::collect(
+            &roots,
+            &submodules,
+            &filter,
+            defer,
+            limit,
+            &mut |source, commits| {
+                if let Some(profile) = &mut profile {
+                    profile.walked(source, commits);
+                }
+                screen
+                    .as_mut()
+                    .is_none_or(|screen| screen.loading(source, commits))
+            },
+            &mut problems,
+        ),
+    };
+    if let Some(profile) = &mut profile {
+        profile.start("sort");
+    }
+    log::sort(&mut entries, args.reverse, args.sort);
+    if entries.is_empty() {
+        // with nothing to browse, why is all there is to tell
+        if !problems.is_empty() {
+            let problem = problems.remove(0);
+            return Err(problem.error.wrap_err(problem.source));
+        }
+        // an empty list is printed, like with `git log` in a range without commits
+        if screen.is_some() {
+            color_eyre::eyre::bail!("no commits to show");
+        }
+    }
+    log::page(&mut entries, args.skip, args.max_count);
+    // paging past the end prints nothing, but leaves nothing to browse
+    if entries.is_empty() && screen.is_some() {
+        color_eyre::eyre::bail!("no commits to show past the first {}", args.skip);
+    }
+    let mut problems: Vec<_> = problems
+        .iter()
+        .map(ToString::to_string)
+        .chain(config_problem)
+        .collect();
 
-    tui::run(git_dir.to_path_buf(), entries)
-}
-
-fn get_log_iter<'a>(
-    repo: &'a gix::Repository,
-    spec: &str,
-) -> Result<Box<dyn Iterator<Item = Result<LogEntryInfo>> + 'a>> {
-    Ok(Box::new(
-        repo.rev_walk([repo
-            .rev_parse_single(spec)?
-            .object()?
-            .try_into_commit()?
-            .id()])
-            .sorting(Sorting::ByCommitTime(Default::default()))
-            .all()?
-            .map(|info| -> Result<_> {
-                let info = info?;
-                let commit = info.object()?;
-                let commit_ref = commit.decode()?;
-
-                let commit_id = commit.id().to_hex().to_string();
-                let author = commit_ref.author().name.into();
-                let author_time = commit_ref.author.time()?;
-                //let time = commit_ref.author.time.to_string();
-                let time = author_time.format(ISO8601);
-                let message = commit_ref.message.to_owned();
-                Ok(LogEntryInfo {
-                    commit_id,
-                    author,
-                    time,
-                    message,
-                    author_time,
-                })
-            }),
-    ))
+    let Some(screen) = screen else {
+        for problem in &problems {
+            eprintln!("warning: {problem}");
+        }
+        if let Some(path) = &args.listen {
+            if let Some(profile) = &mut profile {
+                profile.end();
+                eprintln!("{profile}");
+            }
+            let sources: Vec<_> = roots.iter().map(Source::Root).chain(submodules).collect();
+            return server::serve(path, &entries, &sources, &config);
+        }
+        if let Some(profile) = &mut profile {
+            profile.start("print");
+        }
+        let format = format.unwrap_or(output::Format::Plain);
+        match &changelog {
+            Some(changelog) if format != output::Format::Plain => {
+                let out = std::io::stdout().lock();
+                changelog.print(&entries, format, &config, args.sort, out)?;
+            }
+            _ => output::print(&entries, format, &config, args.sort)?,
+        }
+        if let Some(profile) = &mut profile {
+            profile.end();
+            eprintln!("{profile}");
+        }
+        return Ok(());
+    };
+    if screen.stopped() {
+        problems.push("loading stopped: older commits are left out".into());
+    }
+    let options = tui::Options {
+        describe: args.describe,
+        reverse: args.reverse,
+        sort: args.sort,
+        filter,
+        session: session_path,
+        restore,
+        read_only: args.read_only || config.read_only,
+        screen_reader: args.screen_reader || config.screen_reader,
+        view: args.view.unwrap_or(config.view),
+        ascii: args.ascii,
+        ci: args.ci,
+        prs: args.prs,
+        config,
+        pick: args.pick.then_some(args.pick_format),
+        inline: args.inline,
+        changelog,
+        stdin: args.stdin,
+        problems,
+    };
+    if let Some(profile) = &mut profile {
+        profile.start("first draw");
+    }
+    let picked = tui::run(
+        screen,
+        &roots,
+        &submodules,
+        entries,
+        options,
+        profile.as_mut(),
+    )?;
+    // once the terminal is given back
+    if let Some(profile) = &profile {
+        eprintln!("{profile}");
+    }
+    if let Some(picked) = picked {
+        println!("{picked}");
+    }
+    Ok(())
 }
@@ -0,0 +1,220 @@
+//! Release notes of a range of commits as Markdown, grouped by the kind of change their
+//! conventional commit subjects tell, `type(scope): description`, or by rules of the
+//! configuration.
+
+use std::io::Write;
+
+use color_eyre::Result;
+use gix::bstr::ByteSlice;
+
+use crate::{config::Config, view::Item};
+
+/// Sections of the kinds of conventional commits, in the order of the notes.
+const KINDS: &[(&str, &str)] = &[
+    ("feat", "Features"),
+    ("fix", "Bug Fixes"),
+    ("perf", "Performance"),
+    ("refactor", "Refactoring"),
+    ("docs", "Documentation"),
+    ("test", "Tests"),
+    ("build", "Build"),
+    ("ci", "Continuous Integration"),
+];
+
+/// Section of the commits marked with `!` or a `BREAKING CHANGE` trailer, first.
+const BREAKING: &str = "Breaking Changes";
+
+/// Section of the commits of no other, last.
+const OTHER: &str = "Other Changes";
+
+/// A subject written as a conventional commit.
+pub struct Conventional<'a> {
+    pub kind: &'a str,
+    pub scope: Option<&'a str>,
+    pub breaking: bool,
+    pub description: &'a str,
+}
+
+impl Conventional<'_> {
+    /// `subject` split as `type(scope)!: description`, the scope and `!` being optional.
+    pub fn parse(subject: &str) -> Option<Conventional<'_>> {
+        let (head, description) = subject.split_once(": ")?;
+        let (head, breaking) = match head.strip_suffix('!') {
+            Some(head) => (head, true),
+            None => (head, false),
+        };
+        let (kind, scope) = match head.split_once('(') {
+            Some((kind, scope)) => (kind, Some(scope.strip_suffix(')')?)),
+            None => (head, None),
+        };
+        let word = !kind.is_empty() && kind.chars().all(|c| c.is_ascii_alphabetic());
+        word.then(|| Conventional {
+            kind,
+            scope,
+            breaking,
+            description: description.trim(),
+        })
+    }
+}
+
+/// Write the notes of `entries` from `from` to `to` to `out`.
+///
+/// A commit goes to the section of the first rule of [`Config::release_notes`] matching its
+/// message, or else of its kind, merges and commits which couldn't be read being left out. Those
+/// of submodules are followed by the label of their repository.
+pub fn write(
+    entries: &[Item],
+    config: &Config,
+    from: &str,
+    to: &str,
+    mut out: impl Write,
+) -> Result<()> {
+    let headings = std::iter::once(BREAKING)
+        .chain(config.release_notes.iter().map(|(name, _)| name.as_str()))
+        .chain(KINDS.iter().map(|(_, heading)| *heading))
+        .chain([OTHER]);
+    let mut sections: Vec<(&str, Vec<String>)> = Vec::new();
+    for heading in headings {
+        if !sections.iter().any(|(h, _)| *h == heading) {
+            sections.push((heading, Vec::new()));
+        }
+    }
+    for (entry, source) in entries {
+        if entry.unreadable.is_some() || entry.parents.len() > 1 {
+            continue;
+        }
+        let subject = entry.message.lines().next().unwrap_or_default();
+        let subject = subject.to_str_lossy();
+        let conventional = Conventional::parse(&subject);
+        let breaking = conventional.as_ref().is_some_and(|c| c.breaking)
+            || entry.message.lines().any(|line| {
+                line.starts_with(b"BREAKING CHANGE:") || line.starts_with(b"BREAKING-CHANGE:")
+            });
+        let rule = config
+            .release_notes
+            .iter()
+            .find(|(_, pattern)| pattern.is_match(&entry.message));
+        let kind = conventional.as_ref().and_then(|c| {
+            KINDS
+                .iter()
+                .find(|(kind, _)| kind.eq_ignore_ascii_case(c.kind))
+        });
+        let heading = match (rule, kind) {
+            _ if breaking => BREAKING,
+            (Some((name, _)), _) => name.as_str(),
+            (None, Some((_, heading))) => heading,
+            (None, None) => OTHER,
+        };
+        let mut line = match &conventional {
+            Some(Conventional {
+                scope: Some(scope),
+                description,
+                ..
+            }) => format!("- **{scope}:** {description}"),
+            Some(Conventional { description, .. }) => format!("- {description}"),
+            None => format!("- {subject}"),
+        };
+        let id = entry.id.to_hex_with_len(config.abbrev);
+        match source.label() {
+            label if label.is_empty() => line.push_str(&format!(" ({id})")),
+            label => line.push_str(&format!(" ({label} {id})")),
+        }
+        if let Some((_, lines)) = sections.iter_mut().find(|(h, _)| *h == heading) {
+            lines.push(line);
+        }
+    }
+    writeln!(out, "# Changes from {from} to {to}")?;
+    for (heading, lines) in sections.iter().filter(|(_, lines)| !lines.is_empty()) {
+        writeln!(out, "\n## {heading}\n")?;
+        for line in lines {
+            writeln!(out, "{line}")?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use gixl_core::fixture::Fixture;
+
+    use super::*;
+
+    const ALICE: (&str, &str) = ("Alice", "alice@example.com");
+
+    #[test]
+    fn conventional_subjects() {
+        let fix = Conventional::parse("fix(ui)!: keep the selection").unwrap();
+        assert_eq!(
+            (fix.kind, fix.scope, fix.breaking),
+            ("fix", Some("ui"), true)
+        );
+        assert_eq!(fix.description, "keep the selection");
+        let feat = Conventional::parse("feat: add notes").unwrap();
+        assert_eq!(
+            (feat.kind, feat.scope, feat.breaking),
+            ("feat", None, false)
+        );
+        assert!(Conventional::parse("Merge branch 'main': sync").is_none());
+        assert!(Conventional::parse("Add notes").is_none());
+    }
+
+    #[test]
+    fn breaking_changes_then_rules_then_kinds() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init(dir.path())?;
+        fixture.commit(ALICE, "Tidy up", &[("a", "1\n")])?;
+        fixture.commit(ALICE, "feat: add notes", &[("a", "2\n")])?;
+        fixture.commit(ALICE, "fix: keep the tabs", &[("a", "3\n")])?;
+        fixture.commit(ALICE, "fix(ui)!: keep the selection", &[("a", "4\n")])?;
+        let trailer = "Drop the tab key\n\nBREAKING CHANGE: tabs are gone\n";
+        fixture.commit(ALICE, trailer, &[("a", "5\n")])?;
+        let roots = [fixture.root("")];
+        let mut problems = Vec::new();
+        let entries = gixl_core::collect(
+            &roots,
+            &[],
+            &gixl_core::log::Filter::default(),
+            false,
+            None,
+            &mut |_, _| true,
+            &mut problems,
+        );
+        // a rule matching a fix, and one a breaking change
+        let config = Config {
+            release_notes: vec![
+                ("Tabs".into(), regex::bytes::Regex::new("tab")?),
+                ("Selection".into(), regex::bytes::Regex::new("selection")?),
+            ],
+            ..Config::default()
+        };
+        let mut out = Vec::new();
+        write(&entries, &config, "v1", "v2", &mut out)?;
+
+        let notes = String::from_utf8(out)?;
+        let lines: Vec<_> = (notes.lines())
+            .map(|line| line.split(" (").next().unwrap_or(line))
+            .collect();
+        let expected = [
+            "# Changes from v1 to v2",
+            "",
+            "## Breaking Changes",
+            "",
+            "- Drop the tab key",
+            "- **ui:** keep the selection",
+            "",
+            "## Tabs",
+            "",
+            "- keep the tabs",
+            "",
+            "## Features",
+            "",
+            "- add notes",
+            "",
+            "## Other Changes",
+            "",
+            "- Tidy up",
+        ];
+        assert_eq!(lines, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use gix::state::InProgress;
+
+/// A git command stopped halfway, waiting to be continued or aborted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Am,
+    Bisect,
+    CherryPick,
+    Merge,
+    Rebase,
+    Revert,
+}
+
+impl Operation {
+    pub fn in_progress(repo: &gix::Repository) -> Option<Operation> {
+        Some(match repo.state()? {
+            InProgress::ApplyMailbox | InProgress::ApplyMailboxRebase => Operation::Am,
+            InProgress::Bisect => Operation::Bisect,
+            InProgress::CherryPick | InProgress::CherryPickSequence => Operation::CherryPick,
+            InProgress::Merge => Operation::Merge,
+            InProgress::Rebase | InProgress::RebaseInteractive => Operation::Rebase,
+            InProgress::Revert | InProgress::RevertSequence => Operation::Revert,
+        })
+    }
+
+    /// Name of the git command driving the operation.
+    pub fn label(self) -> &'static str {
+        match self {
+            Operation::Am => "am",
+            Operation::Bisect => "bisect",
+            Operation::CherryPick => "cherry-pick",
+            Operation::Merge => "merge",
+            Operation::Rebase => "rebase",
+            Operation::Revert => "revert",
+        }
+    }
+
+    /// Arguments of the git command resuming the operation, if it can be resumed.
+    pub fn continue_args(self) -> Option<[&'static str; 2]> {
+        match self {
+            Operation::Bisect => None,
+            _ => Some([self.label(), "--continue"]),
+        }
+    }
+
+    /// Arguments of the git command giving up the operation, restoring the state before it.
+    pub fn abort_args(self) -> [&'static str; 2] {
+        match self {
+            Operation::Bisect => ["bisect", "reset"],
+            _ => [self.label(), "--abort"],
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::io::Write;
+
+use color_eyre::Result;
+use gix::bstr::ByteSlice;
+
+use crate::{
+    config::Config,
+    log::{LogEntryInfo, Role},
+    source::Source,
+    view::Item,
+};
+
+/// How entries are printed when not browsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// One tab-separated line per commit: id, date, author, repository and subject.
+    Plain,
+    /// One JSON object per line.
+    Json,
+    /// A Markdown list, one item per commit.
+    #[value(name = "md", alias = "markdown")]
+    Markdown,
+}
+
+/// `value` as a JSON string.
+pub fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `entry` as a JSON object on one line.
+pub fn json(entry: &LogEntryInfo, source: &Source, config: &Config) -> String {
+    let subject = entry.message.lines().next().unwrap_or_default();
+    let parents: Vec<_> = entry
+        .parents
+        .iter()
+        .map(|p| json_string(&p.to_string()))
+        .collect();
+    format!(
+        "{{\"id\":\"{}\",\"parents\":[{}],\"author\":{},\"date\":{},\"time\":{},\"committer\":{},\"committer_date\":{},\"committer_time\":{},\"source\":{},\"subject\":{},\"message\":{},\"boundary\":{}}}",
+        entry.id,
+        parents.join(","),
+        json_string(&entry.author.to_str_lossy()),
+        json_string(config.date.format(entry.author_time).trim_end()),
+        entry.author_time.seconds,
+        json_string(&entry.committer.to_str_lossy()),
+        json_string(config.date.format(entry.committer_time).trim_end()),
+        entry.committer_time.seconds,
+        json_string(&source.label()),
+        json_string(&subject.to_str_lossy()),
+        json_string(&entry.message.to_str_lossy()),
+        entry.boundary,
+    )
+}
+
+/// `entry` as an item of a Markdown list, with the time of `time`.
+pub fn markdown(entry: &LogEntryInfo, config: &Config, time: Role) -> String {
+    let subject = entry.message.lines().next().unwrap_or_default();
+    let mut item = format!(
+        "- `{}{}` {} — {}",
+        boundary(entry),
+        entry.id.to_hex_with_len(config.abbrev),
+        subject.to_str_lossy(),
+        entry.author.to_str_lossy(),
+    );
+    if entry.unreadable.is_none() {
+        item.push_str(&format!(
+            ", {}",
+            config.date.format(entry.time_of(time)).trim_end()
+        ));
+    }
+    item
+}
+
+/// The prefix of the id of `entry`, `-` for a boundary commit like `git log --boundary`.
+fn boundary(entry: &LogEntryInfo) -> &'static str {
+    if entry.boundary { "-" } else { "" }
+}
+
+/// Print `entries`, plain lines and Markdown items showing the time of `time`.
+pub fn print(entries: &[Item], format: Format, config: &Config, time: Role) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    for (entry, source) in entries {
+        let subject = entry.message.lines().next().unwrap_or_default();
+        let subject = subject.to_str_lossy();
+        let date = match entry.unreadable {
+            Some(_) => String::new(),
+            None => config.date.format(entry.time_of(time)),
+        };
+        let res = match format {
+            Format::Plain => writeln!(
+                out,
+                "{}{}\t{}\t{}\t{}\t{subject}",
+                boundary(entry),
+                entry.id.to_hex_with_len(config.abbrev),
+                date.trim_end(),
+                entry.author,
+                source.label(),
+            ),
+            Format::Json => writeln!(out, "{}", json(entry, source, config)),
+            Format::Markdown => match source.label() {
+                label if label.is_empty() => writeln!(out, "{}", markdown(entry, config, time)),
+                label => writeln!(out, "{} ({label})", markdown(entry, config, time)),
+            },
+        };
+        match res {
+            // the reader went away, like `head`
+            Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => return Ok(()),
+            res => res?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("plain"), r#""plain""#);
+        assert_eq!(json_string("say \"hi\"\\"), r#""say \"hi\"\\""#);
+        assert_eq!(json_string("a\nb\tc\r"), r#""a\nb\tc\r""#);
+        assert_eq!(json_string("\u{1b}[1m"), r#""\u001b[1m""#);
+        assert_eq!(json_string("héllo ✓"), "\"héllo ✓\"");
+    }
+
+    #[test]
+    fn json_string_round_trips() {
+        let text = "quote \" backslash \\ bell \u{7} newline \n";
+        let parsed = crate::json::parse(&json_string(text));
+        assert_eq!(parsed, Ok(crate::json::Value::String(text.into())));
+    }
+}
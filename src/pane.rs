@@ -0,0 +1,310 @@
+use std::collections::HashSet;
+
+use ratatui::{prelude::*, widgets::*};
+
+use crate::width;
+
+/// The diff of a commit in place of the list, file after file, with the list of its files on
+/// the left, the one shown at the top being highlighted. Files and hunks fold to their first
+/// line.
+pub struct DiffPane {
+    pub title: String,
+    /// Name of each file.
+    files: Vec<String>,
+    lines: Vec<Line<'static>>,
+    /// File of each line, and hunk of the commit, `None` for the lines before its first hunk.
+    owners: Vec<(usize, Option<usize>)>,
+    /// Index of the first line of each file.
+    file_starts: Vec<usize>,
+    /// Index of the first line of each hunk.
+    hunk_starts: Vec<usize>,
+    folded_files: HashSet<usize>,
+    folded_hunks: HashSet<usize>,
+    /// Index of each line shown, folded ones left out.
+    visible: Vec<usize>,
+    /// Position in the visible lines of the one at the top.
+    scroll: usize,
+    /// Lines shown at once, when last drawn.
+    height: usize,
+    /// Whether the line at the top is highlighted, as the one acted on.
+    pub cursor: bool,
+    state: ListState,
+}
+
+impl DiffPane {
+    /// A pane over the lines of each file, starting with its name, hunks starting with `@@`.
+    pub fn new(title: impl Into<String>, files: Vec<(String, Vec<Line<'static>>)>) -> DiffPane {
+        let mut pane = DiffPane {
+            title: title.into(),
+            files: Vec::new(),
+            lines: Vec::new(),
+            owners: Vec::new(),
+            file_starts: Vec::new(),
+            hunk_starts: Vec::new(),
+            folded_files: HashSet::new(),
+            folded_hunks: HashSet::new(),
+            visible: Vec::new(),
+            scroll: 0,
+            height: 0,
+            cursor: false,
+            state: ListState::default(),
+        };
+        for (file, (name, lines)) in files.into_iter().enumerate() {
+            pane.files.push(name);
+            pane.file_starts.push(pane.lines.len());
+            let mut hunk = None;
+            for line in lines {
+                if line.to_string().starts_with("@@") {
+                    hunk = Some(pane.hunk_starts.len());
+                    pane.hunk_starts.push(pane.lines.len());
+                }
+                pane.owners.push((file, hunk));
+                pane.lines.push(line);
+            }
+        }
+        pane.visible = (0..pane.lines.len()).collect();
+        pane
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = (self.scroll + lines).min(self.visible.len().saturating_sub(1));
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    /// Position of the line at the top among the lines shown.
+    pub fn top(&self) -> usize {
+        self.scroll
+    }
+
+    /// Lines scrolled by a page.
+    pub fn page(&self) -> usize {
+        self.height.max(1)
+    }
+
+    /// File and hunk of the line at the top.
+    fn current(&self) -> Option<(usize, Option<usize>)> {
+        Some(self.owners[*self.visible.get(self.scroll)?])
+    }
+
+    /// File of the line at the top, with its hunk counted in the file and its index in the hunk,
+    /// `0` being the header.
+    pub fn position(&self) -> Option<(usize, Option<(usize, usize)>)> {
+        let i = *self.visible.get(self.scroll)?;
+        let (file, hunk) = self.owners[i];
+        let hunk = hunk.map(|hunk| {
+            let before = self.hunk_starts[..hunk]
+                .iter()
+                .filter(|start| self.owners[**start].0 == file)
+                .count();
+            (before, i - self.hunk_starts[hunk])
+        });
+        Some((file, hunk))
+    }
+
+    /// File of the line at the top, with its number in the new version of the file when in a hunk,
+    /// a removed line standing for the next one kept.
+    pub fn new_line(&self) -> Option<(usize, Option<usize>)> {
+        let i = *self.visible.get(self.scroll)?;
+        let (file, hunk) = self.owners[i];
+        let Some(hunk) = hunk else {
+            return Some((file, None));
+        };
+        // the header is `@@ -a,b +c,d @@`, `c` being the number of the first line of the hunk
+        let start = self.hunk_starts[hunk];
+        let header = self.lines[start].to_string();
+        let first = header
+            .split(' ')
+            .find_map(|range| range.strip_prefix('+'))
+            .and_then(|range| range.split(',').next()?.parse::<usize>().ok());
+        let kept = self.lines[start..i].iter().skip(1).filter(|line| {
+            let text = line.to_string();
+            !text.starts_with('-') && !text.starts_with('\\')
+        });
+        let line = first.map(|first| first + kept.count());
+        Some((file, line.filter(|line| *line > 0)))
+    }
+
+    /// Scroll to the next or previous start of a hunk or of a file which is shown.
+    fn jump(&mut self, hunks: bool, forward: bool) {
+        let starts = match hunks {
+            true => &self.hunk_starts,
+            false => &self.file_starts,
+        };
+        let mut positions = starts
+            .iter()
+            .filter_map(|start| self.visible.binary_search(start).ok());
+        let target = match forward {
+            true => positions.find(|position| *position > self.scroll),
+            false => positions.rev().find(|position| *position < self.scroll),
+        };
+        if let Some(target) = target {
+            self.scroll = target;
+        }
+    }
+
+    pub fn next_file(&mut self) {
+        self.jump(false, true);
+    }
+
+    pub fn previous_file(&mut self) {
+        self.jump(false, false);
+    }
+
+    pub fn next_hunk(&mut self) {
+        self.jump(true, true);
+    }
+
+    pub fn previous_hunk(&mut self) {
+        self.jump(true, false);
+    }
+
+    /// Fold or unfold the hunk at the top.
+    pub fn toggle_hunk(&mut self) {
+        if let Some((_, Some(hunk))) = self.current() {
+            if !self.folded_hunks.remove(&hunk) {
+                self.folded_hunks.insert(hunk);
+            }
+            self.refold(self.hunk_starts[hunk]);
+        }
+    }
+
+    /// Fold or unfold the file at the top.
+    pub fn toggle_file(&mut self) {
+        if let Some((file, _)) = self.current() {
+            if !self.folded_files.remove(&file) {
+                self.folded_files.insert(file);
+            }
+            self.refold(self.file_starts[file]);
+        }
+    }
+
+    /// Fold every file, or unfold everything once they all are.
+    pub fn toggle_all(&mut self) {
+        let Some((file, _)) = self.current() else {
+            return;
+        };
+        if self.folded_files.len() == self.files.len() {
+            self.folded_files.clear();
+            self.folded_hunks.clear();
+        } else {
+            self.folded_files = (0..self.files.len()).collect();
+        }
+        self.refold(self.file_starts[file]);
+    }
+
+    /// Update the lines shown after folding, the line `top` staying at the top.
+    fn refold(&mut self, top: usize) {
+        self.visible = (0..self.lines.len())
+            .filter(|i| {
+                let (file, hunk) = self.owners[*i];
+                let file_folded = self.folded_files.contains(&file) && *i != self.file_starts[file];
+                let hunk_folded = hunk.is_some_and(|hunk| {
+                    self.folded_hunks.contains(&hunk) && *i != self.hunk_starts[hunk]
+                });
+                !file_folded && !hunk_folded
+            })
+            .collect();
+        self.scroll = self.visible.partition_point(|i| *i < top);
+    }
+
+    /// The file and hunk shown at the top, counted in the commit and in the file.
+    pub fn status(&self) -> String {
+        let Some((file, hunk)) = self.current() else {
+            return "no changes".into();
+        };
+        let hunks: Vec<_> = (0..self.hunk_starts.len())
+            .filter(|hunk| self.owners[self.hunk_starts[*hunk]].0 == file)
+            .collect();
+        let mut status = format!(
+            "{} - file {} of {}",
+            self.files[file],
+            file + 1,
+            self.files.len()
+        );
+        if self.folded_files.contains(&file) {
+            status += ", folded";
+        }
+        match hunk.and_then(|hunk| hunks.iter().position(|h| *h == hunk)) {
+            Some(n) => status += &format!(", hunk {} of {}", n + 1, hunks.len()),
+            None => match hunks.len() {
+                0 => {}
+                1 => status += ", 1 hunk",
+                n => status += &format!(", {n} hunks"),
+            },
+        }
+        status
+    }
+
+    /// The line at `i`, telling how many lines are folded under it.
+    fn shown(&self, i: usize) -> Line<'static> {
+        let (file, hunk) = self.owners[i];
+        let file_end = self
+            .file_starts
+            .get(file + 1)
+            .copied()
+            .unwrap_or(self.lines.len());
+        let end = if self.folded_files.contains(&file) && i == self.file_starts[file] {
+            file_end
+        } else if let Some(hunk) = hunk
+            && self.folded_hunks.contains(&hunk)
+            && i == self.hunk_starts[hunk]
+        {
+            let next = self.hunk_starts.get(hunk + 1).copied();
+            next.map_or(file_end, |next| next.min(file_end))
+        } else {
+            return self.lines[i].clone();
+        };
+        let mut line = self.lines[i].clone();
+        line.push_span(Span::styled(
+            format!("  … {} lines folded", end - i - 1),
+            Style::new().gray(),
+        ));
+        line
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let width = self
+            .files
+            .iter()
+            .map(|name| width::width(name) as u16 + 4)
+            .max()
+            .unwrap_or(0)
+            .min(area.width / 3);
+        let [files, diff] =
+            Layout::horizontal([Constraint::Length(width), Constraint::Min(0)]).areas(area);
+        self.state.select(self.current().map(|(file, _)| file));
+        let items: Vec<ListItem> = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(file, name)| match self.folded_files.contains(&file) {
+                true => ListItem::new(Line::styled(name.clone(), Style::new().gray())),
+                false => ListItem::new(name.clone()),
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::new().borders(Borders::RIGHT))
+            .highlight_style(Style::new().bg(Color::LightGreen).bold())
+            .highlight_symbol("> ");
+        f.render_stateful_widget(list, files, &mut self.state);
+
+        let block = Block::bordered().title(format!(" {} ", self.title));
+        self.height = block.inner(diff).height as usize;
+        let mut lines: Vec<_> = self
+            .visible
+            .iter()
+            .skip(self.scroll)
+            .take(self.height)
+            .map(|i| self.shown(*i))
+            .collect();
+        if self.cursor
+            && let Some(top) = lines.first_mut()
+        {
+            *top = std::mem::take(top).patch_style(Modifier::REVERSED);
+        }
+        f.render_widget(Paragraph::new(lines).block(block), diff);
+    }
+}
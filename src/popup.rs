@@ -0,0 +1,50 @@
+use ratatui::{prelude::*, widgets::*};
+
+/// A scrollable, read-only text box drawn over the list.
+pub struct Popup {
+    pub title: String,
+    pub lines: Vec<Line<'static>>,
+    pub scroll: u16,
+}
+
+impl Popup {
+    pub fn new(title: impl Into<String>, lines: Vec<Line<'static>>) -> Popup {
+        Popup {
+            title: title.into(),
+            lines,
+            scroll: 0,
+        }
+    }
+
+    pub fn scroll_down(&mut self) {
+        if (self.scroll as usize) + 1 < self.lines.len() {
+            self.scroll += 1;
+        }
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    pub fn render(&self, f: &mut Frame) {
+        let area = f.area();
+        let width = (area.width * 4 / 5).max(20).min(area.width);
+        let height = (self.lines.len() as u16 + 2)
+            .min(area.height * 4 / 5)
+            .max(3)
+            .min(area.height);
+        let area = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+
+        let block = Block::bordered().title(format!(" {} ", self.title));
+        let paragraph = Paragraph::new(self.lines.clone())
+            .block(block)
+            .scroll((self.scroll, 0));
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+}
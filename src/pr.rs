@@ -0,0 +1,54 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use color_eyre::Result;
+use gix::{ObjectId, bstr::ByteSlice};
+use regex::Regex;
+
+use crate::log::{self, LogEntryInfo};
+
+/// `Subject (#123)`, left by squash and rebase merges on GitHub.
+static SQUASHED: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\(#(\d+)\)$").unwrap());
+/// Subjects and trailers of the merge commits of GitHub and GitLab.
+static MERGED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^Merge pull request #(\d+) from|^See merge request \S*!(\d+)").unwrap()
+});
+
+/// Number of the pull request a merge commit or a squashed commit message refers to.
+fn number(message: &str, merge: bool) -> Option<u64> {
+    let captures = match merge {
+        true => MERGED.captures(message)?,
+        false => SQUASHED.captures(message.lines().next()?.trim_end())?,
+    };
+    captures
+        .iter()
+        .skip(1)
+        .flatten()
+        .find_map(|m| m.as_str().parse().ok())
+}
+
+/// The pull or merge request which introduced each of `entries`, found from the messages of merge
+/// commits (the commits they bring in) and squashed commits, those deferred being read for them.
+/// The commits must be in `repo`.
+pub fn numbers(
+    repo: &gix::Repository,
+    mut entries: Vec<LogEntryInfo>,
+) -> Result<HashMap<ObjectId, u64>> {
+    let mut numbers = HashMap::new();
+    // oldest first: a commit belongs to the first request merging it
+    entries.sort_by_key(|entry| entry.committer_time.seconds);
+    for mut entry in entries {
+        entry.load(repo);
+        let message = entry.message.to_str_lossy();
+        let merge = entry.parents.len() > 1;
+        let Some(n) = number(&message, merge) else {
+            continue;
+        };
+        numbers.entry(entry.id).or_insert(n);
+        if merge {
+            for commit in log::walk(repo, entry.parents[1..].to_vec(), [entry.parents[0]])? {
+                numbers.entry(commit?.id).or_insert(n);
+            }
+        }
+    }
+    Ok(numbers)
+}
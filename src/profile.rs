@@ -0,0 +1,87 @@
+//! Where the time goes while starting, reported with `--profile` to tell why a repository is
+//! slow to list: each step lasts until the next one starts.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::source::Source;
+
+/// A step of starting.
+struct Step {
+    name: String,
+    start: Instant,
+    /// Commits read, for the walk of a source.
+    commits: Option<usize>,
+}
+
+/// The steps of starting, timed as they come.
+pub struct Profile {
+    steps: Vec<Step>,
+    end: Option<Instant>,
+}
+
+impl Profile {
+    /// Start timing, with step `name`.
+    pub fn new(name: &str) -> Profile {
+        let mut profile = Profile {
+            steps: Vec::new(),
+            end: None,
+        };
+        profile.start(name);
+        profile
+    }
+
+    /// End the step going on, starting step `name`.
+    pub fn start(&mut self, name: impl Into<String>) {
+        self.steps.push(Step {
+            name: name.into(),
+            start: Instant::now(),
+            commits: None,
+        });
+    }
+
+    /// Follow the walks of the sources, told that `commits` of `source` were read so far: each
+    /// walk is a step of its own.
+    pub fn walked(&mut self, source: &Source, commits: usize) {
+        let name = match source.label() {
+            label if label.is_empty() => "walk superproject".to_owned(),
+            label => format!("walk {label}"),
+        };
+        if self.steps.last().is_none_or(|step| step.name != name) {
+            self.start(name);
+        }
+        if let Some(step) = self.steps.last_mut() {
+            step.commits = Some(commits);
+        }
+    }
+
+    /// End the last step, unless it already is.
+    pub fn end(&mut self) {
+        self.end.get_or_insert_with(Instant::now);
+    }
+}
+
+impl fmt::Display for Profile {
+    /// One line per step, with how long it took, then the total.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let end = self.end.unwrap_or_else(Instant::now);
+        let ends = self.steps.iter().skip(1).map(|step| step.start);
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        for (step, step_end) in self.steps.iter().zip(ends.chain([end])) {
+            write!(
+                f,
+                "{:<28} {:>10.1} ms",
+                step.name,
+                ms(step_end - step.start)
+            )?;
+            if let Some(commits) = step.commits {
+                write!(f, "  {commits} commits")?;
+            }
+            writeln!(f)?;
+        }
+        let start = self.steps.first().map_or(end, |step| step.start);
+        write!(f, "{:<28} {:>10.1} ms", "total", ms(end - start))
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use color_eyre::Result;
+use gix::{ObjectId, bstr::ByteSlice};
+
+use crate::log::{self, LogEntryInfo, Side};
+
+fn subject(entry: &LogEntryInfo) -> String {
+    let subject = entry.message.lines().next().unwrap_or_default();
+    subject.to_str_lossy().into_owned()
+}
+
+/// What a `fixup!`, `squash!` or `amend!` subject refers to, with repeated prefixes stripped.
+fn squash_target(subject: &str) -> Option<&str> {
+    let mut target = subject;
+    while let Some(rest) = ["fixup! ", "squash! ", "amend! "]
+        .iter()
+        .find_map(|prefix| target.strip_prefix(prefix))
+    {
+        target = rest;
+    }
+    (target.len() < subject.len()).then_some(target)
+}
+
+/// Commits replayed by `git rebase -i <base>`, oldest first: merges are dropped.
+pub fn todo(repo: &gix::Repository, base: ObjectId) -> Result<Vec<LogEntryInfo>> {
+    let head = repo.head_id()?.detach();
+    let mut commits = log::walk(repo, [head], [base])?
+        .filter(|entry| entry.as_ref().ok().is_none_or(|e| e.parents.len() <= 1))
+        .collect::<Result<Vec<_>>>()?;
+    commits.reverse();
+    Ok(commits)
+}
+
+/// Whether the commits of HEAD after `base` are a line, which a rebase replays without losing
+/// merges.
+pub fn linear(repo: &gix::Repository, base: ObjectId) -> Result<bool> {
+    let head = repo.head_id()?.detach();
+    for entry in log::walk(repo, [head], [base])? {
+        if entry?.parents.len() > 1 {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Commits replayed by `git rebase <onto>`, oldest first, flagged as equivalent when their patch
+/// is already in `onto`, which drops them.
+pub fn replay(repo: &gix::Repository, onto: ObjectId) -> Result<Vec<LogEntryInfo>> {
+    let head = repo.head_id()?.detach();
+    let mut commits: Vec<_> = log::compare(repo, onto, head)?
+        .into_iter()
+        .filter(|entry| entry.side == Some(Side::Right) && entry.parents.len() <= 1)
+        .collect();
+    commits.reverse();
+    Ok(commits)
+}
+
+/// Reorder `commits` (oldest first) the way `git rebase --autosquash` does: each `fixup!`,
+/// `squash!` or `amend!` commit moves right after the commits it will be folded into.
+/// Folded commits are flagged.
+pub fn autosquash(commits: Vec<LogEntryInfo>) -> Vec<(LogEntryInfo, bool)> {
+    let subjects: Vec<_> = commits.iter().map(subject).collect();
+    // each group is a commit followed by the ones folded into it
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of: HashMap<usize, usize> = HashMap::new();
+    for (i, subject) in subjects.iter().enumerate() {
+        let target = squash_target(subject).and_then(|target| {
+            (0..i)
+                .find(|j| subjects[*j] == target)
+                .or_else(|| {
+                    (0..i).find(|j| {
+                        target.len() >= 4 && commits[*j].id.to_string().starts_with(target)
+                    })
+                })
+                .or_else(|| (0..i).find(|j| subjects[*j].starts_with(target)))
+        });
+        match target.and_then(|j| group_of.get(&j).copied()) {
+            Some(group) => {
+                groups[group].push(i);
+                group_of.insert(i, group);
+            }
+            None => {
+                group_of.insert(i, groups.len());
+                groups.push(vec![i]);
+            }
+        }
+    }
+
+    let mut commits: Vec<_> = commits.into_iter().map(Some).collect();
+    groups
+        .into_iter()
+        .flat_map(|group| {
+            group
+                .into_iter()
+                .enumerate()
+                .map(|(n, i)| (i, n > 0))
+                .collect::<Vec<_>>()
+        })
+        .filter_map(|(i, folded)| Some((commits[i].take()?, folded)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use gixl_core::fixture::Fixture;
+
+    use super::*;
+
+    const ALICE: (&str, &str) = ("Alice", "alice@example.com");
+
+    #[test]
+    fn autosquash_folds_after_the_targets() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init(dir.path())?;
+        let base = fixture.commit(ALICE, "base", &[("a", "0\n")])?;
+        let first = fixture.commit(ALICE, "add parser", &[("a", "1\n")])?;
+        fixture.commit(ALICE, "add lexer", &[("b", "1\n")])?;
+        fixture.commit(ALICE, "fixup! add parser", &[("a", "2\n")])?;
+        fixture.commit(ALICE, "squash! fixup! add lexer", &[("b", "2\n")])?;
+        let short = first.to_hex_with_len(7).to_string();
+        fixture.commit(ALICE, &format!("amend! {short}"), &[("a", "3\n")])?;
+        fixture.commit(ALICE, "fixup! add pa", &[("a", "4\n")])?;
+        fixture.commit(ALICE, "fixup! nothing", &[("c", "1\n")])?;
+        let root = fixture.root("");
+
+        let todo = autosquash(todo(&root.repo, base)?);
+        let todo: Vec<_> = (todo.iter())
+            .map(|(entry, folded)| (subject(entry), *folded))
+            .collect();
+        let expected = [
+            ("add parser".into(), false),
+            ("fixup! add parser".into(), true),
+            (format!("amend! {short}"), true),
+            ("fixup! add pa".into(), true),
+            ("add lexer".into(), false),
+            ("squash! fixup! add lexer".into(), true),
+            ("fixup! nothing".into(), false),
+        ];
+        assert_eq!(todo, expected);
+        Ok(())
+    }
+}
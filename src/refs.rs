@@ -0,0 +1,495 @@
+use std::{path::Path, process::Command};
+
+use color_eyre::Result;
+use gix::{
+    ObjectId,
+    bstr::{BString, ByteSlice},
+    commit::describe::SelectRef,
+    date::time::format::ISO8601,
+    remote::Direction,
+    revision::walk::Sorting,
+};
+
+use crate::replace;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RefKind {
+    Branch,
+    Remote,
+    Tag,
+}
+
+impl RefKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            RefKind::Branch => "branch",
+            RefKind::Remote => "remote",
+            RefKind::Tag => "tag",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RefInfo {
+    pub kind: RefKind,
+    /// Short name, like `main`, `origin/main` or `v1.0`.
+    pub name: String,
+    /// The commit the reference points to, with tags peeled.
+    pub target: ObjectId,
+}
+
+/// A remote of a repository.
+#[derive(Clone, Debug)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub fetch_url: Option<gix::Url>,
+    /// URL pushed to, when it isn't the one fetched from.
+    pub push_url: Option<gix::Url>,
+    pub refspecs: Vec<String>,
+    /// Number of its remote-tracking branches.
+    pub branches: usize,
+}
+
+/// Remotes of `repo`, by name.
+pub fn remotes(repo: &gix::Repository) -> Result<Vec<RemoteInfo>> {
+    let mut remotes = Vec::new();
+    for name in repo.remote_names() {
+        let remote = repo.find_remote(name.as_ref())?;
+        let fetch_url = remote.url(Direction::Fetch).cloned();
+        let push_url = remote
+            .url(Direction::Push)
+            .filter(|url| Some(*url) != fetch_url.as_ref())
+            .cloned();
+        let refspecs = remote
+            .refspecs(Direction::Fetch)
+            .iter()
+            .map(|spec| spec.to_ref().to_bstring().to_string())
+            .collect();
+        let prefix = format!("refs/remotes/{name}/");
+        let branches = repo.references()?.prefixed(prefix.as_str())?.count();
+        remotes.push(RemoteInfo {
+            name: name.to_string(),
+            fetch_url,
+            push_url,
+            refspecs,
+            branches,
+        });
+    }
+    Ok(remotes)
+}
+
+/// Local branches, remote-tracking branches and tags of `repo`.
+pub fn list(repo: &gix::Repository) -> Result<Vec<RefInfo>> {
+    let platform = repo.references()?;
+    let mut refs = Vec::new();
+    for (kind, iter) in [
+        (RefKind::Branch, platform.local_branches()?),
+        (RefKind::Remote, platform.remote_branches()?),
+        (RefKind::Tag, platform.tags()?),
+    ] {
+        for reference in iter {
+            let Ok(mut reference) = reference else {
+                continue;
+            };
+            let name = reference.name().shorten().to_str_lossy().into_owned();
+            let Ok(id) = reference.peel_to_id_in_place() else {
+                continue;
+            };
+            refs.push(RefInfo {
+                kind,
+                name,
+                target: id.detach(),
+            });
+        }
+    }
+    Ok(refs)
+}
+
+/// The subset of `refs` from which commit `id` is reachable.
+pub fn containing<'a>(
+    repo: &gix::Repository,
+    id: ObjectId,
+    refs: &'a [RefInfo],
+) -> Result<Vec<&'a RefInfo>> {
+    let cache = replace::commit_graph(repo)?;
+    let mut graph = repo.revision_graph(cache.as_ref());
+    let mut found = Vec::new();
+    for r in refs {
+        // tags may point to trees or blobs, which have no history
+        if r.target == id
+            || repo
+                .merge_base_with_graph(id, r.target, &mut graph)
+                .is_ok_and(|base| base.detach() == id)
+        {
+            found.push(r);
+        }
+    }
+    Ok(found)
+}
+
+/// The remote-tracking branch commit `id` was pushed to, if any, a branch rather than the one
+/// `origin/HEAD` points to.
+pub fn pushed(repo: &gix::Repository, id: ObjectId) -> Result<Option<String>> {
+    let refs: Vec<_> = list(repo)?
+        .into_iter()
+        .filter(|r| r.kind == RefKind::Remote)
+        .collect();
+    Ok(containing(repo, id, &refs)?
+        .into_iter()
+        .min_by_key(|r| r.name.ends_with("/HEAD"))
+        .map(|r| r.name.clone()))
+}
+
+/// Commits of `a` not in `b`, and of `b` not in `a`.
+fn ahead_behind(repo: &gix::Repository, a: ObjectId, b: ObjectId) -> Result<(usize, usize)> {
+    Ok((count(repo, a, [b])?, count(repo, b, [a])?))
+}
+
+/// Commits reachable from `tip` and not from `hidden`, counted from the ids of the walk without
+/// decoding them.
+fn count(
+    repo: &gix::Repository,
+    tip: ObjectId,
+    hidden: impl IntoIterator<Item = ObjectId>,
+) -> Result<usize> {
+    let walk = repo
+        .rev_walk([tip])
+        .with_hidden(hidden)
+        .sorting(Sorting::ByCommitTime(Default::default()))
+        .all()?;
+    let mut commits = 0;
+    for info in walk {
+        info?;
+        commits += 1;
+    }
+    Ok(commits)
+}
+
+/// The branch of `origin/HEAD`, or else a local `main` or `master`.
+pub fn default_branch(repo: &gix::Repository) -> Option<RefInfo> {
+    let remote_head = repo
+        .find_reference("refs/remotes/origin/HEAD")
+        .ok()
+        .and_then(|r| r.target().try_name().map(|name| name.to_owned()));
+    let candidates = remote_head
+        .into_iter()
+        .chain(["refs/heads/main", "refs/heads/master"].map(|name| name.try_into().unwrap()));
+    candidates
+        .into_iter()
+        .find_map(|name: gix::refs::FullName| {
+            let mut reference = repo.find_reference(name.as_ref()).ok()?;
+            Some(RefInfo {
+                kind: match name.category() {
+                    Some(gix::refs::Category::RemoteBranch) => RefKind::Remote,
+                    _ => RefKind::Branch,
+                },
+                name: name.as_ref().shorten().to_str_lossy().into_owned(),
+                target: reference.peel_to_id_in_place().ok()?.detach(),
+            })
+        })
+}
+
+/// Commits of local branch `name` in neither HEAD nor its upstream, which deleting it would
+/// lose, as `git branch -d` checks.
+pub fn unmerged(repo: &gix::Repository, name: &str) -> Result<usize> {
+    let mut reference = repo.find_reference(format!("refs/heads/{name}").as_str())?;
+    let full_name = reference.name().to_owned();
+    let target = reference.peel_to_id_in_place()?.detach();
+    let mut hidden: Vec<_> = repo
+        .head_id()
+        .ok()
+        .map(|id| id.detach())
+        .into_iter()
+        .collect();
+    let upstream = repo
+        .branch_remote_tracking_ref_name(full_name.as_ref(), gix::remote::Direction::Fetch)
+        .and_then(Result::ok)
+        .and_then(|name| {
+            repo.find_reference(name.as_ref())
+                .ok()?
+                .peel_to_id_in_place()
+                .ok()
+        });
+    hidden.extend(upstream.map(|id| id.detach()));
+    count(repo, target, hidden)
+}
+
+/// Where a local branch stands against its upstream and the default branch.
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    pub branch: RefInfo,
+    /// The upstream branch with the commits ahead of it and behind it.
+    pub upstream: Option<(String, usize, usize)>,
+    /// Commits ahead of the default branch and behind it.
+    pub default: Option<(usize, usize)>,
+}
+
+/// Divergence of every local branch of `repo` from its upstream and from `default`.
+pub fn divergence(repo: &gix::Repository, default: Option<&RefInfo>) -> Result<Vec<Divergence>> {
+    let mut branches = Vec::new();
+    for reference in repo.references()?.local_branches()? {
+        let Ok(mut reference) = reference else {
+            continue;
+        };
+        let full_name = reference.name().to_owned();
+        let Ok(target) = reference.peel_to_id_in_place() else {
+            continue;
+        };
+        let branch = RefInfo {
+            kind: RefKind::Branch,
+            name: full_name.as_ref().shorten().to_str_lossy().into_owned(),
+            target: target.detach(),
+        };
+        let upstream = repo
+            .branch_remote_tracking_ref_name(full_name.as_ref(), gix::remote::Direction::Fetch)
+            .and_then(Result::ok)
+            .and_then(|name| {
+                let mut upstream = repo.find_reference(name.as_ref()).ok()?;
+                let id = upstream.peel_to_id_in_place().ok()?.detach();
+                Some((name.shorten().to_str_lossy().into_owned(), id))
+            });
+        let upstream = match upstream {
+            Some((name, id)) => {
+                let (ahead, behind) = ahead_behind(repo, branch.target, id)?;
+                Some((name, ahead, behind))
+            }
+            None => None,
+        };
+        let default = match default {
+            Some(default) => Some(ahead_behind(repo, branch.target, default.target)?),
+            None => None,
+        };
+        branches.push(Divergence {
+            branch,
+            upstream,
+            default,
+        });
+    }
+    Ok(branches)
+}
+
+/// Nearest tag preceding commit `id` with its distance, like `git describe --tags`.
+pub fn describe(repo: &gix::Repository, id: ObjectId) -> Result<Option<String>> {
+    let commit = repo.find_commit(id)?;
+    let format = commit
+        .describe()
+        .names(SelectRef::AllTags)
+        .id_as_fallback(false)
+        .try_format()?;
+    Ok(format.map(|f| f.to_string()))
+}
+
+/// How a commit stands against HEAD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    Head,
+    /// Already in HEAD.
+    Ancestor,
+    /// Not in HEAD, but HEAD is in it.
+    Descendant,
+    /// Neither, forked from HEAD at the merge base, when they share any history.
+    Diverged(Option<ObjectId>),
+}
+
+impl Relation {
+    /// What the relation means, merge bases abbreviated to `abbrev` characters.
+    pub fn describe(self, abbrev: usize) -> String {
+        match self {
+            Relation::Head => "HEAD".into(),
+            Relation::Ancestor => "in HEAD".into(),
+            Relation::Descendant => "ahead of HEAD".into(),
+            Relation::Diverged(Some(base)) => {
+                format!("diverged from HEAD at {}", base.to_hex_with_len(abbrev))
+            }
+            Relation::Diverged(None) => "unrelated to HEAD".into(),
+        }
+    }
+}
+
+/// How commit `id` of `repo` stands against its HEAD.
+pub fn relation(repo: &gix::Repository, id: ObjectId) -> Result<Relation> {
+    let head = repo.head_id()?.detach();
+    if head == id {
+        return Ok(Relation::Head);
+    }
+    Ok(match repo.merge_base(id, head) {
+        Ok(base) if base == id => Relation::Ancestor,
+        Ok(base) if base == head => Relation::Descendant,
+        Ok(base) => Relation::Diverged(Some(base.detach())),
+        Err(gix::repository::merge_base::Error::NotFound { .. }) => Relation::Diverged(None),
+        Err(err) => return Err(err.into()),
+    })
+}
+
+#[derive(Clone, Debug)]
+pub enum Signature {
+    /// Verified by `git verify-tag`, with the first line of its report.
+    Good(String),
+    Bad(String),
+    /// The tag object couldn't be read, with why.
+    Unverifiable(String),
+}
+
+/// An annotated tag pointing to a commit.
+#[derive(Clone, Debug)]
+pub struct TagInfo {
+    /// The tag object.
+    pub id: ObjectId,
+    /// Short name of the reference, like `v1.0`, rather than the one in the tag object.
+    pub name: String,
+    pub tagger: Option<String>,
+    pub time: Option<String>,
+    pub message: BString,
+    /// Whether the tag is signed, see [`verify_tag`].
+    pub signed: bool,
+    /// Why the tag object couldn't be read, the other fields being left empty.
+    pub unreadable: Option<String>,
+}
+
+/// Annotated tags whose target peels to commit `id`.
+pub fn annotated_tags(repo: &gix::Repository, id: ObjectId) -> Result<Vec<TagInfo>> {
+    let mut tags = Vec::new();
+    for reference in repo.references()?.tags()? {
+        let Ok(mut reference) = reference else {
+            continue;
+        };
+        let Some(tag_id) = reference.target().try_id().map(ToOwned::to_owned) else {
+            continue;
+        };
+        if reference
+            .peel_to_id_in_place()
+            .ok()
+            .is_none_or(|peeled| peeled != id)
+        {
+            continue;
+        }
+        let name = reference.name().shorten().to_str_lossy().into_owned();
+        match read_tag(repo, tag_id, &name) {
+            Ok(Some(tag)) => tags.push(tag),
+            // a lightweight tag
+            Ok(None) => {}
+            Err(err) => tags.push(TagInfo {
+                id: tag_id,
+                name,
+                tagger: None,
+                time: None,
+                message: BString::default(),
+                signed: false,
+                unreadable: Some(err.to_string()),
+            }),
+        }
+    }
+    Ok(tags)
+}
+
+/// The tag object `id` of the tag `name`, `None` for another kind of object.
+fn read_tag(repo: &gix::Repository, id: ObjectId, name: &str) -> Result<Option<TagInfo>> {
+    let Ok(tag) = repo.find_object(id)?.try_into_tag() else {
+        return Ok(None);
+    };
+    let tag = tag.decode()?;
+    Ok(Some(TagInfo {
+        id,
+        name: name.to_owned(),
+        tagger: tag.tagger.map(|t| format!("{} <{}>", t.name, t.email)),
+        time: tag
+            .tagger
+            .and_then(|t| t.time().ok())
+            .map(|t| t.format(ISO8601)),
+        message: tag.message.to_owned(),
+        signed: tag.pgp_signature.is_some(),
+        unreadable: None,
+    }))
+}
+
+/// The signature of the tag `name`, like `v1.0`, of the repository at `git_dir`, as
+/// `git verify-tag` finds it.
+pub fn verify_tag(git_dir: &Path, name: &str) -> Signature {
+    let output = Command::new("git")
+        .arg("verify-tag")
+        .arg(format!("refs/tags/{name}"))
+        .current_dir(git_dir)
+        .output();
+    match output {
+        Ok(output) => {
+            let report = String::from_utf8_lossy(&output.stderr);
+            let first = report
+                .lines()
+                .find(|line| line.contains("signature"))
+                .or(report.lines().next())
+                .unwrap_or_default()
+                .to_owned();
+            if output.status.success() {
+                Signature::Good(first)
+            } else {
+                Signature::Bad(first)
+            }
+        }
+        Err(err) => Signature::Bad(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gix::refs::transaction::PreviousValue;
+    use gixl_core::fixture::Fixture;
+
+    use super::*;
+
+    const ALICE: (&str, &str) = ("Alice", "alice@example.com");
+
+    #[test]
+    fn divergence_from_the_default_branch() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init(dir.path())?;
+        let base = fixture.commit(ALICE, "Add README", &[("README", "gixl\n")])?;
+        let lexer = fixture.commit(ALICE, "Add the lexer", &[("lexer.rs", "")])?;
+        fixture.reset(base);
+        fixture.commit(ALICE, "Add the parser", &[("parser.rs", "")])?;
+        let parser =
+            fixture.commit(ALICE, "Fix the parser", &[("parser.rs", "fn parse() {}\n")])?;
+        let repo = fixture.root("").repo;
+        repo.reference("refs/heads/lexer", lexer, PreviousValue::Any, "branch")?;
+        assert_eq!(ahead_behind(&repo, parser, lexer)?, (2, 1));
+
+        let default = RefInfo {
+            kind: RefKind::Branch,
+            name: "lexer".into(),
+            target: lexer,
+        };
+        let branches = divergence(&repo, Some(&default))?;
+        let of = |target| {
+            let branch = branches.iter().find(|div| div.branch.target == target);
+            branch.and_then(|div| div.default)
+        };
+        assert_eq!(of(lexer), Some((0, 0)));
+        assert_eq!(of(parser), Some((2, 1)));
+        Ok(())
+    }
+
+    #[test]
+    fn annotated_tags_go_by_reference_name() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init(dir.path())?;
+        let id = fixture.commit(ALICE, "Add README", &[("README", "gixl\n")])?;
+        let repo = fixture.root("").repo;
+        let kind = gix::object::Kind::Commit;
+        let tag = repo.tag("v1.0", id, kind, None, "Release 1.0", PreviousValue::Any)?;
+        let tag_id = tag.id().detach();
+        repo.reference("refs/tags/stable", tag_id, PreviousValue::Any, "tag")?;
+
+        let mut tags: Vec<_> = annotated_tags(&repo, id)?
+            .into_iter()
+            .map(|tag| (tag.name, tag.id, tag.unreadable))
+            .collect();
+        tags.sort();
+        assert_eq!(
+            tags,
+            [
+                ("stable".to_owned(), tag_id, None),
+                ("v1.0".to_owned(), tag_id, None),
+            ]
+        );
+        Ok(())
+    }
+}
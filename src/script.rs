@@ -0,0 +1,145 @@
+//! Columns computed by Rhai scripts from the metadata of each commit, configured with
+//! `NAME.script` or `NAME.script-file` in `[columns]`.
+//!
+//! A script sees the commit as constants: `id`, `subject`, `message`, `author`, `email`,
+//! `committer`, `committer_email`, `time` and `committer_time` in seconds since the epoch,
+//! `parents`, an array of ids, and `source`, the label of its repository. It evaluates to the
+//! text of the cell, `()` for none, or to a map of its `text` with a `color`, `bold` or
+//! `italic`:
+//!
+//! ```rhai
+//! if parents.len() > 1 { #{ text: "merge", color: "magenta", bold: true } }
+//! ```
+
+use std::{collections::HashMap, sync::Mutex};
+
+use gix::{ObjectId, bstr::ByteSlice};
+use ratatui::style::{Style, Stylize};
+use rhai::{Dynamic, Engine, Scope};
+
+use crate::{log::LogEntryInfo, source::Source};
+
+/// Operations a script may run for one commit, so that a loop can't freeze the list.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// A compiled script, with the cells it made.
+pub struct Script {
+    engine: Engine,
+    ast: rhai::AST,
+    /// Cells already made, by commit: the list draws them again and again.
+    cells: Mutex<HashMap<ObjectId, Option<(String, Style)>>>,
+}
+
+impl std::fmt::Debug for Script {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Script").finish_non_exhaustive()
+    }
+}
+
+impl Script {
+    pub fn compile(code: &str) -> Result<Script, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // the UI owns the terminal
+        engine.on_print(|_| {});
+        engine.on_debug(|_, _, _| {});
+        let ast = engine.compile(code).map_err(|err| err.to_string())?;
+        Ok(Script {
+            engine,
+            ast,
+            cells: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The cell of `entry` of `source`, in `style` unless the script tells another, or the
+    /// error of the script in red.
+    pub fn cell(
+        &self,
+        entry: &LogEntryInfo,
+        source: &Source,
+        style: Style,
+    ) -> Option<(String, Style)> {
+        if let Some(cell) = self.cells.lock().ok()?.get(&entry.id) {
+            return cell.clone();
+        }
+        let mut scope = Scope::new();
+        let subject = entry.message.lines().next().unwrap_or_default();
+        scope.push_constant("id", entry.id.to_string());
+        scope.push_constant("subject", subject.to_str_lossy().into_owned());
+        scope.push_constant("message", entry.message.to_str_lossy().into_owned());
+        scope.push_constant("author", entry.author.to_str_lossy().into_owned());
+        scope.push_constant("email", entry.email.to_str_lossy().into_owned());
+        scope.push_constant("committer", entry.committer.to_str_lossy().into_owned());
+        let committer_email = entry.committer_email.to_str_lossy().into_owned();
+        scope.push_constant("committer_email", committer_email);
+        scope.push_constant("time", entry.author_time.seconds);
+        scope.push_constant("committer_time", entry.committer_time.seconds);
+        let parents: rhai::Array = entry
+            .parents
+            .iter()
+            .map(|id| Dynamic::from(id.to_string()))
+            .collect();
+        scope.push_constant("parents", parents);
+        scope.push_constant("source", source.label());
+        let value = self
+            .engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+            .map_err(|err| err.to_string());
+        let cell = value
+            .and_then(|value| cell(value, style))
+            .unwrap_or_else(|err| Some((format!("error: {err}"), Style::new().red())));
+        self.cells.lock().ok()?.insert(entry.id, cell.clone());
+        cell
+    }
+}
+
+/// The cell a script evaluated to, in `style` unless it tells another.
+fn cell(value: Dynamic, style: Style) -> Result<Option<(String, Style)>, String> {
+    if value.is_unit() {
+        return Ok(None);
+    }
+    let map = match value.try_cast_result::<rhai::Map>() {
+        Ok(map) => map,
+        Err(value) => return Ok(Some((value.to_string(), style))),
+    };
+    let (mut text, mut style) = (String::new(), style);
+    for (key, value) in map {
+        match (key.as_str(), value.as_bool()) {
+            ("text", _) => text = value.to_string(),
+            ("color", _) => {
+                let color = value.to_string();
+                let color = color
+                    .parse()
+                    .map_err(|_| format!("invalid color {color}"))?;
+                style = style.fg(color);
+            }
+            ("bold", Ok(true)) => style = style.bold(),
+            ("italic", Ok(true)) => style = style.italic(),
+            ("bold" | "italic", _) => {}
+            (key, _) => return Err(format!("unknown key {key}")),
+        }
+    }
+    Ok(Some((text, style)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_of_values() {
+        let style = Style::new();
+        assert_eq!(cell(Dynamic::UNIT, style), Ok(None));
+        assert_eq!(
+            cell(Dynamic::from("merge"), style),
+            Ok(Some(("merge".into(), style)))
+        );
+        let engine = Engine::new();
+        let map = engine.eval::<Dynamic>(r#"#{ text: "wip", color: "red", bold: true }"#);
+        assert_eq!(
+            cell(map.unwrap(), style),
+            Ok(Some(("wip".into(), Style::new().red().bold())))
+        );
+        assert!(Script::compile("if {").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use std::{path::Path, process::Command};
+
+use color_eyre::Result;
+
+use crate::{
+    config::Config,
+    json::{self, Value},
+    output::{self, json_string},
+    source::Source,
+    view::Item,
+};
+
+/// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+type Reply = std::result::Result<String, (i64, String)>;
+
+/// What the server answers from: the merged log, and every repository it comes from.
+struct State<'a, 'repo> {
+    entries: &'a [Item<'repo>],
+    sources: &'a [Source<'repo>],
+    config: &'a Config,
+}
+
+impl State<'_, '_> {
+    /// `log`: the entries, newest first, from `skip` and at most `limit` of them, of the
+    /// repository `source` only when given.
+    fn log(&self, params: &Value) -> Reply {
+        let skip = params.get("skip").and_then(Value::as_usize).unwrap_or(0);
+        let limit = params.get("limit").and_then(Value::as_usize);
+        let source = params.get("source").and_then(Value::as_str);
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(_, s)| source.is_none_or(|label| s.label() == label))
+            .skip(skip)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|(entry, source)| output::json(entry, source, self.config))
+            .collect();
+        Ok(format!("[{}]", entries.join(",")))
+    }
+
+    /// Repository of the label `source`, the first one by default.
+    fn source(&self, params: &Value) -> std::result::Result<&Source<'_>, (i64, String)> {
+        match params.get("source").and_then(Value::as_str) {
+            Some(label) => self
+                .sources
+                .iter()
+                .find(|s| s.label() == label)
+                .ok_or_else(|| (INVALID_PARAMS, format!("unknown source {label}"))),
+            None => self
+                .sources
+                .first()
+                .ok_or_else(|| (SERVER_ERROR, "no repository".into())),
+        }
+    }
+
+    /// `resolve`: the commit `rev` names in `source`, and whether the log lists it.
+    fn resolve(&self, params: &Value) -> Reply {
+        let rev = params
+            .get("rev")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "expected rev".into()))?;
+        let source = self.source(params)?;
+        let resolve = || -> Result<gix::ObjectId> {
+            let repo = source.open()?;
+            Ok(repo.rev_parse_single(rev)?.object()?.peel_to_commit()?.id)
+        };
+        let id = resolve().map_err(|err| (SERVER_ERROR, format!("{rev}: {err}")))?;
+        let listed = self.entries.iter().any(|(e, _)| e.id == id);
+        Ok(format!("{{\"id\":\"{id}\",\"listed\":{listed}}}"))
+    }
+
+    /// `diff`: the patch of the listed commit `id`, which may be abbreviated.
+    fn diff(&self, params: &Value) -> Reply {
+        let id = params
+            .get("id")
+            .and_then(Value::as_str)
+            .filter(|id| id.len() >= 4)
+            .ok_or((
+                INVALID_PARAMS,
+                "expected id, of 4 characters or more".into(),
+            ))?;
+        let (entry, source) = self
+            .entries
+            .iter()
+            .find(|(e, _)| e.id.to_string().starts_with(id))
+            .ok_or_else(|| (INVALID_PARAMS, format!("{id} is not listed")))?;
+        let output = Command::new("git")
+            .args(["show", "--no-color", "--format="])
+            .arg(entry.id.to_string())
+            .current_dir(source.dir())
+            .output()
+            .map_err(|err| (SERVER_ERROR, format!("git: {err}")))?;
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err((SERVER_ERROR, err.trim().to_owned()));
+        }
+        Ok(format!(
+            "{{\"id\":\"{}\",\"source\":{},\"patch\":{}}}",
+            entry.id,
+            json_string(&source.label()),
+            json_string(&String::from_utf8_lossy(&output.stdout)),
+        ))
+    }
+
+    /// The reply to a request, `None` for notifications, which have no id.
+    fn handle(&self, line: &str) -> Option<String> {
+        let request = match json::parse(line) {
+            Ok(request) => request,
+            Err(err) => return Some(response(&Value::Null, Err((PARSE_ERROR, err)))),
+        };
+        let id = request.get("id").cloned();
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        let reply = match request.get("method").and_then(Value::as_str) {
+            Some("log") => self.log(&params),
+            Some("resolve") => self.resolve(&params),
+            Some("diff") => self.diff(&params),
+            Some(method) => Err((METHOD_NOT_FOUND, format!("unknown method {method}"))),
+            None => Err((INVALID_REQUEST, "expected a method".into())),
+        };
+        Some(response(&id?, reply))
+    }
+}
+
+fn response(id: &Value, reply: Reply) -> String {
+    match reply {
+        Ok(result) => format!(
+            "{{\"jsonrpc\":\"2.0\",\"id\":{},\"result\":{result}}}",
+            id.to_json()
+        ),
+        Err((code, message)) => format!(
+            "{{\"jsonrpc\":\"2.0\",\"id\":{},\"error\":{{\"code\":{code},\"message\":{}}}}}",
+            id.to_json(),
+            json_string(&message),
+        ),
+    }
+}
+
+/// Answer JSON-RPC requests, one per line, on the Unix socket at `path`, one client at a time.
+///
+/// Methods are `log` (`skip`, `limit` and `source`), `resolve` (`rev` and `source`) and `diff`
+/// (`id`).
+#[cfg(unix)]
+pub fn serve(path: &Path, entries: &[Item], sources: &[Source], config: &Config) -> Result<()> {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        os::unix::{
+            fs::FileTypeExt,
+            net::{UnixListener, UnixStream},
+        },
+    };
+
+    // a socket left behind by a server which didn't exit cleanly
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            color_eyre::eyre::bail!("{} exists and is not a socket", path.display());
+        }
+        if UnixStream::connect(path).is_err() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    let listener = UnixListener::bind(path)?;
+    let state = State {
+        entries,
+        sources,
+        config,
+    };
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let mut writer = &stream;
+        for line in BufReader::new(&stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(reply) = state.handle(&line)
+                && writeln!(writer, "{reply}").is_err()
+            {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn serve(_path: &Path, _entries: &[Item], _sources: &[Source], _config: &Config) -> Result<()> {
+    color_eyre::eyre::bail!("--listen needs Unix sockets")
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use color_eyre::Result;
+use gix::ObjectId;
+
+use crate::source::Root;
+
+/// What is restored when the same repositories are listed again.
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    pub selected: Option<ObjectId>,
+    /// Index of the first visible entry.
+    pub offset: usize,
+    pub find_object: Option<ObjectId>,
+    pub function: Option<(String, String)>,
+    /// HEAD of each repository and submodule, by label, to tell which commits are new.
+    pub newest: Vec<(String, ObjectId)>,
+}
+
+/// `$XDG_STATE_HOME/gixl`, defaulting to `~/.local/state/gixl`.
+fn state_dir() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::home_dir()?.join(".local/state"),
+    };
+    Some(dir.join("gixl"))
+}
+
+/// The session file of this set of repositories, named after a hash of their git directories.
+pub fn path(roots: &[Root]) -> Option<PathBuf> {
+    let mut hasher = gix::hash::hasher(gix::hash::Kind::Sha1);
+    for root in roots {
+        let git_dir = root.repo.git_dir();
+        let git_dir = git_dir.canonicalize().unwrap_or(git_dir.to_path_buf());
+        hasher.update(git_dir.as_os_str().as_encoded_bytes());
+        hasher.update(b"\0");
+    }
+    Some(state_dir()?.join(hasher.try_finalize().ok()?.to_string()))
+}
+
+/// The saved session, or the default one when there is none or it can't be read.
+pub fn load(path: &Path) -> Session {
+    let mut session = Session::default();
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return session;
+    };
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once(' ') else {
+            continue;
+        };
+        match key {
+            "selected" => session.selected = value.parse().ok(),
+            "offset" => session.offset = value.parse().unwrap_or_default(),
+            "find-object" => session.find_object = value.parse().ok(),
+            "newest" => {
+                let (id, label) = value.split_once(' ').unwrap_or((value, ""));
+                if let Ok(id) = id.parse() {
+                    session.newest.push((label.to_owned(), id));
+                }
+            }
+            "function" => {
+                session.function = value
+                    .split_once(':')
+                    .map(|(name, path)| (name.to_owned(), path.to_owned()))
+            }
+            _ => {}
+        }
+    }
+    session
+}
+
+pub fn save(path: &Path, session: &Session) -> Result<()> {
+    let mut text = String::new();
+    if let Some(id) = session.selected {
+        text += &format!("selected {id}\n");
+    }
+    text += &format!("offset {}\n", session.offset);
+    if let Some(id) = session.find_object {
+        text += &format!("find-object {id}\n");
+    }
+    if let Some((name, file)) = &session.function {
+        text += &format!("function {name}:{file}\n");
+    }
+    for (label, id) in &session.newest {
+        text += &format!("newest {id} {label}\n");
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u8) -> ObjectId {
+        ObjectId::from_bytes_or_panic(&[n; 20])
+    }
+
+    #[test]
+    fn save_then_load() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("state/session");
+        let session = Session {
+            selected: Some(id(1)),
+            offset: 12,
+            find_object: Some(id(2)),
+            function: Some(("parse".into(), "src/parse.rs".into())),
+            newest: vec![("".into(), id(3)), ("lib".into(), id(4))],
+        };
+        save(&path, &session)?;
+        let loaded = load(&path);
+        assert_eq!(loaded.selected, session.selected);
+        assert_eq!(loaded.offset, 12);
+        assert_eq!(loaded.find_object, session.find_object);
+        assert_eq!(loaded.function, session.function);
+        assert_eq!(loaded.newest, session.newest);
+        Ok(())
+    }
+
+    #[test]
+    fn load_what_can_be_read() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let loaded = load(&dir.path().join("missing"));
+        assert_eq!((loaded.selected, loaded.offset), (None, 0));
+
+        // lines of other versions and invalid values are skipped
+        let path = dir.path().join("session");
+        std::fs::write(
+            &path,
+            "selected nope\noffset 3\nsort author\nfind-object 12\n",
+        )?;
+        let loaded = load(&path);
+        assert_eq!((loaded.selected, loaded.offset), (None, 3));
+        assert_eq!(loaded.find_object, None);
+        Ok(())
+    }
+}
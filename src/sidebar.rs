@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+
+use gix::ObjectId;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    refs::{self, RefKind},
+    source::Root,
+    width,
+};
+
+enum Row {
+    Header(String),
+    Ref {
+        name: String,
+        target: ObjectId,
+        /// Whether the walk of the log reaches it.
+        listed: bool,
+    },
+}
+
+/// HEAD, branches, tags and remote-tracking branches of the roots, on the left of the list.
+pub struct Sidebar {
+    rows: Vec<Row>,
+    state: ListState,
+}
+
+impl Sidebar {
+    /// The references of `roots`, those pointing to a commit of `listed` being marked.
+    pub fn new(roots: &[Root], listed: &HashSet<ObjectId>) -> Sidebar {
+        let reference = |name: String, target: ObjectId| Row::Ref {
+            name,
+            target,
+            listed: listed.contains(&target),
+        };
+        let mut rows = Vec::new();
+        for root in roots {
+            if !root.name.is_empty() {
+                rows.push(Row::Header(root.name.clone()));
+            }
+            if let Ok(head) = root.repo.head_id() {
+                rows.push(reference("HEAD".into(), head.detach()));
+            }
+            let mut refs = refs::list(&root.repo).unwrap_or_default();
+            for (kind, title) in [
+                (RefKind::Branch, "branches"),
+                (RefKind::Tag, "tags"),
+                (RefKind::Remote, "remotes"),
+            ] {
+                let mut section: Vec<_> = refs.extract_if(.., |r| r.kind == kind).collect();
+                if section.is_empty() {
+                    continue;
+                }
+                section.sort_by(|a, b| a.name.cmp(&b.name));
+                rows.push(Row::Header(title.into()));
+                rows.extend(section.into_iter().map(|r| reference(r.name, r.target)));
+            }
+        }
+        let mut sidebar = Sidebar {
+            rows,
+            state: ListState::default(),
+        };
+        sidebar.next();
+        sidebar
+    }
+
+    fn is_ref(&self, i: usize) -> bool {
+        matches!(self.rows.get(i), Some(Row::Ref { .. }))
+    }
+
+    /// Select the next reference, skipping headers.
+    pub fn next(&mut self) {
+        let start = self.state.selected().map_or(0, |i| i + 1);
+        if let Some(i) = (start..self.rows.len()).find(|i| self.is_ref(*i)) {
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let end = self.state.selected().unwrap_or(0);
+        if let Some(i) = (0..end).rev().find(|i| self.is_ref(*i)) {
+            self.state.select(Some(i));
+        }
+    }
+
+    /// Name and target of the selected reference.
+    pub fn selected(&self) -> Option<(&str, ObjectId)> {
+        match self.rows.get(self.state.selected()?)? {
+            Row::Ref { name, target, .. } => Some((name, *target)),
+            Row::Header(_) => None,
+        }
+    }
+
+    /// Width fitting the longest name.
+    pub fn width(&self) -> u16 {
+        let longest = self.rows.iter().map(|row| match row {
+            Row::Header(title) => width::width(title),
+            Row::Ref { name, .. } => width::width(name) + 2,
+        });
+        longest.max().unwrap_or(0) as u16 + 2
+    }
+
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let items: Vec<_> = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Header(title) => {
+                    ListItem::new(Line::styled(title.clone(), Style::new().bold().cyan()))
+                }
+                // the references the log doesn't reach are dimmed
+                Row::Ref {
+                    name, listed: true, ..
+                } => ListItem::new(Line::from(vec![
+                    Span::styled("• ", Style::new().green()),
+                    Span::raw(name.clone()),
+                ])),
+                Row::Ref { name, .. } => {
+                    ListItem::new(Line::styled(format!("  {name}"), Style::new().dark_gray()))
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::new().borders(Borders::RIGHT))
+            .highlight_style(Style::new().reversed());
+        f.render_stateful_widget(list, area, &mut self.state);
+    }
+}
@@ -0,0 +1,150 @@
+//! Work done away from the UI, which keeps drawing and reading keys meanwhile: each task runs on
+//! a thread of its own, what it found coming back as an [`AppEvent`], among the events of the
+//! terminal.
+
+use std::{
+    collections::HashMap,
+    sync::mpsc::{Receiver, Sender, channel},
+    time::Duration,
+};
+
+use color_eyre::Result;
+use crossterm::event::{self, Event};
+use gix::ObjectId;
+use ratatui::text::Line;
+
+use crate::{
+    diff::FileDiff,
+    forge::Request,
+    refs::{Divergence, RefInfo, Relation, Signature},
+};
+
+/// Time the terminal is waited for while tasks run, before looking for what they found again.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Lines of standard input, for [`Tasks::stream`]: each batch holds those which came while the
+/// previous one was being taken, so that a fast producer doesn't cost a draw per line.
+pub fn stdin(emit: &dyn Fn(AppEvent) -> bool) -> AppEvent {
+    let (sender, lines) = channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines().map_while(std::io::Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    while let Ok(line) = lines.recv() {
+        let batch = std::iter::once(line).chain(lines.try_iter()).collect();
+        if !emit(AppEvent::Stdin(batch)) {
+            break;
+        }
+        std::thread::sleep(TICK);
+    }
+    AppEvent::Stdin(Vec::new())
+}
+
+/// What the UI acts on.
+pub enum AppEvent {
+    /// A key pressed, a click or a resize.
+    Terminal(Event),
+    /// The files changed by commit `id`, for the diff pane or to pick one.
+    Diff(ObjectId, DiffUse, Result<Vec<FileDiff>>),
+    /// The signature of the tag object `id`, verified.
+    Signature(ObjectId, Signature),
+    /// The `git describe` of commit `id`.
+    Describe(ObjectId, Option<String>),
+    /// How commit `id` stands against HEAD, as found before HEAD moved so many times.
+    Relation(u64, ObjectId, Option<Relation>),
+    /// The summary of the entries of a tab, the one waiting for this number.
+    Summary(u64, Line<'static>),
+    /// The patch ids of commits, `None` for merges and those which couldn't be diffed.
+    Patches(Vec<(ObjectId, Option<ObjectId>)>),
+    /// The pull or merge requests which introduced the entries of the log.
+    Requests(HashMap<ObjectId, Request>),
+    /// The local branches of each repository, by index, with how far they diverged from their
+    /// upstream and from the default branch.
+    Branches(Vec<(usize, Vec<Divergence>, Option<RefInfo>)>),
+    /// Lines read from standard input with `--stdin`, since the previous ones; none once it is
+    /// closed.
+    Stdin(Vec<String>),
+}
+
+/// What the files changed by a commit are listed for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffUse {
+    Pane,
+    Finder,
+}
+
+/// The tasks running.
+pub struct Tasks {
+    /// What tasks find, with whether they are done.
+    sender: Sender<(AppEvent, bool)>,
+    events: Receiver<(AppEvent, bool)>,
+    running: usize,
+}
+
+impl Tasks {
+    pub fn new() -> Tasks {
+        let (sender, events) = channel();
+        Tasks {
+            sender,
+            events,
+            running: 0,
+        }
+    }
+
+    /// Run `task` on a thread of its own, what it returns being given by [`Tasks::next`].
+    pub fn spawn(&mut self, task: impl FnOnce() -> AppEvent + Send + 'static) {
+        self.running += 1;
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let _ = sender.send((task(), true));
+        });
+    }
+
+    /// Run `task` on a thread of its own, what it gives to its `emit` argument as it goes being
+    /// given by [`Tasks::next`], and then what it returns. It should stop once `emit` says the UI
+    /// is gone.
+    pub fn stream(
+        &mut self,
+        task: impl FnOnce(&dyn Fn(AppEvent) -> bool) -> AppEvent + Send + 'static,
+    ) {
+        self.running += 1;
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let emit = |event| sender.send((event, false)).is_ok();
+            let last = task(&emit);
+            let _ = sender.send((last, true));
+        });
+    }
+
+    pub fn running(&self) -> bool {
+        self.running > 0
+    }
+
+    /// What a task found, or else what happens on the terminal, waited for unless tasks are
+    /// running or the UI is to `wake` up: then `None` after a tick without anything.
+    pub fn next(&mut self, wake: bool) -> Result<Option<AppEvent>> {
+        if let Ok((event, done)) = self.events.try_recv() {
+            self.running -= usize::from(done);
+            return Ok(Some(event));
+        }
+        if (wake || self.running()) && !event::poll(TICK)? {
+            return Ok(None);
+        }
+        Ok(Some(AppEvent::Terminal(event::read()?)))
+    }
+
+    /// What the tasks running found, waiting for them all.
+    pub fn wait(&mut self) -> Vec<AppEvent> {
+        let mut events = Vec::new();
+        while self.running > 0
+            && let Ok((event, done)) = self.events.recv()
+        {
+            self.running -= usize::from(done);
+            events.push(event);
+        }
+        events
+    }
+}
@@ -0,0 +1,386 @@
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use ratatui::{buffer::Buffer, style::Color};
+
+/// Background of the terminal, deciding which palette is used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Background {
+    /// Asked to the terminal, falling back to `COLORFGBG`, then dark.
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl Background {
+    pub fn parse(value: &str) -> Option<Background> {
+        match value {
+            "auto" => Some(Background::Auto),
+            "light" => Some(Background::Light),
+            "dark" => Some(Background::Dark),
+            _ => None,
+        }
+    }
+}
+
+/// Colors the terminal can display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Depth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl Depth {
+    pub fn parse(value: &str) -> Option<Depth> {
+        match value {
+            "16" => Some(Depth::Ansi16),
+            "256" => Some(Depth::Ansi256),
+            "truecolor" | "24bit" => Some(Depth::TrueColor),
+            _ => None,
+        }
+    }
+
+    /// Guessed from `COLORTERM` and `TERM`.
+    pub fn detect() -> Depth {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            Depth::TrueColor
+        } else if term.contains("256color") {
+            Depth::Ansi256
+        } else {
+            Depth::Ansi16
+        }
+    }
+}
+
+/// Named colors used by the UI, replaced by the palette of the background.
+///
+/// The dark palette keeps the colors of the terminal, the light one darkens those which are
+/// hard to read on a light background.
+const LIGHT: &[(Color, Color)] = &[
+    (Color::Yellow, Color::Rgb(0x9a, 0x67, 0x00)),
+    (Color::Green, Color::Rgb(0x1a, 0x7f, 0x37)),
+    (Color::Cyan, Color::Rgb(0x1b, 0x7c, 0x83)),
+    (Color::Gray, Color::Rgb(0x57, 0x60, 0x6a)),
+    (Color::DarkGray, Color::Rgb(0x8c, 0x95, 0x9f)),
+    (Color::LightGreen, Color::Rgb(0xac, 0xee, 0xbb)),
+];
+
+#[derive(Clone, Debug)]
+pub struct Theme {
+    palette: Vec<(Color, Color)>,
+    depth: Depth,
+}
+
+impl Theme {
+    /// The palette of `background` with `overrides` applied, degraded to `depth`.
+    pub fn new(background: Background, overrides: &[(Color, Color)], depth: Depth) -> Theme {
+        let mut palette = match background {
+            Background::Light => LIGHT.to_vec(),
+            Background::Auto | Background::Dark => Vec::new(),
+        };
+        for (name, color) in overrides {
+            palette.retain(|(n, _)| n != name);
+            palette.push((*name, *color));
+        }
+        Theme { palette, depth }
+    }
+
+    fn color(&self, color: Color) -> Color {
+        let color = self
+            .palette
+            .iter()
+            .find(|(name, _)| *name == color)
+            .map_or(color, |(_, c)| *c);
+        degrade(color, self.depth)
+    }
+
+    /// Replace the colors of the cells of `buffer`.
+    pub fn apply(&self, buffer: &mut Buffer) {
+        for cell in &mut buffer.content {
+            cell.fg = self.color(cell.fg);
+            cell.bg = self.color(cell.bg);
+        }
+    }
+}
+
+/// The 16 ANSI colors with their usual xterm values.
+const ANSI: [(Color, [u8; 3]); 16] = [
+    (Color::Black, [0x00, 0x00, 0x00]),
+    (Color::Red, [0xcd, 0x00, 0x00]),
+    (Color::Green, [0x00, 0xcd, 0x00]),
+    (Color::Yellow, [0xcd, 0xcd, 0x00]),
+    (Color::Blue, [0x00, 0x00, 0xee]),
+    (Color::Magenta, [0xcd, 0x00, 0xcd]),
+    (Color::Cyan, [0x00, 0xcd, 0xcd]),
+    (Color::Gray, [0xe5, 0xe5, 0xe5]),
+    (Color::DarkGray, [0x7f, 0x7f, 0x7f]),
+    (Color::LightRed, [0xff, 0x00, 0x00]),
+    (Color::LightGreen, [0x00, 0xff, 0x00]),
+    (Color::LightYellow, [0xff, 0xff, 0x00]),
+    (Color::LightBlue, [0x5c, 0x5c, 0xff]),
+    (Color::LightMagenta, [0xff, 0x00, 0xff]),
+    (Color::LightCyan, [0x00, 0xff, 0xff]),
+    (Color::White, [0xff, 0xff, 0xff]),
+];
+
+/// Levels of the 6×6×6 color cube of the 256 colors palette.
+const CUBE: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (*a as i32 - b as i32).pow(2) as u32)
+        .sum()
+}
+
+/// RGB value of an entry of the 256 colors palette.
+fn indexed_rgb(i: u8) -> [u8; 3] {
+    match i {
+        0..16 => ANSI[i as usize].1,
+        16..232 => {
+            let i = i - 16;
+            [
+                CUBE[(i / 36) as usize],
+                CUBE[(i / 6 % 6) as usize],
+                CUBE[(i % 6) as usize],
+            ]
+        }
+        _ => [8 + (i - 232) * 10; 3],
+    }
+}
+
+/// Nearest entry of the 256 colors palette, in the color cube or the gray ramp.
+fn to_indexed(rgb: [u8; 3]) -> u8 {
+    let level = |v: u8| {
+        (0..6)
+            .min_by_key(|l| (CUBE[*l] as i32 - v as i32).abs())
+            .unwrap_or_default() as u8
+    };
+    let cube = 16 + 36 * level(rgb[0]) + 6 * level(rgb[1]) + level(rgb[2]);
+    let average = rgb.iter().map(|v| *v as u32).sum::<u32>() / 3;
+    let gray = 232 + ((average.saturating_sub(3)) / 10).min(23) as u8;
+    [cube, gray]
+        .into_iter()
+        .min_by_key(|i| distance(rgb, indexed_rgb(*i)))
+        .unwrap_or(cube)
+}
+
+fn to_ansi(rgb: [u8; 3]) -> Color {
+    ANSI.iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// `color` approximated with the colors the terminal can display.
+fn degrade(color: Color, depth: Depth) -> Color {
+    match (color, depth) {
+        (Color::Rgb(r, g, b), Depth::Ansi256) => Color::Indexed(to_indexed([r, g, b])),
+        (Color::Rgb(r, g, b), Depth::Ansi16) => to_ansi([r, g, b]),
+        (Color::Indexed(i), Depth::Ansi16) => to_ansi(indexed_rgb(i)),
+        (color, _) => color,
+    }
+}
+
+/// Background color from an OSC 11 reply, `ESC ] 11 ; rgb:RRRR/GGGG/BBBB`, each component having
+/// one to four hex digits.
+fn parse_reply(reply: &[u8]) -> Option<[u8; 3]> {
+    let reply = String::from_utf8_lossy(reply);
+    let (_, rgb) = reply.split_once("]11;rgb:")?;
+    let mut components = rgb.splitn(3, '/').map(|c| {
+        let digits: String = c.chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+        let value = u32::from_str_radix(&digits, 16).ok()?;
+        let max = (1u32 << (4 * digits.len().min(4))) - 1;
+        Some((value * 255 / max) as u8)
+    });
+    Some([
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    ])
+}
+
+/// Whether a query of the terminal went unanswered in time, its reply being possibly still to
+/// come, see [`drain_replies`].
+#[cfg(unix)]
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Time a late reply of the terminal is waited for by [`drain_replies`].
+#[cfg(unix)]
+const LATE: Duration = Duration::from_millis(500);
+
+/// Read the replies of `tty` to a query, into `reply`, until the device attributes one, last, or
+/// until none comes within `timeout`, telling whether it came.
+#[cfg(unix)]
+fn read_replies(tty: &mut std::fs::File, reply: &mut Vec<u8>, timeout: Duration) -> bool {
+    use std::io::Read;
+
+    use rustix::event::{PollFd, PollFlags, Timespec, poll};
+
+    let Ok(timeout) = Timespec::try_from(timeout) else {
+        return false;
+    };
+    // the device attributes reply is `ESC [ ? ... c`
+    while !reply.ends_with(b"c") || !reply.windows(3).any(|w| w == b"\x1b[?") {
+        let mut fds = [PollFd::new(&*tty, PollFlags::IN)];
+        if !matches!(poll(&mut fds, Some(&timeout)), Ok(ready) if ready > 0) {
+            return false;
+        }
+        let mut buf = [0; 64];
+        match tty.read(&mut buf) {
+            Ok(n) if n > 0 && reply.len() <= 256 => reply.extend_from_slice(&buf[..n]),
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Ask the terminal for its background color, which it must answer within `timeout`.
+///
+/// The query is followed by a device attributes one which every terminal answers, so that those
+/// ignoring OSC 11 don't make us wait. Raw mode must be enabled.
+#[cfg(unix)]
+fn query_background(timeout: Duration) -> Option<[u8; 3]> {
+    use std::io::Write;
+
+    let mut tty = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    tty.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    let mut reply = Vec::new();
+    if !read_replies(&mut tty, &mut reply, timeout) {
+        PENDING.store(true, Ordering::Relaxed);
+    }
+    parse_reply(&reply)
+}
+
+#[cfg(not(unix))]
+fn query_background(_timeout: Duration) -> Option<[u8; 3]> {
+    None
+}
+
+/// Discard the replies of the terminal to [`query_background`] which came too late, given a
+/// little more time, before the keys are read: they would be read as keys. Raw mode must be
+/// enabled.
+#[cfg(unix)]
+pub fn drain_replies() {
+    if !PENDING.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    let tty = std::fs::File::options().read(true).open("/dev/tty");
+    if let Ok(mut tty) = tty {
+        read_replies(&mut tty, &mut Vec::new(), LATE);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn drain_replies() {}
+
+/// `COLORFGBG`, set by some terminals as `FG;BG` with ANSI color numbers.
+fn colorfgbg() -> Option<Background> {
+    let value = std::env::var("COLORFGBG").ok()?;
+    let bg: u8 = value.rsplit(';').next()?.parse().ok()?;
+    Some(match bg {
+        0..=6 | 8 => Background::Dark,
+        _ => Background::Light,
+    })
+}
+
+/// Resolve [`Background::Auto`] by asking the terminal.
+pub fn detect(background: Background) -> Background {
+    if background != Background::Auto {
+        return background;
+    }
+    if let Some([r, g, b]) = query_background(Duration::from_millis(100)) {
+        let luma = 299 * r as u32 + 587 * g as u32 + 114 * b as u32;
+        return if luma > 500 * 255 {
+            Background::Light
+        } else {
+            Background::Dark
+        };
+    }
+    colorfgbg().unwrap_or(Background::Dark)
+}
+
+/// ASCII stand-in for the symbol of a cell: box drawing becomes `-`, `|` and `+`, arrows `>`.
+fn ascii(symbol: &str) -> Option<&'static str> {
+    let mut chars = symbol.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    Some(match c {
+        c if c.is_ascii() => return None,
+        '─' | '━' | '═' | '╌' | '╍' | '┄' | '┅' | '┈' | '┉' => "-",
+        '│' | '┃' | '║' | '╎' | '╏' | '┆' | '┇' | '┊' | '┋' => "|",
+        '\u{2500}'..='\u{257f}' => "+",
+        '\u{2580}'..='\u{259f}' | '■' | '●' => "#",
+        '…' => "~",
+        '→' | '↳' | '▶' | '►' | '»' => ">",
+        '←' | '◀' | '◄' | '«' => "<",
+        '↑' | '▲' => "^",
+        '↓' | '▼' => "v",
+        '•' | '·' => "*",
+        '✓' | '✔' => "v",
+        '✗' | '✘' => "x",
+        '“' | '”' => "\"",
+        '‘' | '’' => "'",
+        '–' | '—' => "-",
+        '≡' => "=",
+        '◇' => "o",
+        _ => return None,
+    })
+}
+
+/// Replace the unicode symbols of `buffer` by ASCII, for terminals and fonts lacking them.
+pub fn to_ascii(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        if let Some(symbol) = ascii(cell.symbol()) {
+            cell.set_symbol(symbol);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reply_scales_components() {
+        let reply = b"\x1b]11;rgb:ffff/8080/0000\x1b\\";
+        assert_eq!(parse_reply(reply), Some([0xff, 0x80, 0x00]));
+        assert_eq!(
+            parse_reply(b"\x1b]11;rgb:f/8/0\x07"),
+            Some([0xff, 0x88, 0x00])
+        );
+        // followed by the device attributes reply
+        let reply = b"\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\\x1b[?62;22c";
+        assert_eq!(parse_reply(reply), Some([0x1e, 0x1e, 0x2e]));
+    }
+
+    #[test]
+    fn parse_reply_needs_three_components() {
+        assert_eq!(parse_reply(b"\x1b[?62;22c"), None);
+        assert_eq!(parse_reply(b"\x1b]11;rgb:ffff/ffff\x1b\\"), None);
+        assert_eq!(parse_reply(b"\x1b]11;rgb:zz/00/00\x1b\\"), None);
+    }
+
+    #[test]
+    fn degrade_to_the_nearest_color() {
+        let orange = Color::Rgb(0xff, 0x87, 0x00);
+        assert_eq!(degrade(orange, Depth::TrueColor), orange);
+        assert_eq!(degrade(orange, Depth::Ansi256), Color::Indexed(208));
+        assert_eq!(
+            degrade(Color::Rgb(0x80, 0x80, 0x80), Depth::Ansi256),
+            Color::Indexed(244)
+        );
+        assert_eq!(degrade(orange, Depth::Ansi16), Color::Yellow);
+        assert_eq!(degrade(Color::Indexed(196), Depth::Ansi16), Color::LightRed);
+        assert_eq!(degrade(Color::Blue, Depth::Ansi16), Color::Blue);
+    }
+}
@@ -0,0 +1,213 @@
+use gix::ObjectId;
+use ratatui::{prelude::*, widgets::*};
+
+/// What an interactive rebase does with a commit.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Verb {
+    Pick,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+}
+
+impl Verb {
+    /// The verb of a key of the editor, its initial as in the todo list of git.
+    pub fn from_key(c: char) -> Option<Verb> {
+        Some(match c {
+            'p' => Verb::Pick,
+            'r' => Verb::Reword,
+            'e' => Verb::Edit,
+            's' => Verb::Squash,
+            'f' => Verb::Fixup,
+            'd' => Verb::Drop,
+            _ => return None,
+        })
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Verb::Pick => "pick",
+            Verb::Reword => "reword",
+            Verb::Edit => "edit",
+            Verb::Squash => "squash",
+            Verb::Fixup => "fixup",
+            Verb::Drop => "drop",
+        }
+    }
+
+    /// Whether the commit is folded into the one before.
+    fn folds(self) -> bool {
+        matches!(self, Verb::Squash | Verb::Fixup)
+    }
+}
+
+pub struct Step {
+    pub verb: Verb,
+    pub id: ObjectId,
+    pub subject: String,
+}
+
+/// The todo list of `git rebase -i` for some commits, oldest first, each picked, reworded,
+/// edited, squashed, fixed up or dropped, and reordered, drawn over the list.
+pub struct TodoEditor {
+    /// Commit the rebase starts from.
+    pub base: ObjectId,
+    pub steps: Vec<Step>,
+    /// Commits after those edited, picked again.
+    replayed: Vec<(ObjectId, String)>,
+    abbrev: usize,
+    state: ListState,
+}
+
+impl TodoEditor {
+    pub fn new(
+        base: ObjectId,
+        steps: Vec<Step>,
+        replayed: Vec<(ObjectId, String)>,
+        abbrev: usize,
+    ) -> TodoEditor {
+        TodoEditor {
+            base,
+            steps,
+            replayed,
+            abbrev,
+            state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    pub fn next(&mut self) {
+        if let Some(i) = self.state.selected()
+            && i + 1 < self.steps.len()
+        {
+            self.state.select(Some(i + 1));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    /// Move the selected step after the next one.
+    pub fn move_down(&mut self) {
+        if let Some(i) = self.state.selected()
+            && i + 1 < self.steps.len()
+        {
+            self.steps.swap(i, i + 1);
+            self.state.select(Some(i + 1));
+        }
+    }
+
+    /// Move the selected step before the previous one.
+    pub fn move_up(&mut self) {
+        if let Some(i) = self.state.selected()
+            && i > 0
+        {
+            self.steps.swap(i, i - 1);
+            self.state.select(Some(i - 1));
+        }
+    }
+
+    pub fn set(&mut self, verb: Verb) {
+        if let Some(i) = self.state.selected() {
+            self.steps[i].verb = verb;
+        }
+    }
+
+    /// Why git would refuse the plan: a commit can only be folded into one which is kept.
+    pub fn check(&self) -> Result<(), String> {
+        match self.steps.iter().find(|step| step.verb != Verb::Drop) {
+            Some(step) if step.verb.folds() => Err(format!(
+                "cannot {} {} without a previous commit",
+                step.verb.label(),
+                step.id.to_hex_with_len(self.abbrev)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The todo list handed to git.
+    pub fn todo(&self) -> String {
+        let steps = self
+            .steps
+            .iter()
+            .map(|step| (step.verb, step.id, step.subject.as_str()));
+        let replayed = self
+            .replayed
+            .iter()
+            .map(|(id, subject)| (Verb::Pick, *id, subject.as_str()));
+        steps
+            .chain(replayed)
+            .map(|(verb, id, subject)| format!("{} {id} {subject}\n", verb.label()))
+            .collect()
+    }
+
+    pub fn render(&mut self, f: &mut Frame) {
+        let area = f.area();
+        let width = (area.width * 4 / 5).max(20).min(area.width);
+        let height = (self.steps.len() as u16 + 5)
+            .min(area.height * 4 / 5)
+            .max(6)
+            .min(area.height);
+        let area = Rect::new(
+            area.x + (area.width - width) / 2,
+            area.y + (area.height - height) / 2,
+            width,
+            height,
+        );
+        f.render_widget(Clear, area);
+        let block = Block::bordered().title(format!(
+            " rebase -i onto {} ",
+            self.base.to_hex_with_len(self.abbrev)
+        ));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [list, footer] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(inner);
+        let items: Vec<ListItem> = self
+            .steps
+            .iter()
+            .map(|step| {
+                let style = match step.verb {
+                    Verb::Pick => Style::new(),
+                    Verb::Drop => Style::new().gray().crossed_out(),
+                    Verb::Squash | Verb::Fixup => Style::new().yellow(),
+                    Verb::Reword | Verb::Edit => Style::new().cyan(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<7}", step.verb.label()), style.bold()),
+                    Span::styled(
+                        format!("{} ", step.id.to_hex_with_len(self.abbrev)),
+                        Style::new().yellow(),
+                    ),
+                    Span::styled(step.subject.clone(), style),
+                ]))
+            })
+            .collect();
+        let list_widget = List::new(items)
+            .highlight_style(Style::new().bg(Color::LightGreen).bold())
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list_widget, list, &mut self.state);
+
+        let replayed = match self.replayed.len() {
+            0 => String::new(),
+            1 => "then the commit after them is picked; ".into(),
+            n => format!("then the {n} commits after them are picked; "),
+        };
+        let help = vec![
+            Line::styled(
+                format!("{replayed}Enter to rebase, Esc to cancel"),
+                Style::new().gray(),
+            ),
+            Line::styled(
+                "p pick  r reword  e edit  s squash  f fixup  d drop  J/K move down/up",
+                Style::new().gray(),
+            ),
+        ];
+        f.render_widget(Paragraph::new(help), footer);
+    }
+}
@@ -1,263 +1,5451 @@
 use color_eyre::Result;
 use crossterm::{
     ExecutableCommand,
-    event::{self, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers, MouseButton,
+        MouseEventKind,
+    },
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
-use gix::{
-    bstr::{BString, ByteSlice},
-    date::Time,
+use gix::bstr::{BString, ByteSlice};
+use ratatui::{TerminalOptions, Viewport, backend::TestBackend, prelude::*, widgets::*};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    io::{Write, stderr, stdout},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
 };
-use ratatui::{prelude::*, widgets::*};
-use std::{io::stdout, path::PathBuf, process::Command};
 
-#[derive(Clone, Debug)]
-pub struct LogEntryInfo {
-    pub commit_id: String,
-    pub author: BString,
-    pub time: String,
-    pub message: BString,
-    pub author_time: Time,
+use crate::{
+    archive,
+    changelog::Changelog,
+    ci::Ci,
+    clipboard,
+    command::{self, RemoteEdit},
+    config::Config,
+    diff::{self, FileDiff},
+    finder::Finder,
+    forge::{self, Forge, ForgeKind, Request},
+    hooks::{self, Hook},
+    log::{self, DateFormat, LogEntryInfo, Role, Side},
+    operation::Operation,
+    pane::DiffPane,
+    partial::PartialClone,
+    popup::Popup,
+    pr,
+    profile::Profile,
+    rebase,
+    refs::{self, Divergence, RefInfo, RefKind, Relation, RemoteInfo},
+    replace,
+    session::{self, Session},
+    sidebar::Sidebar,
+    source::{self, Root, Source},
+    stage::{self, Change},
+    submodule,
+    task::{self, AppEvent, DiffUse, Tasks},
+    text::{Decoding, Shared},
+    theme::{self, Background, Depth, Theme},
+    todo::{Step, TodoEditor, Verb},
+    view::{ACTIVITY_BUCKETS, Item, Summary, View},
+};
+
+/// What is shown first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum StartView {
+    /// The log.
+    #[default]
+    Log,
+    /// The branches dashboard, over the log.
+    Branches,
+}
+
+impl StartView {
+    pub fn parse(value: &str) -> Option<StartView> {
+        match value {
+            "log" => Some(StartView::Log),
+            "branches" => Some(StartView::Branches),
+            _ => None,
+        }
+    }
 }
 
-pub type Item<'repo> = (LogEntryInfo, Option<&'repo gix::Submodule<'repo>>);
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Show the nearest preceding tag of the selected commit.
+    pub describe: bool,
+    /// Oldest entries first.
+    pub reverse: bool,
+    /// Whose time entries are sorted by, and shown in the date column unless set otherwise.
+    pub sort: Role,
+    /// Applied to history loaded from within the UI.
+    pub filter: log::Filter,
+    /// Where the selection and filters are saved on exit.
+    pub session: Option<PathBuf>,
+    /// Selection and scroll position to start from.
+    pub restore: Session,
+    pub config: Config,
+    /// Template printed for the entry picked with Enter, which exits.
+    pub pick: Option<String>,
+    /// Disable the actions changing references or checkouts, but the commands of the
+    /// configuration, which gixl can't tell about.
+    pub read_only: bool,
+    /// Spell out what colors and highlights show, for screen readers.
+    pub screen_reader: bool,
+    /// Replace unicode symbols by ASCII ones.
+    pub ascii: bool,
+    /// Show the status of the checks of commits from their forge.
+    pub ci: bool,
+    /// Show the pull or merge request which introduced each commit.
+    pub prs: bool,
+    /// Height of the viewport drawn below the cursor instead of on the alternate screen.
+    pub inline: Option<u16>,
+    pub view: StartView,
+    /// The ranges of `--changelog`, summed up over the log when it starts.
+    pub changelog: Option<Changelog>,
+    /// Add the commits of the revisions read from standard input to the log as they come.
+    pub stdin: bool,
+    /// What went wrong while loading, like a submodule which couldn't be read, reported once
+    /// the UI is up.
+    pub problems: Vec<String>,
+}
 
 struct App<'repo> {
-    git_dir: PathBuf,
-    items: Vec<Item<'repo>>,
-    list_items: List<'static>,
-    state: ListState,
+    roots: &'repo [Root],
+    /// Every submodule of the roots, whether its history is listed or not.
+    submodules: &'repo [Source<'repo>],
+    /// Looks up the revisions added to the log, keeping the submodules it opened.
+    resolver: gixl_core::Resolver<'repo, 'repo>,
+    options: Options,
+    /// Open tabs; the first one holds the full log.
+    views: Vec<View<'repo>>,
+    current: usize,
     list_height: u16,
+    /// Transient feedback shown in the status bar until the next key press.
+    message: Option<String>,
+    /// Every error reported during the session, oldest first, listed with `!`.
+    errors: Vec<String>,
+    popup: Option<Popup>,
+    /// Diff of a commit shown in place of the list, taking the moving keys while open.
+    pane: Option<DiffPane>,
+    /// Changes of the working tree and of the index of a source, while the pane shows them.
+    staging: Option<(Source<'repo>, Vec<Change>)>,
+    /// The entry at this index of the current tab, with the paths of its files, while the pane
+    /// shows its diff.
+    diffed: Option<(usize, Vec<String>)>,
+    /// Fuzzy finder over commits and references.
+    finder: Option<Finder<Found>>,
+    /// State of each submodule, shown above the list.
+    submodule_health: Vec<(String, Option<submodule::Health>)>,
+    /// Action waiting for the user to confirm it, with its prompt.
+    confirm: Option<(String, Action<'repo>)>,
+    /// Where HEAD was before each ref-changing action, latest last.
+    undo: Vec<Undo<'repo>>,
+    /// Merges, rebases and the like left in progress, shown in a banner.
+    operations: Vec<(Source<'repo>, Operation)>,
+    /// Commits cherry-picked or reverted one by one, stopped on a conflict, resumed once it is
+    /// continued.
+    sequence: Option<Sequence<'repo>>,
+    /// Squash last previewed, started by previewing it again.
+    squash: Option<Squash<'repo>>,
+    /// Todo list of an interactive rebase of a source being edited, taking the keys while open.
+    todo: Option<(Source<'repo>, TodoEditor)>,
+    /// `git describe` output per commit, when enabled.
+    describe: Option<HashMap<gix::ObjectId, Option<String>>>,
+    /// How commits stand against the HEAD of their repository, once selected.
+    relations: HashMap<gix::ObjectId, Option<Relation>>,
+    /// Times HEAD moved, the relations found before being dropped.
+    moves: u64,
+    /// Text typed on the `:` command line, while it is open.
+    command: Option<String>,
+    /// What `:save` writes when it was asked for from a popup or a diff, with its description,
+    /// rather than the patch of the selected entry.
+    save: Option<(String, String)>,
+    date: DateFormat,
+    /// Whose time the date column shows.
+    time: Role,
+    /// Checks statuses fetched in the background, when shown.
+    ci: Option<Ci>,
+    /// Commits which arrived since the previous run.
+    new: HashSet<gix::ObjectId>,
+    /// The pull or merge request which introduced each commit, found in the background once
+    /// shown or asked for, see [`App::find_requests`].
+    prs: Option<HashMap<gix::ObjectId, Request>>,
+    /// Whether pull requests are being found.
+    finding_prs: bool,
+    /// The commit whose pull request is opened once they are found.
+    request_for: Option<gix::ObjectId>,
+    /// Palette for the background of the terminal, when colors are enabled and contrast isn't
+    /// high.
+    theme: Option<Theme>,
+    /// Whether a summary of the current tab is shown below the list.
+    stats: bool,
+    /// References panel on the left of the list, taking the moving keys while open.
+    sidebar: Option<Sidebar>,
+    /// Where the minimap of the history was last drawn, while it is shown.
+    minimap: Option<Rect>,
+    /// Entry the `on-select` hook last ran for.
+    hooked: Option<gix::ObjectId>,
+    /// Entry selected since when, the `on-select` hook waiting for it to stay selected.
+    settling: Option<(gix::ObjectId, Instant)>,
+    /// The `on-select` script of the last entry, stopped if still running when another one is.
+    select_script: Option<std::process::Child>,
+    /// Diffs, signatures and summaries being made in the background.
+    tasks: Tasks,
+    /// Commit whose changed files are being listed, and what for.
+    diffing: Option<(gix::ObjectId, DiffUse)>,
+    /// Signatures of the tag objects shown, `None` while being verified.
+    signatures: HashMap<gix::ObjectId, Option<refs::Signature>>,
+    /// Summaries of tabs asked for so far.
+    summaries: u64,
+    /// Patch ids of the entries, found in the background for those asked for, see
+    /// [`diff::patch_id`].
+    patches: HashMap<gix::ObjectId, Option<gix::ObjectId>>,
+    /// Whether patch ids are being found, to jump to a duplicate of the selected entry then.
+    finding_patches: bool,
+    /// Whether the divergence of branches is being counted, for their finder.
+    counting_branches: bool,
+    /// Entries whose patch is also the one of another entry, like a cherry-picked backport or a
+    /// vendored copy in a submodule.
+    duplicates: HashSet<gix::ObjectId>,
 }
 
 impl<'repo> App<'repo> {
-    fn new(git_dir: PathBuf, items: Vec<Item<'repo>>) -> App<'repo> {
-        let list_items = build_list_items(&items);
+    fn new(
+        roots: &'repo [Root],
+        submodules: &'repo [Source<'repo>],
+        items: Vec<Item<'repo>>,
+        options: Options,
+    ) -> App<'repo> {
+        let mut log = View::new("log", items);
+        log.date = options.config.date;
+        log.time = options.sort;
+        let ci = options.ci.then(Ci::new);
+        log.ci = ci.as_ref().map(Ci::known);
+        log.prs = options.prs.then(HashMap::new);
+        let new = new_since(roots, submodules, &options.restore.newest);
+        log.new = new.clone();
+        log.columns = options.config.columns.clone();
+        log.lint = options.config.lint.clone();
+        log.measure();
+        let config = &options.config;
+        let high_contrast = config.high_contrast || options.screen_reader;
+        let theme = (config.color && !high_contrast).then(|| {
+            let (background, depth) = (theme::detect(config.background), config.depth);
+            let overrides = match background {
+                Background::Light => &config.light,
+                _ => &config.dark,
+            };
+            Theme::new(background, overrides, depth.unwrap_or_else(Depth::detect))
+        });
         App {
-            git_dir,
-            items,
-            state: ListState::default(),
+            roots,
+            submodules,
+            resolver: gixl_core::Resolver::new(roots, submodules),
+            views: vec![log],
+            current: 0,
             list_height: 0,
-            list_items,
+            message: None,
+            errors: Vec::new(),
+            popup: None,
+            pane: None,
+            staging: None,
+            diffed: None,
+            finder: None,
+            submodule_health: health(submodules),
+            confirm: None,
+            undo: Vec::new(),
+            operations: operations(roots, submodules),
+            sequence: None,
+            squash: None,
+            todo: None,
+            describe: options.describe.then(HashMap::new),
+            relations: HashMap::new(),
+            moves: 0,
+            command: None,
+            save: None,
+            date: options.config.date,
+            time: options.sort,
+            ci,
+            prs: None,
+            finding_prs: false,
+            request_for: None,
+            new,
+            stats: false,
+            sidebar: None,
+            minimap: None,
+            hooked: None,
+            settling: None,
+            select_script: None,
+            tasks: Tasks::new(),
+            diffing: None,
+            signatures: HashMap::new(),
+            summaries: 0,
+            patches: HashMap::new(),
+            finding_patches: false,
+            counting_branches: false,
+            duplicates: HashSet::new(),
+            theme,
+            options,
         }
     }
 
-    pub fn next(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i < self.items.len() - 1 {
-                    i + 1
-                } else {
-                    i
+    /// Restore the selection, run the `on-load-complete` hook and open the first view.
+    fn start(&mut self) {
+        let restore = &self.options.restore;
+        let (selected, offset) = (restore.selected, restore.offset);
+        if let Some(id) = selected
+            && self.views[0].select_id(id)
+        {
+            *self.views[0].state.offset_mut() = offset;
+        }
+
+        let (config, items) = (&self.options.config, &self.views[0].items);
+        if let Err(err) = hooks::load_complete(config, items, &self.roots[0].dir) {
+            self.report("on-load-complete", err);
+        }
+        if self.options.prs {
+            self.find_requests();
+        }
+        if self.options.view == StartView::Branches {
+            self.open_branches();
+        }
+        if self.options.changelog.is_some() {
+            self.show_changelog();
+        }
+        if self.options.stdin {
+            self.tasks.stream(task::stdin);
+        }
+        let problems = std::mem::take(&mut self.options.problems);
+        if problems.len() > 1 {
+            self.message = Some(format!(
+                "{} problems while loading, ! to list",
+                problems.len()
+            ));
+        } else {
+            self.message = problems.first().cloned();
+        }
+        self.errors.extend(problems);
+    }
+
+    /// Show `err` on the status bar, keeping it for the list of errors.
+    fn report(&mut self, context: &str, err: impl std::fmt::Display) {
+        let text = format!("{context}: {err}");
+        self.errors.push(text.clone());
+        self.message = Some(text);
+    }
+
+    /// Report how `program` failed, if it did.
+    fn check(&mut self, program: &str, status: std::io::Result<std::process::ExitStatus>) {
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => self.report(program, status),
+            Err(err) => self.report(program, err),
+        }
+    }
+
+    /// Whether actions changing references or checkouts are refused, in read-only mode, saying
+    /// so.
+    fn refuse_write(&mut self) -> bool {
+        if self.options.read_only {
+            self.message = Some("disabled in read-only mode".into());
+        }
+        self.options.read_only
+    }
+
+    /// Copy `text` to the clipboard.
+    fn copy(&mut self, text: &str) {
+        self.copy_as(text, text);
+    }
+
+    /// Copy `text` to the clipboard, telling it is `what`.
+    fn copy_as(&mut self, what: &str, text: &str) {
+        match clipboard::copy(text) {
+            Ok(()) => self.message = Some(format!("copied {what}")),
+            Err(err) => self.report("clipboard", err),
+        }
+    }
+
+    /// The patch of the entry at `selected`, with its message, like `git format-patch`, and its
+    /// description.
+    fn patch(&self, selected: usize) -> Result<(String, String)> {
+        let (entry, source) = &self.view().items[selected];
+        let id = entry.id.to_hex_with_len(self.options.config.abbrev);
+        let output = Command::new("git")
+            .args(["format-patch", "-1", "--stdout", "--no-signature"])
+            .arg(entry.id.to_string())
+            .current_dir(source.dir())
+            .output()?;
+        if !output.status.success() {
+            color_eyre::eyre::bail!(git_failure(&output, "format-patch"));
+        }
+        let text = String::from_utf8_lossy(&output.stdout).into_owned();
+        Ok((format!("the patch of {id}"), text))
+    }
+
+    /// Edit the message of the entry at `selected`, unless it was pushed: amending HEAD, or
+    /// rewording it with a rebase replaying the commits after it.
+    fn reword(&mut self, selected: usize) -> Result<Action<'repo>> {
+        let abbrev = self.options.config.abbrev;
+        let (entry, source) = &self.view().items[selected];
+        let (id, source) = (entry.id, *source);
+        let short = id.to_hex_with_len(abbrev);
+        let repo = source.open()?;
+        if let Some(remote) = refs::pushed(&repo, id)? {
+            self.message = Some(format!("reword: {short} is already pushed to {remote}"));
+            return Ok(Action::Continue);
+        }
+        let head = repo.head_id()?.detach();
+        if head == id {
+            return Ok(Action::Reword(source, id, None));
+        }
+        let Some(&base) = entry.parents.first() else {
+            self.message = Some(format!("reword: {short} is a root commit"));
+            return Ok(Action::Continue);
+        };
+        if !rebase::linear(&repo, base)? {
+            self.message = Some(format!("reword: merges after {short} would be lost"));
+            return Ok(Action::Continue);
+        }
+        let commits = rebase::todo(&repo, base)?;
+        if commits.first().map(|entry| entry.id) != Some(id) {
+            self.message = Some(format!("reword: {short} is not in HEAD"));
+            return Ok(Action::Continue);
+        }
+        let mut todo = String::new();
+        for (i, entry) in commits.iter().enumerate() {
+            let command = if i == 0 { "reword" } else { "pick" };
+            let subject = entry.message.lines().next().unwrap_or_default();
+            todo += &format!("{command} {} {}\n", entry.id, subject.to_str_lossy());
+        }
+        Ok(Action::Reword(source, id, Some((base, todo))))
+    }
+
+    /// Open the todo list of `git rebase -i` for the range of entries of the current tab, or from
+    /// the entry at `selected` to HEAD, unless they were pushed. The commits after the range are
+    /// picked again.
+    fn todo_editor(&mut self, selected: usize) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let view = self.view();
+        let range = view.range().unwrap_or(selected..=selected);
+        let mut items = view.items[range].to_vec();
+        let source = items[0].1;
+        items.sort_by_key(|(entry, _)| Reverse(entry.order));
+        let oldest = &items[0].0;
+        let short = oldest.id.to_hex_with_len(abbrev);
+        let Some(&base) = oldest.parents.first() else {
+            self.message = Some(format!("rebase: {short} is a root commit"));
+            return Ok(());
+        };
+        let repo = source.open()?;
+        if let Some(remote) = refs::pushed(&repo, oldest.id)? {
+            self.message = Some(format!("rebase: {short} is already pushed to {remote}"));
+            return Ok(());
+        }
+        if !rebase::linear(&repo, base)? {
+            self.message = Some(format!("rebase: merges after {short} would be lost"));
+            return Ok(());
+        }
+        let mut commits = rebase::todo(&repo, base)?;
+        let in_line = items.iter().all(|(_, s)| *s == source)
+            && commits.len() >= items.len()
+            && commits
+                .iter()
+                .zip(&items)
+                .all(|(entry, (item, _))| entry.id == item.id);
+        if !in_line {
+            self.message = Some("rebase: the commits must follow each other in HEAD".into());
+            return Ok(());
+        }
+        let subject = |entry: &LogEntryInfo| {
+            let subject = entry.message.lines().next().unwrap_or_default();
+            subject.to_str_lossy().into_owned()
+        };
+        // without a range, up to HEAD
+        let edited = match view.range() {
+            Some(_) => items.len(),
+            None => commits.len(),
+        };
+        let replayed = commits.split_off(edited);
+        let steps = commits
+            .iter()
+            .map(|entry| Step {
+                verb: Verb::Pick,
+                id: entry.id,
+                subject: subject(entry),
+            })
+            .collect();
+        let replayed = replayed
+            .iter()
+            .map(|entry| (entry.id, subject(entry)))
+            .collect();
+        self.todo = Some((source, TodoEditor::new(base, steps, replayed, abbrev)));
+        Ok(())
+    }
+
+    /// Ask to remove the entry at `selected` from the current branch, unless it was pushed,
+    /// replaying the commits after it onto its parent.
+    fn drop_prompt(&mut self, selected: usize) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let (entry, source) = &self.view().items[selected];
+        let (id, source) = (entry.id, *source);
+        let short = id.to_hex_with_len(abbrev);
+        let subject = entry.message.lines().next().unwrap_or_default();
+        let subject = subject.to_str_lossy().into_owned();
+        let repo = source.open()?;
+        let refusal = match entry.parents[..] {
+            [] => Some(format!("{short} is a root commit")),
+            [_, _, ..] => Some(format!("{short} is a merge")),
+            [parent] => match refs::relation(&repo, id)? {
+                Relation::Head | Relation::Ancestor => match refs::pushed(&repo, id)? {
+                    Some(remote) => Some(format!("{short} is already pushed to {remote}")),
+                    None if !rebase::linear(&repo, parent)? => {
+                        Some(format!("merges after {short} would be lost"))
+                    }
+                    None => None,
+                },
+                _ => Some(format!("{short} is not in HEAD")),
+            },
+        };
+        if let Some(refusal) = refusal {
+            self.message = Some(format!("drop: {refusal}"));
+            return Ok(());
+        }
+        let prompt = format!("Drop {short} \"{subject}\" from the current branch?");
+        self.confirm = Some((prompt, Action::Drop(source, id, entry.parents[0])));
+        Ok(())
+    }
+
+    /// Ask `git send-email` to send the range of entries of the current tab as a patch series,
+    /// the selected entry when no range is started, with `args`: options, and recipients added
+    /// with `--to`.
+    fn send_email(&mut self, args: &[&str]) -> Action<'repo> {
+        let view = self.view();
+        let Some(range) = view.range().or(view.selected().map(|i| i..=i)) else {
+            return Action::Continue;
+        };
+        let mut items: Vec<_> = view.items[range].iter().collect();
+        let source = items[0].1;
+        items.sort_by_key(|(entry, _)| Reverse(entry.order));
+        // a series is a line of commits, each on top of the previous one
+        let series = items.iter().all(|(_, s)| *s == source)
+            && items.iter().all(|(entry, _)| entry.parents.len() <= 1)
+            && items
+                .windows(2)
+                .all(|pair| pair[1].0.parents.first() == Some(&pair[0].0.id));
+        if !series {
+            self.message = Some("send-email: the commits must follow each other".into());
+            return Action::Continue;
+        }
+        let newest = items[items.len() - 1].0.id;
+        let revs = match items[0].0.parents.first() {
+            Some(base) => vec![format!("{base}..{newest}")],
+            None => vec!["--root".into(), newest.to_string()],
+        };
+        let args = args
+            .iter()
+            .map(|arg| match arg.starts_with('-') {
+                true => arg.to_string(),
+                false => format!("--to={arg}"),
+            })
+            .chain(revs)
+            .collect();
+        Action::SendEmail(source, args)
+    }
+
+    /// Open the command line to `:save` `text`, described as `what`, to a file.
+    fn save_prompt(&mut self, what: String, text: String) {
+        self.save = Some((what, text));
+        self.command = Some("save ".into());
+    }
+
+    /// Write what `:save` was asked for to `path`, the patch of the selected entry by default,
+    /// not overwriting a file.
+    fn save(&mut self, path: &str) -> Result<()> {
+        let (what, text) = match self.save.take() {
+            Some(save) => save,
+            None => match self.view().selected() {
+                Some(selected) => self.patch(selected)?,
+                None => return Ok(()),
+            },
+        };
+        let path = match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+            (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+            _ => PathBuf::from(path),
+        };
+        let mut file = std::fs::File::create_new(&path)
+            .map_err(|err| color_eyre::eyre::eyre!("{}: {err}", path.display()))?;
+        file.write_all(text.as_bytes())?;
+        self.message = Some(format!("wrote {what} to {}", path.display()));
+        Ok(())
+    }
+
+    /// Popup of the ranges of `--changelog`, with how many of their commits are listed.
+    fn show_changelog(&mut self) {
+        let Some(changelog) = &self.options.changelog else {
+            self.message = Some("no changelog, see --changelog".into());
+            return;
+        };
+        let lines = changelog.lines(&self.views[0].items, self.options.config.abbrev);
+        self.popup = Some(Popup::new("changelog", lines));
+    }
+
+    fn show_errors(&mut self) {
+        if self.errors.is_empty() {
+            self.message = Some("no errors".into());
+            return;
+        }
+        let lines = self.errors.iter().map(|e| Line::raw(e.clone())).collect();
+        self.popup = Some(Popup::new("errors", lines));
+    }
+
+    /// Fetch the checks statuses of the visible entries.
+    fn request_ci(&mut self) {
+        let Some(ci) = &mut self.ci else {
+            return;
+        };
+        let view = &self.views[self.current];
+        let offset = view.state.offset();
+        let visible = usize::from(self.list_height) + 2;
+        for (entry, source) in view.items.iter().skip(offset).take(visible) {
+            ci.request(source, entry.id);
+        }
+    }
+
+    /// Read the messages of the deferred entries of the current tab in `range`, see
+    /// [`LogEntryInfo::deferred`].
+    fn load(&mut self, range: std::ops::Range<usize>) {
+        let items = &mut self.views[self.current].items;
+        let mut repos: Vec<(Source, Result<gix::Repository, String>)> = Vec::new();
+        for (entry, source) in items.iter_mut().take(range.end).skip(range.start) {
+            if !entry.deferred {
+                continue;
+            }
+            let repo = match repos.iter().position(|(s, _)| s == source) {
+                Some(i) => &repos[i].1,
+                None => {
+                    repos.push((*source, source.open().map_err(|err| err.to_string())));
+                    &repos[repos.len() - 1].1
                 }
+            };
+            match repo {
+                Ok(repo) => entry.load(repo),
+                Err(err) => entry.fail(color_eyre::eyre::eyre!("{err}")),
             }
-            None => 0,
-        };
-        self.state.select(Some(i));
+        }
     }
 
-    pub fn previous(&mut self) {
-        let i = match self.state.selected() {
-            Some(i) => {
-                if i > 0 {
-                    i - 1
-                } else {
-                    i
+    /// Read the messages of every entry of the current tab, for them all to be searched.
+    fn load_all(&mut self) {
+        self.load(0..usize::MAX);
+    }
+
+    /// Read the messages of the entries of the current tab shown on a screen of `height` rows,
+    /// and of those a page above and two pages below, scrolled to next.
+    fn load_shown(&mut self, height: usize) {
+        let view = self.view();
+        let (offset, selected) = (view.state.offset(), view.selected().unwrap_or(0));
+        let start = offset.min(selected).saturating_sub(height);
+        self.load(start..offset.max(selected) + 2 * height);
+    }
+
+    /// Show the checks statuses received since the last call.
+    fn receive_ci(&mut self) {
+        let Some(ci) = &mut self.ci else {
+            return;
+        };
+        let replies = ci.receive();
+        if replies.is_empty() {
+            return;
+        }
+        if let Some((_, Err(err))) = replies.iter().find(|(_, reply)| reply.is_err()) {
+            self.report("ci", err);
+        }
+        for view in &mut self.views {
+            let Some(statuses) = &mut view.ci else {
+                continue;
+            };
+            for (id, reply) in &replies {
+                if let Ok(Some(status)) = reply {
+                    statuses.insert(*id, *status);
                 }
             }
-            None => 0,
+        }
+    }
+
+    fn view(&self) -> &View<'repo> {
+        &self.views[self.current]
+    }
+
+    fn view_mut(&mut self) -> &mut View<'repo> {
+        &mut self.views[self.current]
+    }
+
+    fn open_tab(&mut self, mut view: View<'repo>) {
+        view.date = self.date;
+        view.time = self.time;
+        view.ci = self.ci.as_ref().map(Ci::known);
+        view.prs = self
+            .options
+            .prs
+            .then(|| self.prs.clone().unwrap_or_default());
+        view.new = self.new.clone();
+        view.duplicates = self.duplicates.clone();
+        view.columns = self.options.config.columns.clone();
+        view.lint = self.options.config.lint.clone();
+        view.measure();
+        self.views.push(view);
+        self.current = self.views.len() - 1;
+    }
+
+    fn close_tab(&mut self) {
+        if self.current == 0 {
+            self.message = Some("the log tab can't be closed".into());
+            return;
+        }
+        self.views.remove(self.current);
+        self.current = self.current.min(self.views.len() - 1);
+    }
+
+    /// Open a tab with the history of the selected entry's source only.
+    fn open_source_tab(&mut self, selected: usize) {
+        let source = self.view().items[selected].1;
+        let items: Vec<_> = self.views[0]
+            .items
+            .iter()
+            .filter(|(_, s)| *s == source)
+            .cloned()
+            .collect();
+        let label = source.label();
+        let title = if label.is_empty() {
+            "superproject".into()
+        } else {
+            label
         };
-        self.state.select(Some(i));
+        let mut view = View::new(title, items);
+        view.select_id(self.view().items[selected].0.id);
+        self.open_tab(view);
     }
 
-    pub fn page_down(&mut self) {
-        let page_size = (self.list_height / 2).max(1) as usize;
-        let i = match self.state.selected() {
-            Some(i) => {
-                let next = i + page_size;
-                if next >= self.items.len() {
-                    self.items.len() - 1
-                } else {
-                    next
-                }
+    /// Select commit `rev`, given as an abbreviated id of a listed entry or a revision of a
+    /// repository.
+    fn goto(&mut self, rev: &str) {
+        let abbrev = self.options.config.abbrev;
+        let view = self.view_mut();
+        if let Some(i) = view
+            .items
+            .iter()
+            .position(|(e, _)| e.id.to_string().starts_with(rev))
+        {
+            view.state.select(Some(i));
+            return;
+        }
+        let found = self.roots.iter().find_map(|root| {
+            let id = root.repo.rev_parse_single(rev).ok()?.object().ok()?;
+            Some(id.peel_to_commit().ok()?.id)
+        });
+        self.message = Some(match found {
+            Some(id) if self.view_mut().select_id(id) => return,
+            Some(id) => format!("goto: {} is not listed", id.to_hex_with_len(abbrev)),
+            None => format!("goto: unknown revision {rev}"),
+        });
+    }
+
+    /// Open a tab with the entries of the current one whose `key` (author, committer or message)
+    /// matches `pattern`.
+    fn filter_tab(&mut self, key: &str, pattern: &str) {
+        let regex = match regex::bytes::Regex::new(pattern) {
+            Ok(regex) => regex,
+            Err(err) => {
+                self.report("filter", err);
+                return;
             }
-            None => 0,
         };
-        self.state.select(Some(i));
+        self.load_all();
+        let items: Vec<_> = self
+            .view()
+            .items
+            .iter()
+            .filter(|(entry, _)| match key {
+                "author" => regex.is_match(&entry.author),
+                "committer" => regex.is_match(&entry.committer),
+                "person" => log::involves(entry, &regex),
+                _ => regex.is_match(&entry.message),
+            })
+            .cloned()
+            .collect();
+        if items.is_empty() {
+            self.message = Some("filter: no match".into());
+            return;
+        }
+        self.open_tab(View::new(format!("{key}={pattern}"), items));
     }
 
-    pub fn page_up(&mut self) {
-        let page_size = (self.list_height / 2).max(1) as usize;
-        let i = match self.state.selected() {
-            Some(i) => i.saturating_sub(page_size),
-            None => 0,
+    /// Open a tab with the history of `spec`, like a branch or a tag, in the repository of the
+    /// selected entry.
+    fn spec_tab(&mut self, spec: &str) -> Result<()> {
+        let Some(selected) = self.view().selected() else {
+            return Ok(());
         };
-        self.state.select(Some(i));
+        let source = self.view().items[selected].1;
+        let defer = defers(&self.options.config, self.options.prs);
+        let (entries, unchecked) = log::collect(
+            &source.open()?,
+            &source,
+            spec,
+            &self.options.filter,
+            defer,
+            None,
+            &mut |_, _| true,
+        )?;
+        let mut items: Vec<_> = entries.into_iter().map(|entry| (entry, source)).collect();
+        log::sort(&mut items, self.options.reverse, self.options.sort);
+        self.open_tab(View::new(spec, items));
+        if let Some(unchecked) = unchecked {
+            self.report(spec, unchecked);
+        }
+        Ok(())
     }
 
-    pub fn go_to_start(&mut self) {
-        self.state.select(Some(0));
+    /// Run the `on-select` hook when the entry at `selected` wasn't the last one it ran for, once
+    /// it has stayed selected for [`SETTLE`], so that scrolling doesn't start a script per entry.
+    fn select_hook(&mut self, selected: usize) {
+        let id = self.view().items[selected].0.id;
+        let config = &self.options.config;
+        if self.hooked == Some(id) || !config.hooks.iter().any(|(h, _)| *h == Hook::Select) {
+            self.settling = None;
+            return;
+        }
+        match self.settling {
+            Some((settling, since)) if settling == id && since.elapsed() >= SETTLE => {
+                self.settling = None;
+            }
+            Some((settling, _)) if settling == id => return,
+            _ => {
+                self.settling = Some((id, Instant::now()));
+                return;
+            }
+        }
+        // what it does for the previous entry is of no use anymore
+        if let Some(mut script) = self.select_script.take()
+            && let Ok(None) = script.try_wait()
+        {
+            let _ = script.kill();
+            let _ = script.wait();
+        }
+        let (entry, source) = &self.views[self.current].items[selected];
+        match hooks::entry(Hook::Select, &self.options.config, entry, source) {
+            Ok(script) => self.select_script = script,
+            Err(err) => self.report("on-select", err),
+        }
+        self.hooked = Some(id);
     }
 
-    pub fn go_to_end(&mut self) {
-        self.state.select(Some(self.items.len() - 1));
+    /// Select the entry at the position of the minimap clicked on.
+    fn click(&mut self, column: u16, row: u16) {
+        let Some(area) = self.minimap else {
+            return;
+        };
+        if !area.contains(Position::new(column, row)) {
+            return;
+        }
+        let view = self.view_mut();
+        let rows = area.height.min(view.items.len() as u16).max(1);
+        let i = (row - area.y).min(rows - 1) as usize * view.items.len() / rows as usize;
+        view.state.select(Some(i));
     }
-}
 
-fn build_list_items<'repo>(items: &[Item<'repo>]) -> List<'static> {
-    let mut list_items: Vec<ListItem> = Vec::with_capacity(items.len());
-    let mut prev_submodule: Option<&gix::Submodule> = None;
-    for i in items {
-        let message_lines = i.0.message.split(|c| *c == b'\n').collect::<Vec<_>>();
-        let first_line = String::from_utf8_lossy(message_lines[0]).into_owned();
-        let author_str = i.0.author.to_str_lossy();
-        let author = if author_str.len() > 20 {
-            format!("{author_str:.19}…")
-        } else {
-            format!("{author_str:<20}")
+    /// Select the entry the selected reference of the sidebar points to.
+    fn follow_sidebar(&mut self) {
+        let Some((name, target)) = self.sidebar.as_ref().and_then(Sidebar::selected) else {
+            return;
         };
+        let name = name.to_owned();
+        if !self.view_mut().select_id(target) {
+            self.message = Some(format!("{name} is not listed"));
+        }
+    }
+
+    /// Open a tab of the entries authored with the configured `user.email` of their repository.
+    fn mine_tab(&mut self) {
+        self.load_all();
+        let mut repos: Vec<(Source, Option<gix::Repository>)> = Vec::new();
+        let mut items = Vec::new();
+        for item in &self.view().items {
+            let repo = match repos.iter().find(|(source, _)| *source == item.1) {
+                Some((_, repo)) => repo,
+                None => {
+                    repos.push((item.1, item.1.open().ok()));
+                    &repos[repos.len() - 1].1
+                }
+            };
+            if repo
+                .as_ref()
+                .is_some_and(|repo| log::is_mine(repo, &item.0))
+            {
+                items.push(item.clone());
+            }
+        }
+        if items.is_empty() {
+            self.message = Some("no commits authored with user.email".into());
+            return;
+        }
+        self.open_tab(View::new("mine", items));
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        match (key, value) {
+            ("date", value) => match DateFormat::parse(value) {
+                Some(date) => {
+                    self.date = date;
+                    for view in &mut self.views {
+                        view.date = date;
+                    }
+                }
+                None => self.message = Some(format!("set: unknown date format {value}")),
+            },
+            ("time", value) => match Role::parse(value) {
+                Some(time) => {
+                    self.time = time;
+                    for view in &mut self.views {
+                        view.time = time;
+                    }
+                }
+                None => self.message = Some("set: time is author or committer".into()),
+            },
+            ("sort", value) => match Role::parse(value) {
+                Some(sort) => {
+                    self.options.sort = sort;
+                    let view = &mut self.views[0];
+                    let selected = view.selected().map(|i| view.items[i].0.id);
+                    let hidden = view.ungroup();
+                    log::sort(&mut view.items, self.options.reverse, sort);
+                    if hidden {
+                        view.hide_submodules();
+                    }
+                    if let Some(id) = selected {
+                        view.select_id(id);
+                    }
+                }
+                None => self.message = Some("set: sort is author or committer".into()),
+            },
+            _ => self.message = Some(format!("set: unknown setting {key}")),
+        }
+    }
 
-        // Only show submodule if it changed from the previous entry
-        let submodule_display = if prev_submodule.map(|s| s.name()) != i.1.map(|s| s.name()) {
-            format!("{:^20}", i.1.map(|s| s.name()).unwrap_or_default())
+    /// Write the selected commit as a patch file in `dir`, like `git format-patch -1`.
+    fn export_patches(&mut self, selected: usize, dir: &str) -> Result<()> {
+        let (entry, source) = &self.view().items[selected];
+        let dir = std::path::absolute(dir)?;
+        let output = Command::new("git")
+            .args(["format-patch", "-1", "-o"])
+            .arg(&dir)
+            .arg(entry.id.to_string())
+            .current_dir(source.dir())
+            .output()?;
+        self.message = Some(if output.status.success() {
+            format!(
+                "exported {}",
+                String::from_utf8_lossy(&output.stdout).trim()
+            )
         } else {
-            format!("{:^20}", "")
-        };
-        prev_submodule = i.1;
-
-        let lines = vec![Line::from(vec![
-            // time
-            Span::styled(i.0.time.clone(), Style::new().blue()),
-            Span::raw(" "),
-            // author
-            Span::styled(author, Style::default().green()),
-            Span::raw(" "),
-            // submodule
-            Span::styled(submodule_display, Style::default().gray()),
-            Span::raw(" "),
-            // message
-            Span::styled(first_line, Style::default()),
-        ])];
-        list_items.push(ListItem::new(lines).style(Style::default()));
-    }
-
-    List::new(list_items)
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(">> ")
-}
+            git_failure(&output, "export")
+        });
+        Ok(())
+    }
 
-pub fn run<'repo>(git_dir: PathBuf, log_entries: Vec<Item<'repo>>) -> Result<()> {
-    stdout().execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+    /// Write the files of the entry at `selected` to `file`, by default `NAME-ID.tar.gz` in the
+    /// current directory, NAME being the directory of its repository.
+    fn export_archive(
+        &mut self,
+        selected: usize,
+        file: Option<&str>,
+        submodules: bool,
+    ) -> Result<()> {
+        let (entry, source) = &self.view().items[selected];
+        let repo = source.open()?;
+        let dir = source.dir();
+        let name = dir
+            .canonicalize()
+            .unwrap_or(dir)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "archive".into());
+        let name = format!(
+            "{name}-{}",
+            entry.id.to_hex_with_len(self.options.config.abbrev)
+        );
+        let file = match file {
+            Some(file) => file.to_owned(),
+            None => format!("{name}.tar.gz"),
+        };
+        let format = archive::Format::from_path(&file).unwrap_or(archive::Format::Tar);
+        let out = std::io::BufWriter::new(std::fs::File::create(&file)?);
+        archive::write(
+            &repo,
+            entry.id,
+            &format!("{name}/"),
+            submodules,
+            format,
+            out,
+        )?;
+        self.message = Some(format!("wrote {file}"));
+        Ok(())
+    }
 
-    let mut app = App::new(git_dir, log_entries);
-    app.state.select(Some(0));
+    /// The operation in progress the `C` and `X` keys apply to: the one of the selected entry's
+    /// source, or the first one.
+    fn operation(&self) -> Option<(Source<'repo>, Operation)> {
+        let source = self.view().selected().map(|i| self.view().items[i].1);
+        self.operations
+            .iter()
+            .find(|(s, _)| Some(*s) == source)
+            .or(self.operations.first())
+            .copied()
+    }
 
-    let res = run_app(&mut terminal, app);
+    fn save_session(&self) -> Result<()> {
+        let Some(path) = &self.options.session else {
+            return Ok(());
+        };
+        let view = &self.views[0];
+        let session = Session {
+            selected: view.selected().map(|i| view.items[i].0.id),
+            offset: view.state.offset(),
+            find_object: self.options.filter.find_object,
+            function: self.options.filter.function.clone(),
+            newest: sources(self.roots, self.submodules)
+                .filter_map(|source| {
+                    let head = source.open().ok()?.head_id().ok()?.detach();
+                    Some((source.label(), head))
+                })
+                .collect(),
+        };
+        session::save(path, &session)
+    }
 
-    stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
+    /// Add the history of submodules which have been initialized since the log was loaded.
+    fn load_new_submodules(&mut self) -> Result<()> {
+        let view = &mut self.views[0];
+        let selected = view.selected().map(|i| view.items[i].0.id);
+        let hidden = view.ungroup();
+        let mut unchecked = Vec::new();
+        for source in self.submodules {
+            if view.items.iter().any(|(_, s)| s == source) {
+                continue;
+            }
+            if let Some(repo) = source
+                .submodule()
+                .and_then(|s| source::open_submodule(s).ok().flatten())
+            {
+                let defer = defers(&self.options.config, self.options.prs);
+                let (entries, missing) = log::collect(
+                    &repo,
+                    source,
+                    "HEAD",
+                    &self.options.filter,
+                    defer,
+                    None,
+                    &mut |_, _| true,
+                )?;
+                for entry in entries {
+                    view.items.push((entry, *source));
+                }
+                unchecked.extend(missing.map(|missing| (source.label(), missing)));
+            }
+        }
+        log::sort(&mut view.items, self.options.reverse, self.options.sort);
+        if hidden {
+            view.hide_submodules();
+        }
+        view.measure();
+        if let Some(id) = selected {
+            view.select_id(id);
+        }
+        self.submodule_health = health(self.submodules);
+        for (label, missing) in unchecked {
+            self.report(&label, missing);
+        }
+        Ok(())
+    }
 
-    res
-}
+    /// List the files changed by the entry at `selected` in the background, for `usage`: they
+    /// are shown once listed, unless another entry was selected meanwhile.
+    fn diff(&mut self, selected: usize, usage: DiffUse) -> Result<()> {
+        let (entry, source) = &self.view().items[selected];
+        let (id, repo) = (entry.id, source.open()?);
+        self.diffing = Some((id, usage));
+        self.tasks.spawn(move || {
+            let files = diff::commit_diff(&repo, id).map_err(|err| {
+                match PartialClone::detect(&repo).filter(PartialClone::omits_trees) {
+                    Some(partial) => err.wrap_err(format!("not fetched in this {partial}")),
+                    None => err,
+                }
+            });
+            AppEvent::Diff(id, usage, files)
+        });
+        Ok(())
+    }
 
-enum Action {
-    Quit,
-    Select(usize),
-    Continue,
-}
+    /// Act on what a task found, see [`Tasks`].
+    fn finished(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::Terminal(_) => {}
+            AppEvent::Diff(id, usage, files) => {
+                let selected = self.view().selected();
+                if self.diffing != Some((id, usage))
+                    || selected.is_none_or(|i| self.view().items[i].0.id != id)
+                {
+                    return;
+                }
+                self.diffing = None;
+                let selected = selected.unwrap_or_default();
+                let (context, files) = match usage {
+                    DiffUse::Pane => ("diff", files.map(|files| self.open_diff(selected, files))),
+                    DiffUse::Finder => {
+                        ("files", files.map(|files| self.open_files(selected, files)))
+                    }
+                };
+                if let Err(err) = files {
+                    self.report(context, err);
+                }
+            }
+            AppEvent::Signature(id, signature) => {
+                self.signatures.insert(id, Some(signature));
+                self.refresh_details();
+            }
+            AppEvent::Describe(id, describe) => {
+                if let Some(described) = &mut self.describe {
+                    described.insert(id, describe);
+                }
+                self.refresh_details();
+            }
+            AppEvent::Relation(moves, id, relation) => {
+                if moves == self.moves {
+                    self.relations.insert(id, relation);
+                    self.refresh_details();
+                }
+            }
+            AppEvent::Summary(n, line) => {
+                for view in &mut self.views {
+                    if matches!(view.summary, Summary::Making(making) if making == n) {
+                        view.summary = Summary::Made(line.clone());
+                    }
+                }
+            }
+            AppEvent::Stdin(lines) => self.add_revisions(&lines),
+            AppEvent::Requests(prs) => {
+                self.finding_prs = false;
+                if self.options.prs {
+                    for view in &mut self.views {
+                        view.prs = Some(prs.clone());
+                    }
+                }
+                self.prs = Some(prs);
+                let waiting = self.request_for.take();
+                let items = &self.view().items;
+                let selected = waiting.and_then(|id| items.iter().position(|(e, _)| e.id == id));
+                if let Some(selected) = selected
+                    && let Err(err) = self.open_request(selected)
+                {
+                    self.report("pull request", err);
+                }
+            }
+            AppEvent::Branches(branches) => {
+                self.counting_branches = false;
+                self.message = None;
+                self.show_branches(branches);
+            }
+            AppEvent::Patches(patches) => {
+                self.finding_patches = false;
+                self.patches.extend(patches);
+                let mut commits: HashMap<gix::ObjectId, usize> = HashMap::new();
+                for patch in self.patches.values().flatten() {
+                    *commits.entry(*patch).or_default() += 1;
+                }
+                self.duplicates = (self.patches.iter())
+                    .filter(|(_, patch)| patch.is_some_and(|patch| commits[&patch] > 1))
+                    .map(|(id, _)| *id)
+                    .collect();
+                for view in &mut self.views {
+                    view.duplicates = self.duplicates.clone();
+                }
+                self.message = Some(match self.duplicates.len() {
+                    0 => "no duplicate patches".into(),
+                    n => format!("{n} commits share their patch with another"),
+                });
+                if let Some(selected) = self.view().selected() {
+                    self.jump_to_duplicate(selected);
+                }
+            }
+        }
+    }
 
-fn run_app(terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>, mut app: App) -> Result<()> {
-    loop {
-        terminal.draw(|f| ui(f, &mut app))?;
+    /// Add the commits of the revisions of `lines` to the log tab, unless already there, see
+    /// [`gixl_core::revisions`].
+    fn add_revisions(&mut self, lines: &[String]) {
+        let mut entries = Vec::new();
+        let mut unknown = Vec::new();
+        for rev in lines.iter().flat_map(|line| gixl_core::revisions(line)) {
+            match self.resolver.list(&[rev], &self.options.filter) {
+                Ok(listed) => entries.extend(listed),
+                Err(err) => unknown.push(err),
+            }
+        }
+        let view = &mut self.views[0];
+        let listed = view.all_sources.as_ref().unwrap_or(&view.items);
+        let mut known: HashSet<_> = listed.iter().map(|(entry, _)| entry.id).collect();
+        entries.retain(|(entry, _)| known.insert(entry.id));
+        if !entries.is_empty() {
+            let selected = view.selected().map(|i| view.items[i].0.id);
+            let hidden = view.ungroup();
+            view.items.extend(entries);
+            log::sort(&mut view.items, self.options.reverse, self.options.sort);
+            if hidden {
+                view.hide_submodules();
+            }
+            view.measure();
+            if let Some(id) = selected {
+                view.select_id(id);
+            }
+        }
+        match unknown.len() {
+            0 => {}
+            1 => self.report("stdin", &unknown[0]),
+            n => self.report("stdin", format!("{}, and {} more", unknown[0], n - 1)),
+        }
+    }
 
-        match handle_events(&mut app)? {
-            Action::Quit => break,
-            Action::Select(selected) => {
-                let item = &app.items[selected];
-                let current_dir = if let Some(submodule) = item.1 {
-                    &submodule.git_dir()
-                } else {
-                    &app.git_dir
+    /// Select the next entry of the tab with the same patch as the one at `selected`, finding
+    /// the patch ids of the entries not looked at yet in the background first.
+    fn jump_to_duplicate(&mut self, selected: usize) {
+        if self.finding_patches {
+            self.message = Some("finding duplicate patches…".into());
+            return;
+        }
+        // those of the full log, for duplicates in tabs showing a part of it
+        let mut missing: Vec<(Source, gix::ObjectId)> = Vec::new();
+        for (entry, source) in self.views[0].items.iter().chain(&self.view().items) {
+            if !self.patches.contains_key(&entry.id) && entry.unreadable.is_none() {
+                missing.push((*source, entry.id));
+            }
+        }
+        if !missing.is_empty() {
+            let mut repos: Vec<(Source, Option<gix::Repository>)> = Vec::new();
+            let mut commits = Vec::with_capacity(missing.len());
+            for (source, id) in missing {
+                let i = match repos.iter().position(|(s, _)| *s == source) {
+                    Some(i) => i,
+                    None => {
+                        repos.push((source, source.open().ok()));
+                        repos.len() - 1
+                    }
                 };
-                terminal.backend_mut().execute(LeaveAlternateScreen)?;
-                disable_raw_mode()?;
-                Command::new("git")
-                    .arg("-c")
-                    .arg("core.pager=less -RS +0")
-                    .arg("show")
-                    .arg(&item.0.commit_id)
-                    .current_dir(current_dir)
-                    .status()?;
-                enable_raw_mode()?;
-                terminal.backend_mut().execute(EnterAlternateScreen)?;
-                terminal.clear()?;
+                commits.push((i, id));
             }
-            Action::Continue => (),
+            let repos: Vec<_> = repos.into_iter().map(|(_, repo)| repo).collect();
+            self.finding_patches = true;
+            self.message = Some("finding duplicate patches…".into());
+            self.tasks.spawn(move || {
+                let patches = commits.into_iter().map(|(i, id)| {
+                    let patch = repos[i]
+                        .as_ref()
+                        .and_then(|repo| diff::patch_id(repo, id).ok());
+                    (id, patch.flatten())
+                });
+                AppEvent::Patches(patches.collect())
+            });
+            return;
+        }
+        let view = self.view();
+        let (entry, _) = &view.items[selected];
+        let Some(patch) = self.patches.get(&entry.id).copied().flatten() else {
+            self.message = Some("no patch for this commit, like for a merge".into());
+            return;
+        };
+        let same = |i: &usize| {
+            let id = view.items[*i].0.id;
+            id != entry.id && self.patches.get(&id) == Some(&Some(patch))
+        };
+        let found = (selected + 1..view.items.len())
+            .chain(0..selected)
+            .find(same);
+        match found {
+            Some(i) => {
+                let id = view.items[i].0.id;
+                let label = match view.items[i].1.label() {
+                    label if label.is_empty() => "superproject".into(),
+                    label => label,
+                };
+                self.message = Some(format!(
+                    "same patch as {}, here in {label}",
+                    entry.id.to_hex_with_len(self.options.config.abbrev)
+                ));
+                self.view_mut().select_id(id);
+            }
+            None if self.duplicates.contains(&entry.id) => {
+                self.message = Some("its duplicates aren't in this tab".into());
+            }
+            None => self.message = Some("no other commit has this patch".into()),
         }
     }
 
-    Ok(())
-}
+    /// Summarize the entries of the current tab in the background, unless they already are.
+    fn summarize(&mut self) {
+        let view = &self.views[self.current];
+        if !matches!(view.summary, Summary::Stale) {
+            return;
+        }
+        // what a thread needs of the entries, the authors of those not read yet being read there
+        let mut sources: Vec<(Source, String, Option<gix::Repository>)> = Vec::new();
+        let mut entries = Vec::with_capacity(view.items.len());
+        for (entry, source) in &view.items {
+            let i = match sources.iter().position(|(s, ..)| s == source) {
+                Some(i) => i,
+                None => {
+                    let label = match source.label() {
+                        label if label.is_empty() => "superproject".into(),
+                        label => label,
+                    };
+                    sources.push((*source, label, None));
+                    sources.len() - 1
+                }
+            };
+            let email = match entry.deferred {
+                true => {
+                    let repo = &mut sources[i].2;
+                    if repo.is_none() {
+                        *repo = source.open().ok();
+                    }
+                    None
+                }
+                false => Some(entry.email.clone()),
+            };
+            entries.push((i, entry.id, email, entry.author_time));
+        }
+        let sources = sources
+            .into_iter()
+            .map(|(_, label, repo)| (label, repo))
+            .collect();
+        self.summaries += 1;
+        let n = self.summaries;
+        self.views[self.current].summary = Summary::Making(n);
+        self.tasks
+            .spawn(move || AppEvent::Summary(n, summary(entries, sources)));
+    }
 
-fn handle_events(app: &mut App) -> Result<Action> {
-    if let Event::Key(key) = event::read()?
-        && key.kind == event::KeyEventKind::Press
-    {
-        match key.code {
-            KeyCode::Char('q') => return Ok(Action::Quit),
-            KeyCode::Enter => {
-                if let Some(selected) = app.state.selected() {
-                    return Ok(Action::Select(selected));
+    /// Files changed by the entry at `selected`, to look at their changes or restore them.
+    fn open_files(&mut self, selected: usize, files: Vec<FileDiff>) {
+        let entry = &self.view().items[selected].0;
+        let missing = files.iter().filter(|file| file.missing).count();
+        let candidates = files
+            .into_iter()
+            .map(|file| {
+                let mut text = match &file.previous_path {
+                    Some(previous) => format!("{previous} → {}", file.path),
+                    None => file.path.to_string(),
+                };
+                if file.missing {
+                    text.push_str(" (not fetched)");
+                }
+                (text, Found::File(selected, file))
+            })
+            .collect();
+        let id = entry.id.to_hex_with_len(self.options.config.abbrev);
+        let title = match missing {
+            0 => format!("files of {id} (Enter restore, Ctrl-Y permalink)"),
+            n => format!("files of {id}, {n} not fetched (Enter restore, Ctrl-Y permalink)"),
+        };
+        self.finder = Some(Finder::new(title, candidates));
+    }
+
+    /// Show the diff of the entry at `selected`, changing `files`, in place of the list, the line
+    /// at the top being the one a permalink points to.
+    fn open_diff(&mut self, selected: usize, files: Vec<FileDiff>) {
+        let entry = &self.view().items[selected].0;
+        let paths = files.iter().map(|file| file.path.to_string()).collect();
+        let files = files
+            .iter()
+            .map(|file| {
+                let name = match &file.previous_path {
+                    Some(previous) => format!("{previous} → {}", file.path),
+                    None => file.path.to_string(),
+                };
+                (name, file_preview(file))
+            })
+            .collect();
+        let subject = entry.message.lines().next().unwrap_or_default();
+        let title = format!(
+            "{} {} ([ ] file, {{ }} hunk, z Z A fold hunk, file, all, Ctrl-Y permalink)",
+            entry.id.to_hex_with_len(self.options.config.abbrev),
+            subject.to_str_lossy()
+        );
+        let mut pane = DiffPane::new(title, files);
+        pane.cursor = true;
+        self.pane = Some(pane);
+        self.staging = None;
+        self.diffed = Some((selected, paths));
+    }
+
+    /// Show the changes of the working tree of `source` not staged, then those staged, to stage
+    /// or unstage hunk by hunk, or line by line. Shown again, the position is kept.
+    fn open_staging(&mut self, source: Source<'repo>) -> Result<()> {
+        let changes = stage::changes(&source.open()?)?;
+        if changes.is_empty() && self.staging.is_none() {
+            self.message = Some("stage: no changes".into());
+            return Ok(());
+        }
+        let files = changes
+            .iter()
+            .map(|change| {
+                let state = if change.staged { "staged" } else { "unstaged" };
+                let name = format!("{state} {}", change.path);
+                let mut lines = vec![Line::styled(name.clone(), Style::new().yellow())];
+                match &change.patch {
+                    Some(patch) => lines.extend(patch_preview(patch)),
+                    None => lines.push(Line::raw("binary file")),
                 }
+                (name, lines)
+            })
+            .collect();
+        let title = match source.label() {
+            label if label.is_empty() => "changes".to_owned(),
+            label => format!("changes of {label}"),
+        };
+        let title = format!("{title} (s stage or unstage the hunk, S the line)");
+        let mut pane = DiffPane::new(title, files);
+        pane.cursor = true;
+        if let Some(shown) = &self.pane
+            && self.staging.is_some()
+        {
+            pane.scroll_down(shown.top());
+        }
+        self.pane = Some(pane);
+        self.staging = Some((source, changes));
+        self.diffed = None;
+        Ok(())
+    }
+
+    /// Commit the staged changes of the source of the entry at `selected`, the message being
+    /// written in the editor of git, or with `git commit` to conclude an operation in progress,
+    /// or for what else [`stage::needs_git`].
+    fn commit(&mut self, selected: usize) -> Result<Action<'repo>> {
+        let source = self.view().items[selected].1;
+        let repo = source.open()?;
+        let git = match Operation::in_progress(&repo) {
+            Some(operation) => Some(format!("the {} in progress", operation.label())),
+            None => stage::needs_git(&repo),
+        };
+        if let Some(what) = git {
+            return Ok(Action::GitCommit(source, what));
+        }
+        match stage::index_tree(&repo)? {
+            Some(tree) => Ok(Action::Commit(source, tree)),
+            None => {
+                self.message = Some("commit: no staged changes".into());
+                Ok(Action::Continue)
             }
-            KeyCode::Char('j') | KeyCode::Down => app.next(),
-            KeyCode::Char('k') | KeyCode::Up => app.previous(),
-            KeyCode::PageDown => app.page_down(),
-            KeyCode::PageUp => app.page_up(),
-            KeyCode::Home => app.go_to_start(),
-            KeyCode::End => app.go_to_end(),
-            _ => {}
         }
     }
 
-    Ok(Action::Continue)
-}
+    /// Stage the hunk at the top of the pane, or unstage it when staged, or only its line at the
+    /// top with `line`.
+    fn stage(&mut self, line: bool) -> Result<()> {
+        let (Some(pane), Some((source, changes))) = (&self.pane, &self.staging) else {
+            return Ok(());
+        };
+        let source = *source;
+        let Some((file, Some((hunk, offset)))) = pane.position() else {
+            self.message = Some("stage: scroll to a hunk".into());
+            return Ok(());
+        };
+        let line = match (line, offset) {
+            (false, _) => None,
+            (true, 0) => {
+                self.message = Some("stage: scroll to a changed line".into());
+                return Ok(());
+            }
+            (true, offset) => Some(offset - 1),
+        };
+        let change = changes[file].clone();
+        stage::toggle(&source.open()?, &change, hunk, line)?;
+        self.open_staging(source)?;
+        let what = if line.is_some() { "line" } else { "hunk" };
+        let done = if change.staged { "unstaged" } else { "staged" };
+        self.message = Some(format!("{done} a {what} of {}", change.path));
+        Ok(())
+    }
 
-fn ui(f: &mut Frame, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(100), Constraint::Min(1)].as_ref())
-        .split(f.area());
-    app.list_height = chunks[0].height.saturating_sub(2);
+    /// Ask to restore `file` as of the entry at `selected`, or as of its first parent for a file
+    /// it deleted, warning about uncommitted changes.
+    fn confirm_restore(&mut self, selected: usize, file: FileDiff) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let (entry, source) = &self.view().items[selected];
+        let path = file.path;
+        let status = Command::new("git")
+            .args(["status", "--porcelain", "--"])
+            .arg(path.to_os_str()?)
+            .current_dir(source.dir())
+            .output()?;
+        let id = entry.id.to_hex_with_len(abbrev);
+        let (from, what) = match file.mode {
+            Some(_) => (entry.id, format!("as of {id}")),
+            None => {
+                let parent = entry.parents.first().copied();
+                let parent = parent.ok_or_else(|| {
+                    color_eyre::eyre::eyre!("{path} was deleted by {id}, which has no parent")
+                })?;
+                (parent, format!("as it was before {id} deleted it"))
+            }
+        };
+        let prompt = match status.stdout.is_empty() {
+            true => format!("Restore {path} {what}?"),
+            false => {
+                format!("{path} has uncommitted changes, which will be lost. Restore it {what}?")
+            }
+        };
+        self.confirm = Some((prompt, Action::RestoreFile(selected, path, from)));
+        Ok(())
+    }
 
-    f.render_stateful_widget(&app.list_items, chunks[0], &mut app.state);
+    /// Write `path` of the working tree of the source of the entry at `selected` as it is in
+    /// commit `from`.
+    fn restore_file(&mut self, selected: usize, path: &BString, from: gix::ObjectId) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let source = &self.view().items[selected].1;
+        let output = Command::new("git")
+            .arg("restore")
+            .arg(format!("--source={from}"))
+            .arg("--worktree")
+            .arg("--")
+            .arg(path.to_os_str()?)
+            .current_dir(source.dir())
+            .output()?;
+        self.message = Some(match output.status.success() {
+            true => format!("restored {path} as of {}", from.to_hex_with_len(abbrev)),
+            false => git_failure(&output, "restore"),
+        });
+        Ok(())
+    }
 
-    let status_layout = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(100), Constraint::Min(4)].as_ref())
-        .split(chunks[1]);
-
-    let len = app.items.len();
-    let selected = app.state.selected().unwrap_or(0);
-    let item = &app.items[selected];
-    let status = Line::from(format!(
-        "{} - commit {} of {}",
-        item.0.commit_id,
-        selected + 1,
-        len
-    ))
-    .style(Style::new().white().bold().on_light_blue());
-    f.render_widget(status, status_layout[0]);
-    let perc = Line::from(format!(
-        "{}%",
-        if len > 0 { selected * 100 / len } else { 0 }
+    /// Permalink of the file at `path` as of the entry at `selected`, to `line` if given.
+    fn file_permalink(&self, selected: usize, path: &str, line: Option<usize>) -> Result<String> {
+        let (entry, source) = &self.view().items[selected];
+        let forge = Forge::from_repo(&source.open()?)
+            .ok_or_else(|| color_eyre::eyre::eyre!("no usable remote URL"))?;
+        Ok(forge.blob_url(&entry.id.to_string(), path, line))
+    }
+
+    /// Permalink of the line at the top of the diff shown, in the new version of its file.
+    fn line_permalink(&self) -> Result<Option<String>> {
+        let (Some(pane), Some((selected, paths))) = (&self.pane, &self.diffed) else {
+            return Ok(None);
+        };
+        let Some((file, line)) = pane.new_line() else {
+            return Ok(None);
+        };
+        self.file_permalink(*selected, &paths[file], line).map(Some)
+    }
+
+    /// Permalink of the selected entry on its forge, using the submodule remote when relevant.
+    fn permalink(&self, selected: usize) -> Result<String> {
+        let item = &self.view().items[selected];
+        let repo = item.1.open()?;
+        let forge = Forge::from_repo(&repo)
+            .ok_or_else(|| color_eyre::eyre::eyre!("no usable remote URL"))?;
+        Ok(forge.tree_url(&item.0.id.to_string()))
+    }
+
+    /// Find in the background the pull requests which introduced the entries of the log, once.
+    fn find_requests(&mut self) {
+        if self.finding_prs || self.prs.is_some() {
+            return;
+        }
+        let repos = by_repository(&self.views[0].items);
+        self.finding_prs = true;
+        self.tasks
+            .spawn(move || AppEvent::Requests(pull_requests(repos)));
+    }
+
+    /// Open in the browser the pull or merge request which introduced the entry at `selected`,
+    /// once pull requests are found.
+    fn open_request(&mut self, selected: usize) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let Some(prs) = &self.prs else {
+            self.request_for = Some(self.view().items[selected].0.id);
+            self.message = Some("finding pull requests…".into());
+            self.find_requests();
+            return Ok(());
+        };
+        let (entry, source) = &self.view().items[selected];
+        let Some(number) = prs.get(&entry.id).map(|request| request.number) else {
+            let id = entry.id.to_hex_with_len(abbrev);
+            self.message = Some(format!("no pull request found for {id}"));
+            return Ok(());
+        };
+        let forge = Forge::from_repo(&source.open()?)
+            .ok_or_else(|| color_eyre::eyre::eyre!("no usable remote URL"))?;
+        let url = forge.request_url(number);
+        forge::open_in_browser(&url)?;
+        self.message = Some(format!("opened {url}"));
+        Ok(())
+    }
+
+    /// Pick one side of a comparison; on the second pick, open the symmetric difference in a new
+    /// tab.
+    fn compare_pick(&mut self, selected: usize) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let view = self.view_mut();
+        let Some(from) = view.compare_from.take() else {
+            view.compare_from = Some(selected);
+            self.message = Some("compare: pick the other commit".into());
+            return Ok(());
+        };
+        let source = view.items[from].1;
+        if source != view.items[selected].1 {
+            self.message = Some("compare: commits are from different sources".into());
+            return Ok(());
+        }
+        let repo = source.open()?;
+        let (a, b) = (view.items[from].0.id, view.items[selected].0.id);
+        let entries = log::compare(&repo, a, b)?;
+        if entries.is_empty() {
+            self.message = Some("compare: no difference".into());
+            return Ok(());
+        }
+        let title = format!(
+            "{}...{}",
+            a.to_hex_with_len(abbrev),
+            b.to_hex_with_len(abbrev)
+        );
+        let items = entries.into_iter().map(|entry| (entry, source)).collect();
+        self.open_tab(View::new(title, items));
+        self.message = Some(format!(
+            "compare: < only in {}, > only in {}, = equivalent patch on both",
+            a.to_hex_with_len(abbrev),
+            b.to_hex_with_len(abbrev)
+        ));
+        Ok(())
+    }
+
+    /// Describe the entry at `selected` with `git describe` on a task, once, when enabled.
+    fn describe(&mut self, selected: usize) {
+        let (entry, source) = &self.views[self.current].items[selected];
+        let (id, source) = (entry.id, *source);
+        let Some(describe) = &mut self.describe else {
+            return;
+        };
+        if describe.contains_key(&id) {
+            return;
+        }
+        // nothing is shown until it is found
+        describe.insert(id, None);
+        if let Ok(repo) = source.open() {
+            self.tasks.spawn(move || {
+                AppEvent::Describe(id, refs::describe(&repo, id).unwrap_or_default())
+            });
+        }
+    }
+
+    /// Find how the entry at `selected` stands against HEAD on a task, once.
+    fn relation(&mut self, selected: usize) {
+        let (entry, source) = &self.views[self.current].items[selected];
+        let (id, source) = (entry.id, *source);
+        if self.relations.contains_key(&id) {
+            return;
+        }
+        self.relations.insert(id, None);
+        if let Ok(repo) = source.open() {
+            let moves = self.moves;
+            self.tasks
+                .spawn(move || AppEvent::Relation(moves, id, refs::relation(&repo, id).ok()));
+        }
+    }
+
+    /// Forget how commits stand against HEAD, which moved.
+    fn head_moved(&mut self) {
+        self.relations.clear();
+        self.moves += 1;
+    }
+
+    fn open_finder(&mut self) {
+        self.load_all();
+        let abbrev = self.options.config.abbrev;
+        let mut candidates: Vec<_> = self
+            .view()
+            .items
+            .iter()
+            .map(|(entry, _)| {
+                let subject = entry.message.lines().next().unwrap_or_default();
+                (
+                    format!(
+                        "{} {}",
+                        entry.id.to_hex_with_len(abbrev),
+                        subject.to_str_lossy()
+                    ),
+                    Found::Commit(entry.id),
+                )
+            })
+            .collect();
+        for root in self.roots {
+            for r in refs::list(&root.repo).unwrap_or_default() {
+                let name = match root.name.as_str() {
+                    "" => r.name,
+                    repo => format!("{repo}:{}", r.name),
+                };
+                candidates.push((
+                    format!("{} {name}", r.kind.label()),
+                    Found::Commit(r.target),
+                ));
+            }
+        }
+        self.finder = Some(Finder::new(FIND, candidates));
+    }
+
+    /// Open a finder over the references of every repository, to reload the log from one of them.
+    fn open_ref_switcher(&mut self) {
+        let mut candidates = Vec::new();
+        for (i, root) in self.roots.iter().enumerate() {
+            for r in refs::list(&root.repo).unwrap_or_default() {
+                let text = match root.name.as_str() {
+                    "" => format!("{} {}", r.kind.label(), r.name),
+                    repo => format!("{} {repo}:{}", r.kind.label(), r.name),
+                };
+                candidates.push((text, Found::Ref(i, r)));
+            }
+        }
+        self.finder = Some(Finder::new("switch to", candidates));
+    }
+
+    /// Count in the background how far ahead and behind its upstream and the default branch every
+    /// local branch is, to list them.
+    fn open_branches(&mut self) {
+        if self.counting_branches {
+            return;
+        }
+        let repos: Vec<_> = self.roots.iter().map(|root| root.repo.clone()).collect();
+        self.counting_branches = true;
+        self.message = Some("counting the commits of branches…".into());
+        self.tasks.spawn(move || {
+            let branches = repos.iter().enumerate().map(|(i, repo)| {
+                let default = refs::default_branch(repo);
+                let divergence = refs::divergence(repo, default.as_ref()).unwrap_or_default();
+                (i, divergence, default)
+            });
+            AppEvent::Branches(branches.collect())
+        });
+    }
+
+    /// Open the finder of the branches counted by [`App::open_branches`].
+    fn show_branches(&mut self, branches: Vec<(usize, Vec<Divergence>, Option<RefInfo>)>) {
+        let mut candidates = Vec::new();
+        for (i, divergence, default) in branches {
+            let root = &self.roots[i];
+            for div in divergence {
+                let upstream = match &div.upstream {
+                    Some((name, ahead, behind)) => format!("{name} ↑{ahead} ↓{behind}"),
+                    None => "no upstream".into(),
+                };
+                let default_status = match (&default, div.default) {
+                    (Some(default), Some((ahead, behind))) => {
+                        format!("  {} ↑{ahead} ↓{behind}", default.name)
+                    }
+                    _ => String::new(),
+                };
+                let name = match root.name.as_str() {
+                    "" => div.branch.name.clone(),
+                    repo => format!("{repo}:{}", div.branch.name),
+                };
+                let text = format!("{name}  {upstream}{default_status}");
+                candidates.push((text, Found::Branch(i, div, default.clone())));
+            }
+        }
+        let title = "branches (Enter compare, Ctrl-X delete, Ctrl-R rename)";
+        self.finder = Some(Finder::new(title, candidates));
+    }
+
+    /// Open a finder over the tags of every repository, to reload the log from one of them.
+    fn open_tags(&mut self) {
+        let mut candidates = Vec::new();
+        for (i, root) in self.roots.iter().enumerate() {
+            let tags = refs::list(&root.repo).unwrap_or_default();
+            for r in tags.into_iter().filter(|r| r.kind == RefKind::Tag) {
+                let text = match root.name.as_str() {
+                    "" => r.name.clone(),
+                    repo => format!("{repo}:{}", r.name),
+                };
+                candidates.push((text, Found::Ref(i, r)));
+            }
+        }
+        let title = "tags (Enter switch, Ctrl-X delete, Ctrl-U push)";
+        self.finder = Some(Finder::new(title, candidates));
+    }
+
+    /// The repository of a local branch named `repo:branch`, or only `branch` when looked for
+    /// in every repository, with its name in it.
+    fn find_branch<'a>(&self, name: &'a str) -> Option<(usize, &'a str)> {
+        self.find_ref("refs/heads/", name)
+    }
+
+    /// Open a finder over the remotes of every repository, to add, edit and remove them.
+    fn open_remotes(&mut self) {
+        let mut candidates = Vec::new();
+        for (i, root) in self.roots.iter().enumerate() {
+            for remote in refs::remotes(&root.repo).unwrap_or_default() {
+                let mut text = self.qualified(i, &remote.name);
+                if let Some(url) = &remote.fetch_url {
+                    text += &format!("  {}", url.to_bstring());
+                }
+                if let Some(url) = &remote.push_url {
+                    text += &format!("  push {}", url.to_bstring());
+                }
+                candidates.push((text, Found::Remote(i, remote)));
+            }
+        }
+        self.finder = Some(Finder::new(REMOTES, candidates));
+    }
+
+    /// `name` as given to commands: `repo:name` in a named repository.
+    fn qualified(&self, root: usize, name: &str) -> String {
+        match self.roots[root].name.as_str() {
+            "" => name.to_owned(),
+            repo => format!("{repo}:{name}"),
+        }
+    }
+
+    /// The repository named in `repo:name`, the first one for only `name`, with the name in it.
+    fn root_named<'a>(&self, name: &'a str) -> Option<(usize, &'a str)> {
+        match name.split_once(':') {
+            Some((repo, name)) => {
+                let root = self.roots.iter().position(|root| root.name == repo)?;
+                Some((root, name))
+            }
+            None => Some((0, name)),
+        }
+    }
+
+    /// As [`App::find_branch`], for references under `prefix`.
+    fn find_ref<'a>(&self, prefix: &str, name: &'a str) -> Option<(usize, &'a str)> {
+        self.roots.iter().enumerate().find_map(|(i, root)| {
+            let branch = match name.split_once(':') {
+                Some((repo, branch)) if repo == root.name => branch,
+                Some(_) => return None,
+                None => name,
+            };
+            let reference = format!("{prefix}{branch}");
+            let found = root.repo.try_find_reference(reference.as_str()).ok()?;
+            found.is_some().then_some((i, branch))
+        })
+    }
+
+    /// Ask to delete a local branch, forcing it when it has commits in neither HEAD nor its
+    /// upstream.
+    fn delete_branch_prompt(&mut self, name: &str) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let Some((root, branch)) = self.find_branch(name) else {
+            self.message = Some(format!("delete-branch: no branch {name}"));
+            return Ok(());
+        };
+        let repo = &self.roots[root].repo;
+        let head = repo.head_name()?;
+        if head.is_some_and(|head| head.shorten() == branch) {
+            self.message = Some(format!("delete-branch: {name} is checked out"));
+            return Ok(());
+        }
+        let target = repo.rev_parse_single(format!("refs/heads/{branch}").as_str())?;
+        let short = target.to_hex_with_len(abbrev);
+        let (prompt, force) = match refs::unmerged(repo, branch)? {
+            0 => (format!("Delete branch {name} ({short})?"), false),
+            1 => (
+                format!("{name} ({short}) has 1 commit merged nowhere. Delete it anyway?"),
+                true,
+            ),
+            n => (
+                format!("{name} ({short}) has {n} commits merged nowhere. Delete it anyway?"),
+                true,
+            ),
+        };
+        let action = Action::DeleteBranch(root, branch.to_owned(), target.detach(), force);
+        self.confirm = Some((prompt, action));
+        Ok(())
+    }
+
+    /// Ask to delete a tag.
+    fn delete_tag_prompt(&mut self, name: &str) -> Result<()> {
+        let Some((root, tag)) = self.find_ref("refs/tags/", name) else {
+            self.message = Some(format!("delete-tag: no tag {name}"));
+            return Ok(());
+        };
+        let target = self.roots[root]
+            .repo
+            .rev_parse_single(format!("refs/tags/{tag}").as_str())?;
+        let short = target.to_hex_with_len(self.options.config.abbrev);
+        let action = Action::DeleteTag(root, tag.to_owned(), target.detach());
+        self.confirm = Some((format!("Delete tag {name} ({short})?"), action));
+        Ok(())
+    }
+
+    /// Ask to push a tag to `remote`, or to the default remote for pushing.
+    fn push_tag_prompt(&mut self, name: &str, remote: Option<&str>) {
+        let Some((root, tag)) = self.find_ref("refs/tags/", name) else {
+            self.message = Some(format!("push-tag: no tag {name}"));
+            return;
+        };
+        let repo = &self.roots[root].repo;
+        let remote = match remote {
+            Some(remote) => remote.to_owned(),
+            None => match repo.remote_default_name(gix::remote::Direction::Push) {
+                Some(remote) => remote.to_string(),
+                None => {
+                    self.message = Some("push-tag: no default remote, name one".into());
+                    return;
+                }
+            },
+        };
+        if repo.find_remote(remote.as_str()).is_err() {
+            self.message = Some(format!("push-tag: no remote {remote}"));
+            return;
+        }
+        let prompt = format!("Push tag {name} to {remote}?");
+        self.confirm = Some((prompt, Action::PushTag(root, tag.to_owned(), remote)));
+    }
+
+    /// Change the remotes of a repository with `git remote`, asking first to remove one.
+    fn edit_remote(&mut self, edit: RemoteEdit) -> Result<()> {
+        let (RemoteEdit::Add(name, _)
+        | RemoteEdit::Remove(name)
+        | RemoteEdit::Rename(name, _)
+        | RemoteEdit::SetUrl(name, _, _)) = edit;
+        let found = match edit {
+            RemoteEdit::Add(..) => self.root_named(name),
+            _ => self.roots.iter().enumerate().find_map(|(i, root)| {
+                let remote = match name.split_once(':') {
+                    Some((repo, remote)) if repo == root.name => remote,
+                    Some(_) => return None,
+                    None => name,
+                };
+                root.repo.find_remote(remote).is_ok().then_some((i, remote))
+            }),
+        };
+        let Some((root, remote)) = found else {
+            self.message = Some(format!("remote: no remote {name}"));
+            return Ok(());
+        };
+        let (args, done) = match edit {
+            RemoteEdit::Add(_, url) => (vec!["add", remote, url], format!("added remote {name}")),
+            RemoteEdit::Remove(_) => {
+                let prompt = format!("Remove remote {name} and its remote-tracking branches?");
+                let action = Action::RemoveRemote(root, remote.to_owned());
+                self.confirm = Some((prompt, action));
+                return Ok(());
+            }
+            RemoteEdit::Rename(_, new) => (
+                vec!["rename", remote, new],
+                format!("renamed remote {name} to {new}"),
+            ),
+            RemoteEdit::SetUrl(_, url, false) => (
+                vec!["set-url", remote, url],
+                format!("{name} fetches from {url}"),
+            ),
+            RemoteEdit::SetUrl(_, url, true) => (
+                vec!["set-url", "--push", remote, url],
+                format!("{name} pushes to {url}"),
+            ),
+        };
+        let output = Command::new("git")
+            .arg("remote")
+            .args(args)
+            .current_dir(Source::Root(&self.roots[root]).dir())
+            .output()?;
+        if !output.status.success() {
+            self.message = Some(git_failure(&output, "remote"));
+            return Ok(());
+        }
+        self.refresh_refs();
+        self.message = Some(done);
+        Ok(())
+    }
+
+    /// Rename a local branch with `git branch -m`.
+    fn rename_branch(&mut self, name: &str, new: &str) -> Result<()> {
+        let Some((root, branch)) = self.find_branch(name) else {
+            self.message = Some(format!("rename-branch: no branch {name}"));
+            return Ok(());
+        };
+        let output = Command::new("git")
+            .args(["branch", "-m", branch, new])
+            .current_dir(Source::Root(&self.roots[root]).dir())
+            .output()?;
+        if !output.status.success() {
+            self.message = Some(git_failure(&output, "rename-branch"));
+            return Ok(());
+        }
+        if self.views[0].title == branch {
+            self.views[0].title = new.to_owned();
+        }
+        self.refresh_refs();
+        self.message = Some(format!("renamed {name} to {new}"));
+        Ok(())
+    }
+
+    /// Show the references as changed by an action: the panel is built again.
+    fn refresh_refs(&mut self) {
+        self.head_moved();
+        if self.sidebar.is_some() {
+            let listed = self.views[0].items.iter().map(|(e, _)| e.id).collect();
+            self.sidebar = Some(Sidebar::new(self.roots, &listed));
+        }
+    }
+
+    /// Open a tab of the commits of `branch` not in `base`.
+    fn branch_tab(&mut self, root: usize, branch: &RefInfo, base: &RefInfo) -> Result<()> {
+        let root = &self.roots[root];
+        let items: Vec<_> = log::walk(&root.repo, [branch.target], [base.target])?
+            .map(|entry| Ok((entry?, Source::Root(root))))
+            .collect::<Result<_>>()?;
+        if items.is_empty() {
+            self.message = Some(format!(
+                "{} has no commit not in {}",
+                branch.name, base.name
+            ));
+            return Ok(());
+        }
+        self.open_tab(View::new(format!("only in {}", branch.name), items));
+        Ok(())
+    }
+
+    /// Replace the history of `source` in the log tab by the one of `spec`.
+    fn reload(&mut self, source: Source<'repo>, spec: &str) -> Result<()> {
+        // HEAD may have moved
+        self.head_moved();
+        let defer = defers(&self.options.config, self.options.prs);
+        let (entries, unchecked) = log::collect(
+            &source.open()?,
+            &source,
+            spec,
+            &self.options.filter,
+            defer,
+            None,
+            &mut |_, _| true,
+        )?;
+        let view = &mut self.views[0];
+        let selected = view.selected().map(|i| view.items[i].0.id);
+        let hidden = view.ungroup();
+        view.items.retain(|(_, s)| *s != source);
+        view.items
+            .extend(entries.into_iter().map(|entry| (entry, source)));
+        log::sort(&mut view.items, self.options.reverse, self.options.sort);
+        if hidden {
+            view.hide_submodules();
+        }
+        view.measure();
+        view.go_to_start();
+        if let Some(id) = selected {
+            view.select_id(id);
+        }
+        if let Some(unchecked) = unchecked {
+            self.report(spec, unchecked);
+        }
+        Ok(())
+    }
+
+    /// Replace the history of repository `root` in the log tab by the one of `reference`.
+    fn switch_to(&mut self, root: usize, reference: &RefInfo) -> Result<()> {
+        self.reload(Source::Root(&self.roots[root]), &reference.name)?;
+        self.views[0].title = reference.name.clone();
+        self.views[0].select_id(reference.target);
+        self.current = 0;
+        Ok(())
+    }
+
+    /// Open a tab listing the commits `git rebase -i --autosquash` onto the selected commit would
+    /// replay, in the order it would, with `fixup!` commits under their targets.
+    fn autosquash_preview(&mut self, selected: usize) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let (base, source) = &self.view().items[selected];
+        let (base, source) = (base.id, *source);
+        let commits = rebase::todo(&source.open()?, base)?;
+        if commits.is_empty() {
+            self.message = Some("autosquash: nothing to rebase".into());
+            return Ok(());
+        }
+        let todo = rebase::autosquash(commits);
+        let folded = todo.iter().filter(|(_, f)| *f).map(|(e, _)| e.id).collect();
+        let items = todo.into_iter().map(|(entry, _)| (entry, source)).collect();
+        let mut view = View::new(
+            format!("autosquash onto {}", base.to_hex_with_len(abbrev)),
+            items,
+        );
+        view.rebase_onto = Some((base, &["-i", "--autosquash"]));
+        view.folded = folded;
+        self.open_tab(view);
+        self.message = Some("autosquash: A again to start the rebase".into());
+        Ok(())
+    }
+
+    /// Open a tab of the commits `git rebase <onto>` would replay in the repository of the
+    /// selection, marking those it would drop as already applied.
+    fn rebase_preview(&mut self, onto: &str) -> Result<()> {
+        let Some(selected) = self.view().selected() else {
+            return Ok(());
+        };
+        let source = self.view().items[selected].1;
+        let repo = source.open()?;
+        let base = repo.rev_parse_single(onto)?.object()?.peel_to_commit()?.id;
+        let commits = rebase::replay(&repo, base)?;
+        if commits.is_empty() {
+            self.message = Some(format!("rebase: nothing to replay onto {onto}"));
+            return Ok(());
+        }
+        let dropped = commits.iter().filter(|entry| entry.equivalent).count();
+        let replayed = commits.len() - dropped;
+        let items = commits.into_iter().map(|entry| (entry, source)).collect();
+        let mut view = View::new(format!("rebase onto {onto}"), items);
+        view.rebase_onto = Some((base, &[]));
+        self.open_tab(view);
+        self.message = Some(format!(
+            "rebase: {replayed} replayed, {dropped} dropped as already upstream (=), A to start it"
+        ));
+        Ok(())
+    }
+
+    /// Remember where the HEAD of `source` is, to restore it with `undo` using `git reset <mode>`.
+    fn record_head(&mut self, source: Source<'repo>, mode: &'static str) -> Result<()> {
+        let repo = source.open()?;
+        self.undo.push(Undo {
+            source,
+            branch: repo.head_name()?.map(|name| name.as_bstr().to_string()),
+            id: repo.head_id()?.detach(),
+            mode,
+        });
+        Ok(())
+    }
+
+    /// Move the HEAD of the source of the latest ref-changing action back to where it was.
+    fn undo(&mut self) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let Some(undo) = self.undo.pop() else {
+            self.message = Some("nothing to undo".into());
+            return Ok(());
+        };
+        let repo = undo.source.open()?;
+        let current = repo.head_name()?.map(|name| name.as_bstr().to_string());
+        if current != undo.branch {
+            let branch = undo.branch.as_deref().unwrap_or("detached HEAD");
+            self.message = Some(format!("undo: no longer on {branch}"));
+            return Ok(());
+        }
+        let output = Command::new("git")
+            .args(["reset", undo.mode])
+            .arg(undo.id.to_string())
+            .current_dir(undo.source.dir())
+            .output()?;
+        if !output.status.success() {
+            self.message = Some(git_failure(&output, "undo"));
+            self.undo.push(undo);
+            return Ok(());
+        }
+        self.reload(undo.source, "HEAD")?;
+        self.message = Some(format!(
+            "HEAD restored to {}",
+            undo.id.to_hex_with_len(abbrev)
+        ));
+        Ok(())
+    }
+
+    /// Ask to commit the staged changes as a `fixup!` of the selected commit.
+    fn fixup_prompt(&mut self, selected: usize) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let (entry, source) = &self.view().items[selected];
+        let (id, source) = (entry.id, *source);
+        let staged = Command::new("git")
+            .args(["diff", "--cached", "--quiet"])
+            .current_dir(source.dir())
+            .status()?;
+        if staged.success() {
+            self.message = Some("fixup: no staged changes".into());
+            return Ok(());
+        }
+        let prompt = format!(
+            "Commit the staged changes as a fixup! of {}?",
+            id.to_hex_with_len(abbrev)
+        );
+        self.confirm = Some((prompt, Action::Fixup(source, id)));
+        Ok(())
+    }
+
+    /// Commit the staged changes of `source` as a `fixup!` of `id`, for `git rebase --autosquash`.
+    fn fixup(&mut self, source: Source<'repo>, id: gix::ObjectId) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        // undoing brings the changes back to the index
+        self.record_head(source, "--soft")?;
+        let output = Command::new("git")
+            .arg("commit")
+            .arg(format!("--fixup={id}"))
+            .current_dir(source.dir())
+            .output()?;
+        if !output.status.success() {
+            self.message = Some(git_failure(&output, "fixup"));
+            self.undo.pop();
+            return Ok(());
+        }
+        self.reload(source, "HEAD")?;
+        self.message = Some(format!("created fixup! for {}", id.to_hex_with_len(abbrev)));
+        Ok(())
+    }
+
+    /// Ask to cherry-pick the range of entries of the current tab onto the HEAD of their
+    /// repository, oldest first, or to revert them, newest first, in one commit with `squash`:
+    /// the selected entry when no range is started.
+    fn sequence_prompt(&mut self, operation: Operation, squash: bool) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let label = operation.label();
+        let view = self.view();
+        let Some(range) = view.range().or(view.selected().map(|i| i..=i)) else {
+            return Ok(());
+        };
+        self.load(*range.start()..range.end() + 1);
+        let mut items: Vec<_> = self.view().items[range].iter().collect();
+        let source = items[0].1;
+        if items.iter().any(|(_, s)| *s != source) {
+            self.message = Some(format!("{label}: commits are from different sources"));
+            return Ok(());
+        }
+        if let Some((entry, _)) = items
+            .iter()
+            .find(|(e, _)| e.parents.len() > 1 || e.unreadable.is_some())
+        {
+            let id = entry.id.to_hex_with_len(abbrev);
+            self.message = Some(match entry.unreadable {
+                Some(_) => format!("{label}: {id} can't be read"),
+                None => format!("{label}: {id} is a merge"),
+            });
+            return Ok(());
+        }
+        // parents after their children in a walk: changes are undone in the reverse order
+        match operation {
+            Operation::Revert => items.sort_by_key(|(entry, _)| entry.order),
+            _ => items.sort_by_key(|(entry, _)| Reverse(entry.order)),
+        }
+        let commits: Vec<_> = items
+            .iter()
+            .map(|(entry, _)| {
+                let subject = entry.message.lines().next().unwrap_or_default();
+                (entry.id, subject.to_str_lossy().into_owned())
+            })
+            .collect();
+        let total = commits.len();
+        let squash = match squash && total > 1 {
+            true => {
+                // what git writes for each, after a summary
+                let mut message = format!("Revert {total} commits\n");
+                for (id, subject) in &commits {
+                    message += &format!("\nRevert \"{subject}\"\nThis reverts commit {id}.\n");
+                }
+                Some((source.open()?.head_id()?.detach(), message))
+            }
+            false => None,
+        };
+        let prompt = match (operation, total) {
+            (Operation::Revert, 1) => format!("Revert {}?", commits[0].0.to_hex_with_len(abbrev)),
+            (Operation::Revert, n) if squash.is_some() => format!("Revert {n} commits in one?"),
+            (Operation::Revert, n) => format!("Revert {n} commits, newest first?"),
+            (_, 1) => format!(
+                "Cherry-pick {} onto HEAD?",
+                commits[0].0.to_hex_with_len(abbrev)
+            ),
+            (_, n) => format!("Cherry-pick {n} commits onto HEAD, oldest first?"),
+        };
+        let sequence = Sequence {
+            source,
+            operation,
+            remaining: commits,
+            done: 0,
+            total,
+            squash,
+        };
+        self.confirm = Some((prompt, Action::Sequence(sequence)));
+        Ok(())
+    }
+
+    /// Preview squashing the range of entries of the current tab into its oldest one: the
+    /// messages combined, and the changes of them all. Previewing the same range again asks to
+    /// squash it with `git rebase -i`, the commits following being replayed.
+    fn squash_preview(&mut self) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let view = self.view();
+        let Some(range) = view.range().filter(|range| range.clone().count() > 1) else {
+            self.message = Some("squash: select a range with v first".into());
+            return Ok(());
+        };
+        self.load(*range.start()..range.end() + 1);
+        let mut items = self.view().items[range].to_vec();
+        let source = items[0].1;
+        items.sort_by_key(|(entry, _)| Reverse(entry.order));
+        let commits: Vec<_> = items.iter().map(|(entry, _)| entry.id).collect();
+        let Some(&base) = items[0].0.parents.first() else {
+            self.message = Some("squash: the oldest commit has no parent".into());
+            return Ok(());
+        };
+        if let Some(squash) = self
+            .squash
+            .take_if(|s| s.source == source && s.commits == commits)
+        {
+            if self.refuse_write() {
+                return Ok(());
+            }
+            let prompt = format!("Squash {} commits with git rebase -i?", commits.len());
+            self.confirm = Some((prompt, Action::Squash(squash)));
+            return Ok(());
+        }
+        let repo = source.open()?;
+        let replayed = rebase::todo(&repo, base)?;
+        let in_line = items.iter().all(|(_, s)| *s == source)
+            && replayed.len() >= commits.len()
+            && replayed
+                .iter()
+                .zip(&commits)
+                .all(|(entry, id)| entry.id == *id);
+        if !in_line {
+            self.message = Some("squash: the commits must follow each other in HEAD".into());
+            return Ok(());
+        }
+        let mut todo = String::new();
+        for (i, entry) in replayed.iter().enumerate() {
+            let command = match i {
+                0 => "pick",
+                i if i < commits.len() => "squash",
+                _ => "pick",
+            };
+            let subject = entry.message.lines().next().unwrap_or_default();
+            todo += &format!("{command} {} {}\n", entry.id, subject.to_str_lossy());
+        }
+
+        let mut lines = vec![Line::styled(
+            format!(
+                "{} commits squashed onto {}, their messages combined:",
+                commits.len(),
+                base.to_hex_with_len(abbrev)
+            ),
+            Style::new().yellow(),
+        )];
+        for (entry, _) in &items {
+            lines.push(Line::raw(""));
+            lines.extend(
+                entry
+                    .message
+                    .trim_end()
+                    .lines()
+                    .map(|line| Line::raw(format!("    {}", line.to_str_lossy()))),
+            );
+        }
+        let files = diff::range_diff(&repo, base, commits[commits.len() - 1])?;
+        lines.push(Line::raw(""));
+        lines.push(Line::styled(
+            format!("{} files changed:", files.len()),
+            Style::new().yellow(),
+        ));
+        for file in &files {
+            lines.push(Line::raw(""));
+            lines.extend(file_preview(file));
+        }
+        if !self.options.read_only {
+            let replaying = match replayed.len() - commits.len() {
+                0 => String::new(),
+                1 => ", replaying the commit after them".into(),
+                n => format!(", replaying the {n} commits after them"),
+            };
+            lines.push(Line::raw(""));
+            lines.push(Line::styled(
+                format!("Z again to squash them with git rebase -i{replaying}"),
+                Style::new().gray(),
+            ));
+        }
+        self.popup = Some(Popup::new("squash", lines));
+        self.squash = Some(Squash {
+            source,
+            commits,
+            base,
+            todo,
+        });
+        Ok(())
+    }
+
+    /// Show how applying the commits of `sequence` went, and keep it if it stopped on a
+    /// conflict, with the commits left.
+    fn applied(
+        &mut self,
+        mut sequence: Sequence<'repo>,
+        result: std::io::Result<(usize, Option<std::process::ExitStatus>)>,
+    ) {
+        let abbrev = self.options.config.abbrev;
+        let (source, label, total) = (sequence.source, sequence.operation.label(), sequence.total);
+        self.view_mut().range_from = None;
+        if let Err(err) = self.reload(source, "HEAD") {
+            self.report(label, err);
+            return;
+        }
+        let (applied, status) = match result {
+            Ok(result) => result,
+            Err(err) => return self.report(label, err),
+        };
+        let Some(status) = status else {
+            let plural = if total == 1 { "" } else { "s" };
+            self.message = Some(match (sequence.operation, &sequence.squash) {
+                (Operation::Revert, Some(_)) => format!("reverted {total} commits in one"),
+                (Operation::Revert, None) => format!("reverted {total} commit{plural}"),
+                _ => format!("cherry-picked {total} commit{plural} onto HEAD"),
+            });
+            return;
+        };
+        if applied == sequence.remaining.len() {
+            // all applied, not squashed
+            let message = format!("{status} committing the {total} reverts in one, left staged");
+            return self.report(label, message);
+        }
+        let at = sequence.done + applied + 1;
+        let id = sequence.remaining[applied]
+            .0
+            .to_hex_with_len(abbrev)
+            .to_string();
+        let stopped = source
+            .open()
+            .ok()
+            .and_then(|repo| Operation::in_progress(&repo));
+        if stopped == Some(sequence.operation) {
+            sequence.remaining.drain(..=applied);
+            sequence.done = at;
+            self.sequence = Some(sequence);
+            self.message = Some(format!(
+                "{label} stopped at {at}/{total} {id}: resolve it, then C to continue or X to abort"
+            ));
+        } else {
+            let left = total - at + 1;
+            self.report(
+                label,
+                format!("{status} at {at}/{total} {id}, {left} not applied"),
+            );
+        }
+    }
+
+    /// Summary of the commit of a finder entry.
+    fn preview(&self, found: &Found) -> Vec<Line<'static>> {
+        let id = match found {
+            Found::Commit(id) => *id,
+            Found::Ref(_, reference) => reference.target,
+            Found::Branch(root, div, default) => {
+                return branch_preview(&self.roots[*root].repo, div, default.as_ref());
+            }
+            Found::File(_, file) => return file_preview(file),
+            Found::Remote(root, remote) => return remote_preview(&self.roots[*root], remote),
+        };
+        let Some((entry, source)) = self
+            .views
+            .iter()
+            .flat_map(|view| &view.items)
+            .find(|(e, _)| e.id == id)
+        else {
+            // references of a history not loaded yet
+            let summary = match found {
+                Found::Ref(root, _) => self.roots[*root]
+                    .repo
+                    .find_commit(id)
+                    .ok()
+                    .and_then(|commit| Some(commit.message().ok()?.summary().to_string())),
+                Found::Commit(_) | Found::Branch(..) | Found::File(..) | Found::Remote(..) => None,
+            };
+            return match summary {
+                Some(summary) => vec![
+                    Line::styled(format!("commit {id}"), Style::new().yellow()),
+                    Line::raw(""),
+                    Line::raw(summary),
+                ],
+                None => vec![Line::raw(format!("{id} is not listed"))],
+            };
+        };
+        let mut lines = vec![
+            Line::styled(format!("commit {id}"), Style::new().yellow()),
+            Line::raw(format!("Author: {}", entry.author)),
+            Line::raw(format!("Date:   {}", entry.time())),
+        ];
+        let label = source.label();
+        if !label.is_empty() {
+            lines.push(Line::raw(format!("Source: {label}")));
+        }
+        lines.push(Line::raw(""));
+        lines.extend(
+            entry
+                .message
+                .lines()
+                .map(|line| Line::raw(line.to_str_lossy().into_owned())),
+        );
+        lines
+    }
+
+    /// Annotated tags of the entry at `selected`, with their signatures once verified: those of
+    /// signed tags are verified in the background the first time.
+    fn tags(&mut self, selected: usize) -> Vec<(refs::TagInfo, Option<refs::Signature>)> {
+        let (entry, source) = &self.views[self.current].items[selected];
+        let Ok(repo) = source.open() else {
+            return Vec::new();
+        };
+        let tags = refs::annotated_tags(&repo, entry.id).unwrap_or_default();
+        for tag in &tags {
+            if let Some(err) = &tag.unreadable {
+                let signature = refs::Signature::Unverifiable(err.clone());
+                self.signatures.insert(tag.id, Some(signature));
+            }
+            if !tag.signed || self.signatures.contains_key(&tag.id) {
+                continue;
+            }
+            self.signatures.insert(tag.id, None);
+            let (id, name, git_dir) = (tag.id, tag.name.clone(), repo.git_dir().to_owned());
+            self.tasks
+                .spawn(move || AppEvent::Signature(id, refs::verify_tag(&git_dir, &name)));
+        }
+        tags.into_iter()
+            .map(|tag| {
+                let signature = self.signatures.get(&tag.id).cloned().flatten();
+                (tag, signature)
+            })
+            .collect()
+    }
+
+    /// Show the details again, with what a task found for them.
+    fn refresh_details(&mut self) {
+        if let Some(popup) = &self.popup
+            && popup.title == "details"
+            && let Some(selected) = self.view().selected()
+        {
+            let scroll = popup.scroll;
+            self.show_details(selected);
+            if let Some(popup) = &mut self.popup {
+                popup.scroll = scroll;
+            }
+        }
+    }
+
+    fn show_details(&mut self, selected: usize) {
+        self.describe(selected);
+        self.relation(selected);
+        let id = self.view().items[selected].0.id;
+        let describe = self.describe.as_ref().and_then(|d| d.get(&id)?.clone());
+        let relation = self.relations.get(&id).copied().flatten();
+        let tags = self.tags(selected);
+        let abbrev = self.options.config.abbrev;
+        let format = self.view().date;
+        // like in the list
+        let date = |time| format.format(time).trim_end().to_owned();
+        let (entry, source) = &self.views[self.current].items[selected];
+        let field = |name: &str, value: String| {
+            Line::from(vec![
+                Span::styled(format!("{name:<10}"), Style::new().gray()),
+                Span::raw(value),
+            ])
+        };
+        let mut lines = vec![
+            field("commit", entry.id.to_string()),
+            field("author", entry.author.to_string()),
+            field("date", date(entry.author_time)),
+            field("committer", entry.committer.to_string()),
+            field("committed", date(entry.committer_time)),
+        ];
+        if !source.label().is_empty() {
+            lines.push(field("source", source.label()));
+        }
+        match entry.decoding {
+            Decoding::Utf8 => {}
+            Decoding::Converted(encoding) => {
+                lines.push(field(
+                    "encoding",
+                    format!("{}, shown as UTF-8", encoding.name()),
+                ));
+            }
+            Decoding::Lossy(encoding) => lines.push(field(
+                "encoding",
+                format!("not valid {}, shown with � where it isn't", encoding.name()),
+            )),
+        }
+        if entry.boundary {
+            lines.push(field(
+                "boundary",
+                "left out of the range, parent of a commit in it".into(),
+            ));
+        } else if entry.is_root() {
+            lines.push(field(
+                "root",
+                "first commit of its history, without parents".into(),
+            ));
+        }
+        let failures = self.views[self.current].lint_failures(selected);
+        if !failures.is_empty() {
+            lines.push(field("lint", failures.join(", ")));
+        }
+        if let Some(Some(patch)) = self.patches.get(&entry.id)
+            && self.duplicates.contains(&entry.id)
+        {
+            let same = self.views[0].items.iter().filter(|(other, _)| {
+                other.id != entry.id && self.patches.get(&other.id) == Some(&Some(*patch))
+            });
+            let same: Vec<_> = same
+                .map(|(other, source)| match source.label() {
+                    label if label.is_empty() => other.id.to_hex_with_len(abbrev).to_string(),
+                    label => format!("{} in {label}", other.id.to_hex_with_len(abbrev)),
+                })
+                .collect();
+            lines.push(field("patch", format!("also {}", same.join(", "))));
+        }
+        let repo = source.open().ok();
+        if let Some(partial) = repo.as_ref().and_then(PartialClone::detect) {
+            lines.push(field("clone", partial.to_string()));
+        }
+        if let Some(repo) = repo.as_ref().filter(|repo| replace::enabled(repo))
+            && let Ok(reference) =
+                repo.find_reference(&format!("{}{}", replace::ref_base(), entry.id))
+            && let Some(replacement) = reference.target().try_id()
+        {
+            lines.push(field(
+                "replaced",
+                format!(
+                    "by {}, shown as written with --no-replace-objects",
+                    replacement.to_hex_with_len(12)
+                ),
+            ));
+        }
+        if let Some(describe) = describe {
+            lines.push(field("describe", describe));
+        }
+        if let Some(relation) = relation {
+            lines.push(field("checkout", relation.describe(abbrev)));
+        }
+        lines.push(Line::default());
+        lines.extend(
+            entry
+                .message
+                .lines()
+                .map(|line| Line::from(line.to_str_lossy().into_owned())),
+        );
+
+        if let Source::Root(root) = source {
+            for bump in submodule::bumps(&root.repo, entry.id).unwrap_or_default() {
+                let short = |id: gix::ObjectId| {
+                    if id.is_null() {
+                        "(none)".to_owned()
+                    } else {
+                        id.to_hex_with_len(12).to_string()
+                    }
+                };
+                lines.push(Line::default());
+                lines.push(field(
+                    "submodule",
+                    format!("{} {} → {}", bump.path, short(bump.old), short(bump.new)),
+                ));
+                for commit in bump.commits.iter().flatten() {
+                    let subject = commit.message.lines().next().unwrap_or_default();
+                    lines.push(Line::from(vec![
+                        Span::raw("    "),
+                        Span::styled(
+                            commit.id.to_hex_with_len(12).to_string(),
+                            Style::new().yellow(),
+                        ),
+                        Span::raw(format!(" {}", subject.to_str_lossy())),
+                    ]));
+                }
+            }
+        }
+
+        for (tag, signature) in tags {
+            lines.push(Line::default());
+            lines.push(field("tag", tag.name));
+            if let Some(tagger) = tag.tagger {
+                lines.push(field("tagger", tagger));
+            }
+            if let Some(time) = tag.time {
+                lines.push(field("date", time));
+            }
+            let report = match signature {
+                Some(refs::Signature::Unverifiable(err)) => Some(Span::styled(
+                    format!("unverifiable: {err}"),
+                    Style::new().yellow(),
+                )),
+                _ if !tag.signed => None,
+                None => Some(Span::styled("verifying…", Style::new().dark_gray())),
+                Some(refs::Signature::Good(report)) => {
+                    Some(Span::styled(report, Style::new().green()))
+                }
+                Some(refs::Signature::Bad(report)) => {
+                    Some(Span::styled(report, Style::new().red()))
+                }
+            };
+            if let Some(report) = report {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:<10}", "signature"), Style::new().gray()),
+                    report,
+                ]));
+            }
+            lines.push(Line::default());
+            lines.extend(
+                tag.message
+                    .lines()
+                    .map(|line| Line::from(format!("    {}", line.to_str_lossy()))),
+            );
+        }
+        self.popup = Some(Popup::new("details", lines));
+    }
+
+    /// Popup listing branches and tags from which the selected commit is reachable.
+    fn show_containing(&mut self, selected: usize) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let (entry, source) = &self.views[self.current].items[selected];
+        let repo = source.open()?;
+        let refs = refs::list(&repo)?;
+        let mut lines: Vec<Line> = refs::containing(&repo, entry.id, &refs)?
+            .into_iter()
+            .map(|r| {
+                Line::from(vec![
+                    Span::styled(format!("{:<7}", r.kind.label()), Style::new().gray()),
+                    Span::raw(r.name.clone()),
+                ])
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("no branch or tag contains this commit"));
+        }
+        self.popup = Some(Popup::new(
+            format!("refs containing {}", entry.id.to_hex_with_len(abbrev)),
+            lines,
+        ));
+        Ok(())
+    }
+
+    /// Select the superproject commit that first included the selected submodule commit.
+    fn jump_to_landing(&mut self, selected: usize) -> Result<()> {
+        let abbrev = self.options.config.abbrev;
+        let (entry, source @ Source::Submodule(root, submodule)) =
+            &self.views[self.current].items[selected]
+        else {
+            self.message = Some("not a submodule commit".into());
+            return Ok(());
+        };
+        let sub = source.open()?;
+        let path = submodule.path()?.into_owned();
+        let mut candidates: Vec<_> = self.views[self.current]
+            .items
+            .iter()
+            .filter(|(_, s)| *s == Source::Root(root))
+            .map(|(e, _)| (e.author_time, e.id))
+            .collect();
+        candidates.sort_by_key(|(time, _)| *time);
+        let landing = submodule::landing(
+            &root.repo,
+            &sub,
+            &path,
+            entry.id,
+            candidates.into_iter().map(|(_, id)| id),
+        )?;
+        let id = entry.id;
+        if landing.is_some_and(|landing| self.view_mut().select_id(landing)) {
+            self.message = Some(format!("{} landed here", id.to_hex_with_len(abbrev)));
+        } else {
+            self.message = Some("not included in any listed superproject commit".into());
+        }
+        Ok(())
+    }
+}
+
+fn health(submodules: &[Source]) -> Vec<(String, Option<submodule::Health>)> {
+    submodules
+        .iter()
+        .filter_map(|source| {
+            let health = submodule::health(source.submodule()?).ok();
+            Some((source.label(), health))
+        })
+        .collect()
+}
+
+/// The changes of a file, colored.
+fn file_preview(file: &FileDiff) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::styled(file.path.to_string(), Style::new().yellow())];
+    let Some(patch) = &file.patch else {
+        lines.push(Line::raw(match file.missing {
+            true => "not fetched, left out of this partial clone",
+            false => "binary or special file",
+        }));
+        return lines;
+    };
+    lines.extend(patch_preview(patch));
+    lines
+}
+
+/// The lines of hunks in unified format, colored.
+fn patch_preview(patch: &[u8]) -> impl Iterator<Item = Line<'static>> {
+    patch.lines().map(|line| {
+        let style = match line.first() {
+            Some(b'+') => Style::new().green(),
+            Some(b'-') => Style::new().red(),
+            Some(b'@') => Style::new().cyan(),
+            _ => Style::new(),
+        };
+        Line::styled(line.to_str_lossy().into_owned(), style)
+    })
+}
+
+/// How a branch stands, with its first commits not in the default branch.
+fn remote_preview(root: &Root, remote: &RemoteInfo) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::styled(
+        format!("remote {}", remote.name),
+        Style::new().yellow(),
+    )];
+    let fetch_url = remote.fetch_url.as_ref();
+    lines.push(Line::raw(match fetch_url {
+        Some(url) => format!("fetch {}", url.to_bstring()),
+        None => "no URL".into(),
+    }));
+    lines.push(Line::raw(match (&remote.push_url, fetch_url) {
+        (Some(url), _) => format!("push  {}", url.to_bstring()),
+        (None, Some(_)) => "push  to the same URL".into(),
+        (None, None) => "no push URL".into(),
+    }));
+    if let Some(forge) = remote
+        .push_url
+        .as_ref()
+        .or(fetch_url)
+        .and_then(Forge::from_url)
+    {
+        lines.push(Line::raw(format!("forge {}", forge.base)));
+    }
+    let default = root.repo.remote_default_name(gix::remote::Direction::Fetch);
+    if default.is_some_and(|default| *default == remote.name.as_str()) {
+        lines.push(Line::raw("default remote"));
+    }
+    lines.push(Line::raw(""));
+    lines.extend(remote.refspecs.iter().map(|spec| Line::raw(spec.clone())));
+    lines.push(Line::raw(match remote.branches {
+        1 => "1 remote-tracking branch".into(),
+        n => format!("{n} remote-tracking branches"),
+    }));
+    lines
+}
+
+fn branch_preview(
+    repo: &gix::Repository,
+    div: &Divergence,
+    default: Option<&RefInfo>,
+) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::styled(
+        format!("branch {}", div.branch.name),
+        Style::new().yellow(),
+    )];
+    lines.push(Line::raw(match &div.upstream {
+        Some((name, ahead, behind)) => format!("{ahead} ahead of {name}, {behind} behind"),
+        None => "no upstream".into(),
+    }));
+    let Some(default) = default else {
+        return lines;
+    };
+    if let Some((ahead, behind)) = div.default {
+        lines.push(Line::raw(format!(
+            "{ahead} ahead of {}, {behind} behind",
+            default.name
+        )));
+    }
+    lines.push(Line::raw(""));
+    let only = log::walk(repo, [div.branch.target], [default.target]);
+    for entry in only.into_iter().flatten().take(20).flatten() {
+        let subject = entry.message.lines().next().unwrap_or_default();
+        lines.push(Line::raw(format!("- {}", subject.to_str_lossy())));
+    }
+    lines
+}
+
+/// Every root and submodule.
+fn sources<'repo>(
+    roots: &'repo [Root],
+    submodules: &[Source<'repo>],
+) -> impl Iterator<Item = Source<'repo>> {
+    roots
+        .iter()
+        .map(Source::Root)
+        .chain(submodules.iter().copied())
+}
+
+/// Commits reachable from the HEAD of each source but not from its HEAD in the previous run,
+/// `newest`.
+fn new_since(
+    roots: &[Root],
+    submodules: &[Source],
+    newest: &[(String, gix::ObjectId)],
+) -> HashSet<gix::ObjectId> {
+    let mut new = HashSet::new();
+    for source in sources(roots, submodules) {
+        let label = source.label();
+        let Some((_, seen)) = newest.iter().find(|(l, _)| *l == label) else {
+            continue;
+        };
+        let Ok(repo) = source.open() else {
+            continue;
+        };
+        let Ok(head) = repo.head_id() else {
+            continue;
+        };
+        // the previous HEAD may be gone, after a rebase and a gc
+        let Ok(walk) = log::walk(&repo, [head.detach()], [*seen]) else {
+            continue;
+        };
+        new.extend(walk.filter_map(|entry| Some(entry.ok()?.id)));
+    }
+    new
+}
+
+/// The entries of `items` with the repository they are in, for [`pull_requests`].
+fn by_repository(items: &[Item]) -> Vec<(gix::Repository, Vec<LogEntryInfo>)> {
+    let mut sources: Vec<(Source, Vec<LogEntryInfo>)> = Vec::new();
+    for (entry, source) in items {
+        match sources.iter_mut().find(|(s, _)| s == source) {
+            Some((_, entries)) => entries.push(entry.clone()),
+            None => sources.push((*source, vec![entry.clone()])),
+        }
+    }
+    sources
+        .into_iter()
+        .filter_map(|(source, entries)| Some((source.open().ok()?, entries)))
+        .collect()
+}
+
+/// The pull or merge requests which introduced the entries of each repository.
+fn pull_requests(
+    repos: Vec<(gix::Repository, Vec<LogEntryInfo>)>,
+) -> HashMap<gix::ObjectId, Request> {
+    let mut requests = HashMap::new();
+    for (repo, entries) in repos {
+        let kind = Forge::from_repo(&repo).map_or(ForgeKind::Other, |forge| forge.kind);
+        for (id, number) in pr::numbers(&repo, entries).unwrap_or_default() {
+            requests.insert(id, Request { kind, number });
+        }
+    }
+    requests
+}
+
+fn operations<'repo>(
+    roots: &'repo [Root],
+    submodules: &[Source<'repo>],
+) -> Vec<(Source<'repo>, Operation)> {
+    let roots = roots
+        .iter()
+        .map(|root| (Source::Root(root), Operation::in_progress(&root.repo)));
+    let submodules = submodules.iter().map(|source| {
+        (
+            *source,
+            source
+                .open()
+                .ok()
+                .and_then(|repo| Operation::in_progress(&repo)),
+        )
+    });
+    roots
+        .chain(submodules)
+        .filter_map(|(source, state)| Some((source, state?)))
+        .collect()
+}
+
+/// Whether the list can do with deferred entries, reading their messages as they are shown, see
+/// [`LogEntryInfo::deferred`]: not when columns, checks of the messages, pull requests or the
+/// `on-load-complete` hook need the messages of all of them.
+pub fn defers(config: &Config, prs: bool) -> bool {
+    config.columns.is_empty()
+        && !config.lint.enabled()
+        && !prs
+        && !config
+            .hooks
+            .iter()
+            .any(|(hook, _)| *hook == Hook::LoadComplete)
+}
+
+/// Time an entry stays selected before the `on-select` hook runs for it.
+const SETTLE: Duration = Duration::from_millis(150);
+
+/// Time between two frames showing the progress of loading.
+const LOADING_FRAME: Duration = Duration::from_millis(100);
+
+/// The terminal the UI is drawn on, taken before the log is loaded to show how far loading goes,
+/// and given back when dropped.
+pub struct Screen {
+    terminal: Term,
+    inline: bool,
+    /// Commits read so far in each source, by label.
+    read: Vec<(String, usize)>,
+    /// When loading was last drawn, and how many times.
+    drawn: Option<(Instant, usize)>,
+    stopped: bool,
+}
+
+impl Screen {
+    /// Take the terminal, drawing on standard error for a picker, whose output is captured by its
+    /// caller, in a viewport of `inline` rows below the cursor if given.
+    pub fn open(pick: bool, inline: Option<u16>) -> Result<Screen> {
+        let out: Box<dyn Write> = match pick {
+            true => Box::new(stderr()),
+            false => Box::new(stdout()),
+        };
+        let viewport = match inline {
+            Some(height) => Viewport::Inline(height),
+            None => Viewport::Fullscreen,
+        };
+        let mut terminal =
+            Terminal::with_options(CrosstermBackend::new(out), TerminalOptions { viewport })?;
+        if inline.is_none() {
+            terminal.backend_mut().execute(EnterAlternateScreen)?;
+        }
+        enable_raw_mode()?;
+        Ok(Screen {
+            terminal,
+            inline: inline.is_some(),
+            read: Vec::new(),
+            drawn: None,
+            stopped: false,
+        })
+    }
+
+    /// Show in the status bar that `commits` of `source` were read so far, with those of the
+    /// sources walked before, telling whether to go on: not once Esc or Ctrl-C is pressed.
+    pub fn loading(&mut self, source: &Source, commits: usize) -> bool {
+        let label = match source.label() {
+            label if label.is_empty() => "superproject".into(),
+            label => label,
+        };
+        match self.read.iter_mut().find(|(l, _)| *l == label) {
+            Some((_, n)) => *n = commits,
+            None => self.read.push((label, commits)),
+        }
+        while let Ok(true) = event::poll(Duration::ZERO) {
+            if let Ok(Event::Key(key)) = event::read()
+                && (key.code == KeyCode::Esc
+                    || key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL))
+            {
+                self.stopped = true;
+            }
+        }
+        let frame = match self.drawn {
+            Some((at, _)) if at.elapsed() < LOADING_FRAME => return !self.stopped,
+            Some((_, frame)) => frame + 1,
+            None => 0,
+        };
+        self.drawn = Some((Instant::now(), frame));
+        let spinner = ["|", "/", "-", "\\"][frame % 4];
+        let read: Vec<_> = self
+            .read
+            .iter()
+            .map(|(label, n)| format!("{label} {n}"))
+            .collect();
+        let text = format!(
+            "{spinner} loading: {} commits - Esc to stop",
+            read.join(", ")
+        );
+        let _ = self.terminal.draw(|f| {
+            let [_, status] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.area());
+            let line = Line::from(text).style(Style::new().white().bold().on_light_blue());
+            f.render_widget(line, status);
+        });
+        !self.stopped
+    }
+
+    /// Whether loading was stopped before the end, the log being incomplete.
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = self.terminal.backend_mut().execute(DisableMouseCapture);
+        if self.inline {
+            // leave the prompt where the viewport was
+            let _ = self.terminal.clear();
+            let _ = self.terminal.show_cursor();
+        } else {
+            let _ = self.terminal.backend_mut().execute(LeaveAlternateScreen);
+        }
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Browse `log_entries` on `screen`, returning what was picked with `--pick`. The `profile`
+/// ends once the list is first drawn.
+pub fn run<'repo>(
+    mut screen: Screen,
+    roots: &'repo [Root],
+    submodules: &'repo [Source<'repo>],
+    log_entries: Vec<Item<'repo>>,
+    options: Options,
+    profile: Option<&mut Profile>,
+) -> Result<Option<String>> {
+    let mut app = App::new(roots, submodules, log_entries, options);
+    app.start();
+    if let Some(profile) = profile {
+        draw(&mut screen.terminal, &mut app)?;
+        profile.end();
+    }
+    run_app(&mut screen.terminal, app)
+}
+
+/// The UI driven by synthetic events and drawn into a [`TestBackend`] instead of the terminal,
+/// for golden tests of its frames.
+///
+/// Colors don't depend on the terminal: an automatic background is dark, with 16 colors unless
+/// configured otherwise. Actions running other programs, like showing a commit with Enter, or
+/// changing the repositories are not carried out.
+pub struct Headless<'repo> {
+    app: App<'repo>,
+    terminal: Terminal<TestBackend>,
+}
+
+impl<'repo> Headless<'repo> {
+    /// The UI of `log_entries`, as [`run`] would show it, in a `width` by `height` terminal.
+    pub fn new(
+        roots: &'repo [Root],
+        submodules: &'repo [Source<'repo>],
+        log_entries: Vec<Item<'repo>>,
+        mut options: Options,
+        width: u16,
+        height: u16,
+    ) -> Result<Headless<'repo>> {
+        let config = &mut options.config;
+        if config.background == Background::Auto {
+            config.background = Background::Dark;
+        }
+        config.depth.get_or_insert(Depth::Ansi16);
+        let mut app = App::new(roots, submodules, log_entries, options);
+        app.start();
+        let terminal = Terminal::new(TestBackend::new(width, height))?;
+        let mut headless = Headless { app, terminal };
+        headless.draw()?;
+        Ok(headless)
+    }
+
+    /// Draw the next frame once the tasks started are finished, for frames not to depend on
+    /// how long they take.
+    fn draw(&mut self) -> Result<()> {
+        draw(&mut self.terminal, &mut self.app)?;
+        while self.app.tasks.running() {
+            for event in self.app.tasks.wait() {
+                self.app.finished(event);
+            }
+            draw(&mut self.terminal, &mut self.app)?;
+        }
+        Ok(())
+    }
+
+    /// Handle `event` and draw the next frame. Returns whether the UI was left, with q or by
+    /// picking an entry.
+    pub fn send(&mut self, event: Event) -> Result<bool> {
+        let action = handle_event(&mut self.app, event);
+        self.draw()?;
+        Ok(matches!(action, Action::Quit | Action::Pick(_)))
+    }
+
+    /// Press and release the key `code`, without modifiers.
+    pub fn press(&mut self, code: KeyCode) -> Result<bool> {
+        self.send(Event::Key(event::KeyEvent::from(code)))
+    }
+
+    /// Press the keys of the characters of `keys`, one after the other, until the UI is left.
+    pub fn keys(&mut self, keys: &str) -> Result<bool> {
+        for c in keys.chars() {
+            if self.press(KeyCode::Char(c))? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Resize the terminal, the next frame being drawn at once.
+    pub fn resize(&mut self, width: u16, height: u16) -> Result<()> {
+        self.terminal.backend_mut().resize(width, height);
+        self.draw()
+    }
+
+    /// The last frame, with its styles.
+    pub fn buffer(&self) -> &Buffer {
+        self.terminal.backend().buffer()
+    }
+
+    /// The text of the last frame, one line per row, trailing spaces trimmed.
+    pub fn screen(&self) -> String {
+        let buffer = self.buffer();
+        let mut screen = String::new();
+        for y in 0..buffer.area.height {
+            let line: String = (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect();
+            screen.push_str(line.trim_end());
+            screen.push('\n');
+        }
+        screen
+    }
+}
+
+/// Replace in `template` `%H` and `%h` by the full and abbreviated commit id, `%an` by the
+/// author, `%s` by the subject and `%D` by the directory of the repository or submodule.
+fn format_entry(template: &str, entry: &LogEntryInfo, source: &Source, abbrev: usize) -> String {
+    let subject = entry.message.lines().next().unwrap_or_default();
+    // in one pass, so that placeholders in the values, like `%h` in a subject, are left alone
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(i) = rest.find('%') {
+        text.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let (value, len) = match rest.as_bytes() {
+            [b'H', ..] => (entry.id.to_string(), 1),
+            [b'h', ..] => (entry.id.to_hex_with_len(abbrev).to_string(), 1),
+            [b'a', b'n', ..] => (entry.author.to_string(), 2),
+            [b's', ..] => (subject.to_str_lossy().into_owned(), 1),
+            [b'D', ..] => (source.dir().to_string_lossy().into_owned(), 1),
+            _ => ("%".to_owned(), 0),
+        };
+        text.push_str(&value);
+        rest = &rest[len..];
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Split a command template into arguments, and fill in the placeholders of each.
+fn expand(
+    template: &str,
+    entry: &LogEntryInfo,
+    source: &Source,
+    abbrev: usize,
+) -> Result<Vec<String>> {
+    let args = shell_words::split(template)?;
+    Ok(args
+        .iter()
+        .map(|arg| format_entry(arg, entry, source, abbrev))
+        .collect())
+}
+
+type Term = Terminal<CrosstermBackend<Box<dyn Write>>>;
+
+/// Leave the alternate screen while `f` runs a program that needs the terminal. An `inline`
+/// viewport is left alone instead, the program running on the alternate screen.
+///
+/// Only failing to get the terminal back is an error, that of `f` being returned to report.
+fn suspend<T>(
+    terminal: &mut Term,
+    inline: bool,
+    f: impl FnOnce() -> std::io::Result<T>,
+) -> Result<std::io::Result<T>> {
+    let backend = terminal.backend_mut();
+    backend.execute(DisableMouseCapture)?;
+    match inline {
+        true => backend.execute(EnterAlternateScreen)?,
+        false => backend.execute(LeaveAlternateScreen)?,
+    };
+    disable_raw_mode()?;
+    let res = f();
+    #[cfg(windows)]
+    restore_console()?;
+    enable_raw_mode()?;
+    let backend = terminal.backend_mut();
+    match inline {
+        true => backend.execute(LeaveAlternateScreen)?,
+        false => backend.execute(EnterAlternateScreen)?,
+    };
+    terminal.clear()?;
+    Ok(res)
+}
+
+/// `git <command> <id>` in a pager keeping colors and long lines unwrapped.
+///
+/// Git for Windows runs the pager through its own shell, where `less` may not exist (MinGit):
+/// its configured pager is kept there, only told how to behave when it is `less`.
+fn paged_command(command: &str, id: gix::ObjectId) -> Command {
+    let mut cmd = Command::new("git");
+    if cfg!(windows) {
+        if std::env::var_os("LESS").is_none() {
+            cmd.env("LESS", "FRS");
+        }
+    } else {
+        cmd.arg("-c").arg("core.pager=less -RS +0");
+    }
+    cmd.arg(command).arg(id.to_string());
+    cmd
+}
+
+/// Programs like the MSYS `less` of Git for Windows reset the console mode on exit: turn the
+/// processing of escape sequences back on, crossterm only does it once at startup.
+#[cfg(windows)]
+fn restore_console() -> std::io::Result<()> {
+    use crossterm_winapi::{ConsoleMode, Handle};
+
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+    let mode = ConsoleMode::from(Handle::current_out_handle()?);
+    mode.set_mode(mode.mode()? | ENABLE_VIRTUAL_TERMINAL_PROCESSING)
+}
+
+/// HEAD of a source before a ref-changing action.
+struct Undo<'repo> {
+    source: Source<'repo>,
+    /// Checked out branch, `None` when detached.
+    branch: Option<String>,
+    id: gix::ObjectId,
+    /// `git reset` mode restoring it, `--soft` to keep the undone changes staged.
+    mode: &'static str,
+}
+
+/// Commits cherry-picked or reverted one by one.
+struct Sequence<'repo> {
+    source: Source<'repo>,
+    /// [`Operation::CherryPick`] or [`Operation::Revert`].
+    operation: Operation,
+    /// Commits left to apply, in order, with their subject.
+    remaining: Vec<(gix::ObjectId, String)>,
+    /// How many were applied before, a commit stopped on a conflict counting once resolved.
+    done: usize,
+    total: usize,
+    /// HEAD before the sequence, and the message of the one commit they are squashed into
+    /// on top of it, to edit.
+    squash: Option<(gix::ObjectId, String)>,
+}
+
+/// Apply the commits left of `sequence` one by one in `dir`, telling which one is being applied,
+/// then squash them all when asked, the message being edited. Returns how many were applied,
+/// and how git failed on the next one if it did, stopping there, or squashing them.
+fn apply(
+    dir: &Path,
+    sequence: &Sequence,
+) -> std::io::Result<(usize, Option<std::process::ExitStatus>)> {
+    let args: &[&str] = match sequence.operation {
+        Operation::Revert => &["revert", "--no-edit"],
+        _ => &["cherry-pick", "--allow-empty"],
+    };
+    // on standard error, like the UI, the output of a picker being captured by its caller
+    let git = || {
+        let mut git = Command::new("git");
+        git.current_dir(dir).stdout(std::io::stderr());
+        git
+    };
+    for (i, (id, subject)) in sequence.remaining.iter().enumerate() {
+        let at = sequence.done + i + 1;
+        eprintln!("[{at}/{}] {id} {subject}", sequence.total);
+        let status = git().args(args).arg(id.to_string()).status()?;
+        if !status.success() {
+            return Ok((i, Some(status)));
+        }
+    }
+    let applied = sequence.remaining.len();
+    if let Some((base, message)) = &sequence.squash {
+        let mut status = git()
+            .args(["reset", "--soft"])
+            .arg(base.to_string())
+            .status()?;
+        if status.success() {
+            status = git().args(["commit", "--edit", "-m", message]).status()?;
+        }
+        if !status.success() {
+            return Ok((applied, Some(status)));
+        }
+    }
+    Ok((applied, None))
+}
+
+/// Commits of the history of HEAD to squash into the oldest one with `git rebase -i`.
+struct Squash<'repo> {
+    source: Source<'repo>,
+    /// The commits squashed, oldest first.
+    commits: Vec<gix::ObjectId>,
+    /// Parent of the oldest commit, the rebase starts from.
+    base: gix::ObjectId,
+    /// Todo list of the rebase, the commits after those squashed being picked again.
+    todo: String,
+}
+
+/// Run `git rebase -i <base>` in `dir` with `todo` instead of the list to edit, git still
+/// asking for the messages to write, of a squashed or reworded commit. The todo is written in the
+/// git directory `git_dir` for the time of the rebase.
+fn rebase_with(
+    dir: &Path,
+    git_dir: &Path,
+    base: gix::ObjectId,
+    todo: &str,
+) -> std::io::Result<std::process::ExitStatus> {
+    let file = git_dir.join("gixl-rebase-todo");
+    std::fs::write(&file, todo)?;
+    // git runs the editor with a shell
+    let path = file.to_string_lossy().replace('\'', "'\\''");
+    let status = Command::new("git")
+        .args(["rebase", "-i"])
+        .arg(base.to_string())
+        .env("GIT_SEQUENCE_EDITOR", format!("cp '{path}'"))
+        .current_dir(dir)
+        .status();
+    std::fs::remove_file(&file)?;
+    status
+}
+
+/// What git said when it failed doing `what`: the first line it wrote, on standard error or else
+/// on standard output, like `git commit` telling there is nothing to commit.
+fn git_failure(output: &std::process::Output, what: &str) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let reason = (stderr.lines().chain(stdout.lines())).find(|line| !line.trim().is_empty());
+    match reason {
+        Some(reason) => format!("{what}: {}", reason.trim()),
+        None => format!("{what}: git failed, {}", output.status),
+    }
+}
+
+/// Message offered to edit when committing.
+const COMMIT_TEMPLATE: &str = "
+# Please enter the commit message for your changes. Lines starting
+# with '#' will be ignored, and an empty message aborts the commit.
+";
+
+/// Run the editor of git on `file` from `dir`, as git finds and runs it for `git commit`.
+fn edit_file(dir: &Path, file: &Path) -> std::io::Result<std::process::ExitStatus> {
+    // the only git command editing any file, without reading it as configuration
+    Command::new("git")
+        .args(["config", "--edit", "--file"])
+        .arg(file)
+        .current_dir(dir)
+        .status()
+}
+
+/// What a finder entry stands for.
+#[derive(Clone)]
+enum Found {
+    Commit(gix::ObjectId),
+    /// A reference of the repository at this index of the roots.
+    Ref(usize, RefInfo),
+    /// A local branch of a root, with the default branch it is compared to.
+    Branch(usize, Divergence, Option<RefInfo>),
+    /// A file changed by the entry at this index of the current tab.
+    File(usize, FileDiff),
+    /// A remote of the repository at this index of the roots.
+    Remote(usize, RemoteInfo),
+}
+
+/// Title of the finder of commits, whose matches the minimap shows.
+const FIND: &str = "find";
+
+/// Title of the finder of remotes, whose keys act on the remotes even without any.
+const REMOTES: &str =
+    "remotes (Enter set URL, Ctrl-U set push URL, Ctrl-A add, Ctrl-R rename, Ctrl-X remove)";
+
+enum Action<'repo> {
+    Quit,
+    Select(usize),
+    /// Diff the working tree of an entry's source against the entry.
+    DiffWorktree(usize),
+    /// Start an interactive autosquash rebase of an entry's source onto a commit.
+    Rebase(usize, gix::ObjectId, &'static [&'static str]),
+    /// Run a git command resolving an operation in progress in a source.
+    Resolve(Source<'repo>, [&'static str; 2]),
+    /// Print an entry and exit, in `--pick` mode.
+    Pick(usize),
+    Permalink(usize),
+    /// Restore a file of the working tree of an entry's source to its content in a commit: the
+    /// entry, or its parent for a file the entry deleted.
+    RestoreFile(usize, BString, gix::ObjectId),
+    /// Initialize and update the submodule of an entry, or every submodule.
+    UpdateSubmodules(Option<usize>),
+    /// Run a user-defined command template for an entry.
+    External(usize, String),
+    /// Cherry-pick or revert commits of a source one by one.
+    Sequence(Sequence<'repo>),
+    /// Squash commits of the history of HEAD with an interactive rebase.
+    Squash(Squash<'repo>),
+    /// Run an interactive rebase of a source from a base with a todo list, of so many commits.
+    Interactive(Source<'repo>, gix::ObjectId, String, usize),
+    /// Remove a commit of a source from its current branch, replaying the commits after it onto
+    /// its parent.
+    Drop(Source<'repo>, gix::ObjectId, gix::ObjectId),
+    /// Commit the staged changes of a source as a `fixup!` of a commit.
+    Fixup(Source<'repo>, gix::ObjectId),
+    /// Edit the message of a commit of a source, HEAD by amending it, or another with a rebase
+    /// from a base with a todo list.
+    Reword(
+        Source<'repo>,
+        gix::ObjectId,
+        Option<(gix::ObjectId, String)>,
+    ),
+    /// Delete a local branch of a root, pointing to a commit, forcibly when unmerged.
+    DeleteBranch(usize, String, gix::ObjectId, bool),
+    /// Delete a tag of a root, pointing to a commit.
+    DeleteTag(usize, String, gix::ObjectId),
+    /// Push a tag of a root to a remote.
+    PushTag(usize, String, String),
+    /// Remove a remote of a root.
+    RemoveRemote(usize, String),
+    /// Commit a tree of a source on top of its HEAD, asking for the message.
+    Commit(Source<'repo>, gix::ObjectId),
+    /// Run `git commit` in a source, for what committing with gix doesn't do.
+    GitCommit(Source<'repo>, String),
+    /// Run `git send-email` in a source with these arguments.
+    SendEmail(Source<'repo>, Vec<String>),
+    Continue,
+}
+
+/// Draw a frame, with what the selection needs.
+fn draw<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    app.load_shown(terminal.size()?.height.into());
+    if let Some(selected) = app.view().selected() {
+        app.describe(selected);
+        app.relation(selected);
+        app.select_hook(selected);
+    }
+    app.receive_ci();
+    if app.stats {
+        app.summarize();
+    }
+    terminal.draw(|f| ui(f, app))?;
+    app.request_ci();
+    Ok(())
+}
+
+fn run_app(terminal: &mut Term, mut app: App) -> Result<Option<String>> {
+    theme::drain_replies();
+    let inline = app.options.inline.is_some();
+    let mut stale = false;
+    let mut mouse = false;
+    loop {
+        if std::mem::take(&mut stale) {
+            app.operations = operations(app.roots, app.submodules);
+            // programs run meanwhile were given the mouse back
+            mouse = false;
+        }
+        // the mouse is only captured to click on the minimap, keeping text selectable otherwise
+        if app.minimap.is_some() != mouse {
+            mouse = app.minimap.is_some();
+            match mouse {
+                true => terminal.backend_mut().execute(EnableMouseCapture)?,
+                false => terminal.backend_mut().execute(DisableMouseCapture)?,
+            };
+        }
+        draw(terminal, &mut app)?;
+
+        let action = handle_events(&mut app)?;
+        // actions may run git commands finishing or starting an operation
+        stale = !matches!(action, Action::Continue);
+        match action {
+            Action::Quit => {
+                app.save_session()?;
+                break;
+            }
+            Action::Rebase(tab, base, args) => {
+                let source = app.views[tab].items[0].1;
+                if let Err(err) = app.record_head(source, "--keep") {
+                    app.report("rebase", err);
+                    continue;
+                }
+                let current_dir = source.dir();
+                let status = suspend(terminal, inline, || {
+                    Command::new("git")
+                        .arg("rebase")
+                        .args(args)
+                        .arg(base.to_string())
+                        .current_dir(current_dir)
+                        .status()
+                })?;
+                app.views.remove(tab);
+                app.current = 0;
+                if let Err(err) = app.reload(source, "HEAD") {
+                    app.report("rebase", err);
+                } else {
+                    app.check("git rebase", status);
+                }
+            }
+            Action::Resolve(source, args) => {
+                if let Err(err) = app.record_head(source, "--keep") {
+                    app.report(args[0], err);
+                    continue;
+                }
+                let current_dir = source.dir();
+                let status = suspend(terminal, inline, || {
+                    Command::new("git")
+                        .args(args)
+                        .current_dir(current_dir)
+                        .status()
+                })?;
+                let succeeded = matches!(&status, Ok(status) if status.success());
+                if let Err(err) = app.reload(source, "HEAD") {
+                    app.report(args[0], err);
+                } else {
+                    app.check(&format!("git {} {}", args[0], args[1]), status);
+                }
+                // the rest of a sequence stopped on a conflict
+                if let Some(sequence) = app
+                    .sequence
+                    .take_if(|seq| seq.source == source && seq.operation.label() == args[0])
+                {
+                    match args[1] {
+                        "--continue" if succeeded => {
+                            let dir = source.dir();
+                            let result = suspend(terminal, inline, || apply(&dir, &sequence))?;
+                            app.applied(sequence, result);
+                        }
+                        "--continue" => app.sequence = Some(sequence),
+                        _ => {
+                            let Sequence { done, total, .. } = sequence;
+                            app.message = Some(format!(
+                                "{} aborted at {done}/{total}, {} not applied",
+                                args[0],
+                                total - done + 1
+                            ))
+                        }
+                    }
+                }
+            }
+            Action::SendEmail(source, args) => {
+                let current_dir = source.dir();
+                let status = suspend(terminal, inline, || {
+                    Command::new("git")
+                        .arg("send-email")
+                        .args(&args)
+                        .current_dir(current_dir)
+                        .status()
+                })?;
+                app.view_mut().range_from = None;
+                app.check("git send-email", status);
+            }
+            Action::DeleteBranch(root, branch, target, force) => {
+                let output = Command::new("git")
+                    .args(["branch", if force { "-D" } else { "-d" }, &branch])
+                    .current_dir(Source::Root(&app.roots[root]).dir())
+                    .output();
+                match output {
+                    Ok(output) if !output.status.success() => {
+                        app.message = Some(git_failure(&output, "delete-branch"));
+                    }
+                    Ok(_) => {
+                        app.refresh_refs();
+                        let abbrev = app.options.config.abbrev;
+                        let was = target.to_hex_with_len(abbrev);
+                        app.message = Some(format!("deleted branch {branch} (was {was})"));
+                    }
+                    Err(err) => app.report("git branch", err),
+                }
+            }
+            Action::DeleteTag(root, tag, target) => {
+                let output = Command::new("git")
+                    .args(["tag", "-d", &tag])
+                    .current_dir(Source::Root(&app.roots[root]).dir())
+                    .output();
+                match output {
+                    Ok(output) if !output.status.success() => {
+                        app.message = Some(git_failure(&output, "delete-tag"));
+                    }
+                    Ok(_) => {
+                        app.refresh_refs();
+                        let abbrev = app.options.config.abbrev;
+                        let was = target.to_hex_with_len(abbrev);
+                        app.message = Some(format!("deleted tag {tag} (was {was})"));
+                    }
+                    Err(err) => app.report("git tag", err),
+                }
+            }
+            Action::PushTag(root, tag, remote) => {
+                let current_dir = Source::Root(&app.roots[root]).dir();
+                let status = suspend(terminal, inline, || {
+                    Command::new("git")
+                        .args(["push", &remote, &format!("refs/tags/{tag}")])
+                        .current_dir(current_dir)
+                        .status()
+                })?;
+                if matches!(&status, Ok(status) if status.success()) {
+                    app.refresh_refs();
+                    app.message = Some(format!("pushed tag {tag} to {remote}"));
+                } else {
+                    app.check("git push", status);
+                }
+            }
+            Action::RemoveRemote(root, remote) => {
+                let output = Command::new("git")
+                    .args(["remote", "remove", &remote])
+                    .current_dir(Source::Root(&app.roots[root]).dir())
+                    .output();
+                match output {
+                    Ok(output) if !output.status.success() => {
+                        app.message = Some(git_failure(&output, "remote"));
+                    }
+                    Ok(_) => {
+                        app.refresh_refs();
+                        app.message = Some(format!("removed remote {remote}"));
+                    }
+                    Err(err) => app.report("git remote", err),
+                }
+            }
+            Action::Commit(source, tree) => {
+                let repo = match source.open() {
+                    Ok(repo) => repo,
+                    Err(err) => {
+                        app.report("commit", err);
+                        continue;
+                    }
+                };
+                let file = repo.git_dir().join("COMMIT_EDITMSG");
+                if let Err(err) = std::fs::write(&file, COMMIT_TEMPLATE) {
+                    app.report("commit", err);
+                    continue;
+                }
+                let dir = source.dir();
+                let status = suspend(terminal, inline, || edit_file(&dir, &file))?;
+                if !matches!(&status, Ok(status) if status.success()) {
+                    app.check("editor", status);
+                    continue;
+                }
+                let message = match std::fs::read_to_string(&file) {
+                    Ok(text) => stage::cleanup(&text),
+                    Err(err) => {
+                        app.report("commit", err);
+                        continue;
+                    }
+                };
+                if message.is_empty() {
+                    app.message = Some("commit: empty message, nothing committed".into());
+                    continue;
+                }
+                // undoing brings the changes back to the index
+                if let Err(err) = app.record_head(source, "--soft") {
+                    app.report("commit", err);
+                    continue;
+                }
+                let id = match stage::commit(&repo, tree, &message) {
+                    Ok(id) => id,
+                    Err(err) => {
+                        app.undo.pop();
+                        app.report("commit", err);
+                        continue;
+                    }
+                };
+                if let Err(err) = app.reload(source, "HEAD") {
+                    app.report("commit", err);
+                    continue;
+                }
+                app.current = 0;
+                app.views[0].select_id(id);
+                if app.staging.is_some()
+                    && let Err(err) = app.open_staging(source)
+                {
+                    app.report("stage", err);
+                }
+                let subject = message.lines().next().unwrap_or_default();
+                let abbrev = app.options.config.abbrev;
+                app.message = Some(format!(
+                    "committed {} {subject}",
+                    id.to_hex_with_len(abbrev)
+                ));
+            }
+            Action::GitCommit(source, what) => {
+                if let Err(err) = app.record_head(source, "--soft") {
+                    app.report("commit", err);
+                    continue;
+                }
+                let dir = source.dir();
+                let status = suspend(terminal, inline, || {
+                    Command::new("git").arg("commit").current_dir(&dir).status()
+                })?;
+                if !matches!(&status, Ok(status) if status.success()) {
+                    app.undo.pop();
+                    app.check("git commit", status);
+                    continue;
+                }
+                if let Err(err) = app.reload(source, "HEAD") {
+                    app.report("commit", err);
+                    continue;
+                }
+                app.current = 0;
+                if let Ok(repo) = source.open()
+                    && let Ok(id) = repo.head_id()
+                {
+                    app.views[0].select_id(id.detach());
+                }
+                if app.staging.is_some()
+                    && let Err(err) = app.open_staging(source)
+                {
+                    app.report("stage", err);
+                }
+                app.message = Some(format!("committed with git commit, for {what}"));
+            }
+            Action::Fixup(source, id) => {
+                if let Err(err) = app.fixup(source, id) {
+                    app.report("fixup", err);
+                }
+            }
+            Action::Drop(source, id, parent) => {
+                if let Err(err) = app.record_head(source, "--keep") {
+                    app.report("drop", err);
+                    continue;
+                }
+                let current_dir = source.dir();
+                let status = suspend(terminal, inline, || {
+                    Command::new("git")
+                        .args(["rebase", "--onto"])
+                        .args([parent.to_string(), id.to_string()])
+                        .current_dir(current_dir)
+                        .status()
+                })?;
+                if let Err(err) = app.reload(source, "HEAD") {
+                    app.report("drop", err);
+                } else if matches!(&status, Ok(status) if status.success()) {
+                    let abbrev = app.options.config.abbrev;
+                    app.message = Some(format!("dropped {}", id.to_hex_with_len(abbrev)));
+                } else {
+                    app.check("git rebase", status);
+                }
+            }
+            Action::Reword(source, id, rebase) => {
+                let git_dir = match source.open() {
+                    Ok(repo) => repo.git_dir().to_owned(),
+                    Err(err) => {
+                        app.report("reword", err);
+                        continue;
+                    }
+                };
+                if let Err(err) = app.record_head(source, "--keep") {
+                    app.report("reword", err);
+                    continue;
+                }
+                let dir = source.dir();
+                let status = suspend(terminal, inline, || match &rebase {
+                    Some((base, todo)) => rebase_with(&dir, &git_dir, *base, todo),
+                    None => Command::new("git")
+                        .args(["commit", "--amend", "--only"])
+                        .current_dir(&dir)
+                        .status(),
+                })?;
+                let program = match rebase {
+                    Some(_) => "git rebase",
+                    None => "git commit --amend",
+                };
+                if let Err(err) = app.reload(source, "HEAD") {
+                    app.report("reword", err);
+                } else if matches!(&status, Ok(status) if status.success()) {
+                    let abbrev = app.options.config.abbrev;
+                    app.message = Some(format!("reworded {}", id.to_hex_with_len(abbrev)));
+                } else {
+                    app.check(program, status);
+                }
+            }
+            Action::Squash(plan) => {
+                let source = plan.source;
+                let git_dir = match source.open() {
+                    Ok(repo) => repo.git_dir().to_owned(),
+                    Err(err) => {
+                        app.report("squash", err);
+                        continue;
+                    }
+                };
+                if let Err(err) = app.record_head(source, "--keep") {
+                    app.report("squash", err);
+                    continue;
+                }
+                let dir = source.dir();
+                let status = suspend(terminal, inline, || {
+                    rebase_with(&dir, &git_dir, plan.base, &plan.todo)
+                })?;
+                app.view_mut().range_from = None;
+                if let Err(err) = app.reload(source, "HEAD") {
+                    app.report("squash", err);
+                } else if matches!(&status, Ok(status) if status.success()) {
+                    app.message = Some(format!("squashed {} commits", plan.commits.len()));
+                } else {
+                    app.check("git rebase", status);
+                }
+            }
+            Action::Interactive(source, base, todo, count) => {
+                app.todo = None;
+                let git_dir = match source.open() {
+                    Ok(repo) => repo.git_dir().to_owned(),
+                    Err(err) => {
+                        app.report("rebase", err);
+                        continue;
+                    }
+                };
+                if let Err(err) = app.record_head(source, "--keep") {
+                    app.report("rebase", err);
+                    continue;
+                }
+                let dir = source.dir();
+                let status = suspend(terminal, inline, || {
+                    rebase_with(&dir, &git_dir, base, &todo)
+                })?;
+                app.view_mut().range_from = None;
+                let stopped = source
+                    .open()
+                    .is_ok_and(|repo| Operation::in_progress(&repo).is_some());
+                if let Err(err) = app.reload(source, "HEAD") {
+                    app.report("rebase", err);
+                } else if !matches!(&status, Ok(status) if status.success()) {
+                    app.check("git rebase", status);
+                } else if stopped {
+                    app.message = Some("rebase stopped to edit a commit, C to continue".into());
+                } else {
+                    app.message = Some(match count {
+                        1 => "rebased 1 commit".into(),
+                        n => format!("rebased {n} commits"),
+                    });
+                }
+            }
+            Action::Sequence(sequence) => {
+                let source = sequence.source;
+                if let Err(err) = app.record_head(source, "--keep") {
+                    app.report(sequence.operation.label(), err);
+                    continue;
+                }
+                let dir = source.dir();
+                let result = suspend(terminal, inline, || apply(&dir, &sequence))?;
+                app.applied(sequence, result);
+            }
+            Action::Pick(selected) => {
+                app.save_session()?;
+                let template = app.options.pick.as_deref().unwrap_or("%H");
+                let (entry, source) = &app.view().items[selected];
+                return Ok(Some(format_entry(
+                    template,
+                    entry,
+                    source,
+                    app.options.config.abbrev,
+                )));
+            }
+            Action::Select(selected) => {
+                let item = &app.view().items[selected];
+                let hooked = hooks::entry(Hook::Open, &app.options.config, &item.0, &item.1);
+                match hooked {
+                    Ok(script) => hooks::detach(script),
+                    Err(err) => app.report("on-open", err),
+                }
+                let item = &app.view().items[selected];
+                let current_dir = item.1.dir();
+                let status = suspend(terminal, inline, || {
+                    paged_command("show", item.0.id)
+                        .current_dir(current_dir)
+                        .status()
+                })?;
+                // the pager quitting early is no failure
+                if let Err(err) = status {
+                    app.report("git show", err);
+                }
+            }
+            Action::DiffWorktree(selected) => {
+                let item = &app.view().items[selected];
+                let current_dir = item.1.dir();
+                let status = suspend(terminal, inline, || {
+                    paged_command("diff", item.0.id)
+                        .current_dir(current_dir)
+                        .status()
+                })?;
+                if let Err(err) = status {
+                    app.report("git diff", err);
+                }
+            }
+            Action::External(selected, template) => {
+                let (entry, source) = &app.view().items[selected];
+                let args = match expand(&template, entry, source, app.options.config.abbrev) {
+                    Ok(args) if !args.is_empty() => args,
+                    Ok(_) => continue,
+                    Err(err) => {
+                        app.report(&template, err);
+                        continue;
+                    }
+                };
+                let current_dir = source.dir();
+                let status = suspend(terminal, inline, || {
+                    Command::new(&args[0])
+                        .args(&args[1..])
+                        .current_dir(current_dir)
+                        .status()
+                })?;
+                app.check(&args[0], status);
+            }
+            Action::UpdateSubmodules(selected) => {
+                // the selected submodule, or all submodules of every repository
+                let targets = match selected.map(|i| app.view().items[i].1) {
+                    Some(Source::Submodule(root, submodule)) => match submodule.path() {
+                        Ok(path) => vec![(root, Some(path.to_string()))],
+                        Err(err) => {
+                            app.report("submodules", err);
+                            continue;
+                        }
+                    },
+                    _ => app.roots.iter().map(|root| (root, None)).collect(),
+                };
+                let status = suspend(terminal, inline, || {
+                    for (root, path) in targets {
+                        let status = Command::new("git")
+                            .args(["submodule", "update", "--init", "--progress", "--"])
+                            .args(path)
+                            .current_dir(&root.dir)
+                            .status()?;
+                        if !status.success() {
+                            return Ok(status);
+                        }
+                    }
+                    Ok(std::process::ExitStatus::default())
+                })?;
+                match status {
+                    Ok(status) if status.success() => match app.load_new_submodules() {
+                        Ok(()) => app.message = Some("submodules updated".into()),
+                        Err(err) => app.report("submodules", err),
+                    },
+                    status => app.check("git submodule update", status),
+                }
+            }
+            Action::Permalink(selected) => match app.permalink(selected) {
+                Ok(url) => app.copy(&url),
+                Err(err) => app.report("permalink", err),
+            },
+            Action::RestoreFile(selected, path, from) => {
+                if let Err(err) = app.restore_file(selected, &path, from) {
+                    app.report("restore", err);
+                }
+            }
+            Action::Continue => (),
+        }
+    }
+
+    Ok(None)
+}
+
+fn run_command<'repo>(app: &mut App<'repo>, input: &str) -> Result<Action<'repo>> {
+    // what to save only holds for the `:save` it was asked for
+    let save = app.save.take();
+    if input.trim_start().starts_with("save ") {
+        app.save = save;
+    }
+    match command::parse(input) {
+        Ok(command::Command::Goto(rev)) => app.goto(rev),
+        Ok(command::Command::Rebase(onto)) => {
+            if let Err(err) = app.rebase_preview(onto) {
+                app.report("rebase", err);
+            }
+        }
+        Ok(command::Command::Tab(spec)) => {
+            if let Err(err) = app.spec_tab(spec) {
+                app.report("tab", err);
+            }
+        }
+        Ok(command::Command::Filter(key, pattern)) => app.filter_tab(key, pattern),
+        Ok(command::Command::SendEmail(args)) => return Ok(app.send_email(&args)),
+        Ok(command::Command::Save(path)) => {
+            if let Err(err) = app.save(path) {
+                app.report("save", err);
+            }
+        }
+        Ok(command::Command::Revert(squash)) => {
+            if !app.refuse_write()
+                && let Err(err) = app.sequence_prompt(Operation::Revert, squash)
+            {
+                app.report("revert", err);
+            }
+        }
+        Ok(
+            command::Command::DeleteBranch(_)
+            | command::Command::RenameBranch(..)
+            | command::Command::DeleteTag(_)
+            | command::Command::PushTag(..)
+            | command::Command::Remote(_),
+        ) if app.refuse_write() => {}
+        Ok(command::Command::DeleteBranch(name)) => {
+            if let Err(err) = app.delete_branch_prompt(name) {
+                app.report("delete-branch", err);
+            }
+        }
+        Ok(command::Command::DeleteTag(name)) => {
+            if let Err(err) = app.delete_tag_prompt(name) {
+                app.report("delete-tag", err);
+            }
+        }
+        Ok(command::Command::PushTag(name, remote)) => app.push_tag_prompt(name, remote),
+        Ok(command::Command::Remote(edit)) => {
+            if let Err(err) = app.edit_remote(edit) {
+                app.report("remote", err);
+            }
+        }
+        Ok(command::Command::RenameBranch(name, new)) => {
+            if let Err(err) = app.rename_branch(name, new) {
+                app.report("rename-branch", err);
+            }
+        }
+        Ok(command::Command::Set(key, value)) => app.set(key, value),
+        Ok(command::Command::ExportPatches(dir)) => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.export_patches(selected, dir.unwrap_or("."))
+            {
+                app.report("export", err);
+            }
+        }
+        Ok(command::Command::ExportArchive(file, submodules)) => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.export_archive(selected, file, submodules)
+            {
+                app.report("archive", err);
+            }
+        }
+        Ok(command::Command::Key(code)) => return handle_key(app, code),
+        Err(err) => app.message = Some(err),
+    }
+    Ok(Action::Continue)
+}
+
+fn handle_events<'repo>(app: &mut App<'repo>) -> Result<Action<'repo>> {
+    // wake up to show checks statuses as they come, and to run the `on-select` hook
+    let wake = app.ci.as_ref().is_some_and(Ci::waiting) || app.settling.is_some();
+    Ok(match app.tasks.next(wake)? {
+        Some(AppEvent::Terminal(event)) => handle_event(app, event),
+        Some(event) => {
+            app.finished(event);
+            Action::Continue
+        }
+        None => Action::Continue,
+    })
+}
+
+/// Act on `event`, what fails being reported on the status line.
+fn handle_event<'repo>(app: &mut App<'repo>, event: Event) -> Action<'repo> {
+    match try_handle_event(app, event) {
+        Ok(action) => action,
+        Err(err) => {
+            app.report("error", err);
+            Action::Continue
+        }
+    }
+}
+
+fn try_handle_event<'repo>(app: &mut App<'repo>, event: Event) -> Result<Action<'repo>> {
+    let abbrev = app.options.config.abbrev;
+    if let Event::Mouse(mouse) = event
+        && mouse.kind == MouseEventKind::Down(MouseButton::Left)
+    {
+        app.click(mouse.column, mouse.row);
+    }
+    if let Event::Key(key) = event
+        && key.kind == event::KeyEventKind::Press
+    {
+        app.message = None;
+        if let Some((_, action)) = app.confirm.take() {
+            return Ok(match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => action,
+                _ => Action::Continue,
+            });
+        }
+        if let Some(popup) = &mut app.popup {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => popup.scroll_down(),
+                KeyCode::Char('k') | KeyCode::Up => popup.scroll_up(),
+                KeyCode::Char('Y') | KeyCode::Char('E') => {
+                    let text: String = popup.lines.iter().map(|line| format!("{line}\n")).collect();
+                    let what = format!("the {} text", popup.title);
+                    match key.code {
+                        KeyCode::Char('Y') => app.copy_as(&what, &text),
+                        _ => {
+                            app.popup = None;
+                            app.save_prompt(what, text);
+                        }
+                    }
+                }
+                // starting the squash previewed
+                KeyCode::Char('Z') if popup.title == "squash" => {
+                    app.popup = None;
+                    return handle_key(app, key.code);
+                }
+                _ => app.popup = None,
+            }
+            return Ok(Action::Continue);
+        }
+        if let Some((source, editor)) = &mut app.todo {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => editor.next(),
+                KeyCode::Char('k') | KeyCode::Up => editor.previous(),
+                KeyCode::Char('J') => editor.move_down(),
+                KeyCode::Char('K') => editor.move_up(),
+                KeyCode::Enter => match editor.check() {
+                    Ok(()) => {
+                        let count = editor.steps.len();
+                        let commits = match count {
+                            1 => "1 commit".into(),
+                            n => format!("{n} commits"),
+                        };
+                        let prompt = format!(
+                            "Rebase {commits} onto {} with git rebase -i?",
+                            editor.base.to_hex_with_len(abbrev)
+                        );
+                        let action =
+                            Action::Interactive(*source, editor.base, editor.todo(), count);
+                        app.confirm = Some((prompt, action));
+                    }
+                    Err(refusal) => app.message = Some(format!("rebase: {refusal}")),
+                },
+                KeyCode::Esc | KeyCode::Char('q') => app.todo = None,
+                KeyCode::Char(c) => {
+                    if let Some(verb) = Verb::from_key(c) {
+                        editor.set(verb);
+                    }
+                }
+                _ => {}
+            }
+            return Ok(Action::Continue);
+        }
+        if app.pane.is_some()
+            && app.staging.is_some()
+            && let KeyCode::Char(c @ ('s' | 'S')) = key.code
+        {
+            if !app.refuse_write()
+                && let Err(err) = app.stage(c == 'S')
+            {
+                app.report("stage", err);
+            }
+            return Ok(Action::Continue);
+        }
+        // committing what was staged
+        if app.staging.is_some() && key.code == KeyCode::Char('N') {
+            return handle_key(app, key.code);
+        }
+        if app.diffed.is_some()
+            && key.code == KeyCode::Char('y')
+            && key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            match app.line_permalink() {
+                Ok(Some(url)) => app.copy(&url),
+                Ok(None) => {}
+                Err(err) => app.report("permalink", err),
+            }
+            return Ok(Action::Continue);
+        }
+        if let Some(pane) = &mut app.pane {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => pane.scroll_down(1),
+                KeyCode::Char('k') | KeyCode::Up => pane.scroll_up(1),
+                KeyCode::Char(' ') | KeyCode::PageDown => pane.scroll_down(pane.page()),
+                KeyCode::PageUp => pane.scroll_up(pane.page()),
+                KeyCode::Char(']') => pane.next_file(),
+                KeyCode::Char('[') => pane.previous_file(),
+                KeyCode::Char('}') => pane.next_hunk(),
+                KeyCode::Char('{') => pane.previous_hunk(),
+                KeyCode::Char('z') => pane.toggle_hunk(),
+                KeyCode::Char('Z') => pane.toggle_file(),
+                KeyCode::Char('A') => pane.toggle_all(),
+                KeyCode::Esc | KeyCode::Char('q' | 'l') => {
+                    app.pane = None;
+                    app.staging = None;
+                    app.diffed = None;
+                }
+                _ => {}
+            }
+            return Ok(Action::Continue);
+        }
+        if let Some(sidebar) = &mut app.sidebar {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => sidebar.next(),
+                KeyCode::Char('k') | KeyCode::Up => sidebar.previous(),
+                KeyCode::Enter | KeyCode::Esc | KeyCode::Char('L') => {
+                    app.sidebar = None;
+                    return Ok(Action::Continue);
+                }
+                // other keys act on the list
+                _ => return handle_key(app, key.code),
+            }
+            app.follow_sidebar();
+            return Ok(Action::Continue);
+        }
+        if let Some(finder) = &mut app.finder {
+            match key.code {
+                KeyCode::Esc => app.finder = None,
+                KeyCode::Enter => {
+                    let found = finder.selected().cloned();
+                    app.finder = None;
+                    match found {
+                        Some(Found::Commit(id)) if !app.view_mut().select_id(id) => {
+                            app.message =
+                                Some(format!("{} is not listed", id.to_hex_with_len(abbrev)));
+                        }
+                        Some(Found::Ref(root, reference)) => {
+                            if let Err(err) = app.switch_to(root, &reference) {
+                                app.report("switch", err);
+                            }
+                        }
+                        Some(Found::Branch(root, div, default)) => {
+                            let base = default.or_else(|| {
+                                let (name, _, _) = div.upstream.as_ref()?;
+                                let repo = &app.roots[root].repo;
+                                Some(RefInfo {
+                                    kind: RefKind::Remote,
+                                    name: name.clone(),
+                                    target: repo.rev_parse_single(name.as_str()).ok()?.detach(),
+                                })
+                            });
+                            match base {
+                                Some(base) => {
+                                    if let Err(err) = app.branch_tab(root, &div.branch, &base) {
+                                        app.report("branches", err);
+                                    }
+                                }
+                                None => {
+                                    app.message = Some(format!(
+                                        "{}: no default branch or upstream to compare to",
+                                        div.branch.name
+                                    ))
+                                }
+                            }
+                        }
+                        Some(Found::File(..) | Found::Remote(..)) if app.refuse_write() => {}
+                        Some(Found::Remote(root, remote)) => {
+                            let url = remote.fetch_url.map(|url| url.to_bstring().to_string());
+                            app.command = Some(format!(
+                                "remote set-url {} {}",
+                                app.qualified(root, &remote.name),
+                                url.unwrap_or_default()
+                            ));
+                        }
+                        Some(Found::File(selected, file)) => {
+                            if let Err(err) = app.confirm_restore(selected, file) {
+                                app.report("restore", err);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                KeyCode::Char(c @ ('x' | 'r' | 'u' | 'a'))
+                    if key.modifiers.contains(KeyModifiers::CONTROL) && finder.title == REMOTES =>
+                {
+                    let selected = match finder.selected() {
+                        Some(Found::Remote(root, remote)) => Some((*root, remote.clone())),
+                        _ => None,
+                    };
+                    app.finder = None;
+                    let root = selected.as_ref().map_or(0, |(root, _)| *root);
+                    let repo = app.qualified(root, "");
+                    match (c, selected) {
+                        _ if app.refuse_write() => {}
+                        ('a', _) => app.command = Some(format!("remote add {repo}")),
+                        (_, None) => {}
+                        ('r', Some((_, remote))) => {
+                            app.command = Some(format!("remote rename {repo}{} ", remote.name));
+                        }
+                        ('u', Some((_, remote))) => {
+                            let url = remote.push_url.or(remote.fetch_url);
+                            app.command = Some(format!(
+                                "remote set-push-url {repo}{} {}",
+                                remote.name,
+                                url.map(|url| url.to_bstring().to_string())
+                                    .unwrap_or_default()
+                            ));
+                        }
+                        (_, Some((_, remote))) => {
+                            let name = format!("{repo}{}", remote.name);
+                            if let Err(err) = app.edit_remote(RemoteEdit::Remove(&name)) {
+                                app.report("remote", err);
+                            }
+                        }
+                    }
+                }
+                KeyCode::Char(c @ ('x' | 'r' | 'u'))
+                    if key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    // a branch is deleted or renamed, a tag deleted or pushed
+                    let target = match finder.selected() {
+                        Some(Found::Branch(root, div, _)) if c != 'u' => {
+                            Some((*root, div.branch.name.clone(), true))
+                        }
+                        Some(Found::Ref(root, tag)) if tag.kind == RefKind::Tag && c != 'r' => {
+                            Some((*root, tag.name.clone(), false))
+                        }
+                        _ => None,
+                    };
+                    if let Some((root, name, branch)) = target {
+                        app.finder = None;
+                        let name = app.qualified(root, &name);
+                        let result = match (c, branch) {
+                            _ if app.refuse_write() => Ok(()),
+                            ('r', _) => {
+                                app.command = Some(format!("rename-branch {name} "));
+                                Ok(())
+                            }
+                            ('u', _) => {
+                                app.push_tag_prompt(&name, None);
+                                Ok(())
+                            }
+                            (_, true) => app.delete_branch_prompt(&name),
+                            (_, false) => app.delete_tag_prompt(&name),
+                        };
+                        if let Err(err) = result {
+                            let what = if branch {
+                                "delete-branch"
+                            } else {
+                                "delete-tag"
+                            };
+                            app.report(what, err);
+                        }
+                    }
+                }
+                KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Some(Found::File(selected, file)) = finder.selected().cloned() {
+                        app.finder = None;
+                        match app.file_permalink(selected, &file.path.to_string(), None) {
+                            Ok(url) => app.copy(&url),
+                            Err(err) => app.report("permalink", err),
+                        }
+                    }
+                }
+                KeyCode::Char(c @ ('d' | 'e')) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Some(Found::File(_, file)) = finder.selected().cloned() {
+                        app.finder = None;
+                        let what = format!("the diff of {}", file.path);
+                        match file.to_patch() {
+                            Some(patch) if c == 'd' => app.copy_as(&what, &patch.to_str_lossy()),
+                            Some(patch) => app.save_prompt(what, patch.to_string()),
+                            None => app.message = Some(format!("{}: no diff", file.path)),
+                        }
+                    }
+                }
+                KeyCode::Backspace => finder.pop(),
+                KeyCode::Down => finder.next(),
+                KeyCode::Up => finder.previous(),
+                KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    finder.next()
+                }
+                KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    finder.previous()
+                }
+                KeyCode::Char(c) => finder.push(c),
+                _ => {}
+            }
+            return Ok(Action::Continue);
+        }
+        if key.code == KeyCode::Char('p') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            app.open_finder();
+            return Ok(Action::Continue);
+        }
+        if let Some(input) = &mut app.command {
+            match key.code {
+                KeyCode::Esc => {
+                    app.command = None;
+                    app.save = None;
+                }
+                KeyCode::Enter => {
+                    let input = std::mem::take(input);
+                    app.command = None;
+                    return run_command(app, &input);
+                }
+                KeyCode::Backspace if input.pop().is_none() => {
+                    app.command = None;
+                    app.save = None;
+                }
+                KeyCode::Tab => {
+                    let candidates = command::complete(input);
+                    if let Some(prefix) = command::common_prefix(&candidates)
+                        && prefix.len() > input.len()
+                    {
+                        *input = prefix.to_owned();
+                    }
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return Ok(Action::Continue);
+        }
+        if let KeyCode::Char(c) = key.code
+            && let Some((_, template)) = app.options.config.commands.iter().find(|(k, _)| *k == c)
+            && let Some(selected) = app.view().selected()
+        {
+            return Ok(Action::External(selected, template.clone()));
+        }
+        if key.code == KeyCode::Char(':') {
+            app.command = Some(String::new());
+            return Ok(Action::Continue);
+        }
+        return handle_key(app, key.code);
+    }
+
+    Ok(Action::Continue)
+}
+
+fn handle_key<'repo>(app: &mut App<'repo>, code: KeyCode) -> Result<Action<'repo>> {
+    let abbrev = app.options.config.abbrev;
+    let writes = match code {
+        KeyCode::Char('A') => app.view().rebase_onto.is_some(),
+        KeyCode::Char('C' | 'X' | 'F' | 'p' | 'V' | 'w' | 'd' | 'I' | 'N' | 'u' | 'U') => true,
+        _ => false,
+    };
+    if writes && app.refuse_write() {
+        return Ok(Action::Continue);
+    }
+    match code {
+        KeyCode::Char('q') => return Ok(Action::Quit),
+        KeyCode::Enter => {
+            if let Some(selected) = app.view().selected() {
+                return Ok(match app.options.pick {
+                    Some(_) => Action::Pick(selected),
+                    None => Action::Select(selected),
+                });
+            }
+        }
+        KeyCode::Char('W') => {
+            if let Some(selected) = app.view().selected() {
+                return Ok(Action::DiffWorktree(selected));
+            }
+        }
+        KeyCode::Char('N') => {
+            if let Some(selected) = app.view().selected() {
+                match app.commit(selected) {
+                    Ok(action) => return Ok(action),
+                    Err(err) => app.report("commit", err),
+                }
+            }
+        }
+        KeyCode::Char('a') => {
+            if let Some(selected) = app.view().selected() {
+                let source = app.view().items[selected].1;
+                if let Err(err) = app.open_staging(source) {
+                    app.report("stage", err);
+                }
+            }
+        }
+        KeyCode::Char('l') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.diff(selected, DiffUse::Pane)
+            {
+                app.report("diff", err);
+            }
+        }
+        KeyCode::Char('f') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.diff(selected, DiffUse::Finder)
+            {
+                app.report("files", err);
+            }
+        }
+        KeyCode::Char('M') => app.mine_tab(),
+        KeyCode::Char('s') => app.stats = !app.stats,
+        KeyCode::Char('!') => app.show_errors(),
+        KeyCode::Char('=') => app.show_changelog(),
+        KeyCode::Char('*') => {
+            if let Some(selected) = app.view().selected() {
+                app.jump_to_duplicate(selected);
+            }
+        }
+        KeyCode::Char('m') => {
+            app.minimap = match app.minimap {
+                Some(_) => None,
+                None => Some(Rect::default()),
+            }
+        }
+        KeyCode::Char(']') => app.view_mut().jump(false),
+        KeyCode::Char('[') => app.view_mut().jump(true),
+        KeyCode::Char('L') => {
+            let listed = app.views[0].items.iter().map(|(e, _)| e.id).collect();
+            app.sidebar = Some(Sidebar::new(app.roots, &listed));
+            app.follow_sidebar();
+        }
+        KeyCode::Char('D') => app.open_branches(),
+        KeyCode::Char('T') => app.open_tags(),
+        KeyCode::Char('O') => app.open_remotes(),
+        KeyCode::Char('P') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.open_request(selected)
+            {
+                app.report("pull request", err);
+            }
+        }
+        KeyCode::Char('y') => {
+            if let Some(selected) = app.view().selected() {
+                return Ok(Action::Permalink(selected));
+            }
+        }
+        KeyCode::Char('Y') | KeyCode::Char('E') => {
+            if let Some(selected) = app.view().selected() {
+                match app.patch(selected) {
+                    Ok((what, text)) if code == KeyCode::Char('Y') => app.copy_as(&what, &text),
+                    Ok((what, text)) => app.save_prompt(what, text),
+                    Err(err) => app.report("patch", err),
+                }
+            }
+        }
+        KeyCode::Char('g') | KeyCode::Char('b') => {
+            if let Some(selected) = app.view().selected() {
+                app.message = app
+                    .view_mut()
+                    .bisect_mark(selected, code == KeyCode::Char('g'));
+            }
+        }
+        KeyCode::Char('B') => app.view_mut().bisect_reset(),
+        KeyCode::Char('c') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.compare_pick(selected)
+            {
+                app.report("compare", err);
+            }
+        }
+        KeyCode::Char('v') => {
+            app.view_mut().toggle_range();
+            if app.view().range_from.is_some() {
+                app.message = Some(
+                    "range: move to its other end, p to cherry-pick it, V or :revert to revert it, Z to squash it"
+                        .into(),
+                );
+            }
+        }
+        KeyCode::Char('p') | KeyCode::Char('V') => {
+            let operation = match code {
+                KeyCode::Char('p') => Operation::CherryPick,
+                _ => Operation::Revert,
+            };
+            if let Err(err) = app.sequence_prompt(operation, false) {
+                app.report(operation.label(), err);
+            }
+        }
+        KeyCode::Char('e') => app.command = Some("send-email --to=".into()),
+        KeyCode::Char('I') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.todo_editor(selected)
+            {
+                app.report("rebase", err);
+            }
+        }
+        KeyCode::Char('d') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.drop_prompt(selected)
+            {
+                app.report("drop", err);
+            }
+        }
+        KeyCode::Char('w') => {
+            if let Some(selected) = app.view().selected() {
+                match app.reword(selected) {
+                    Ok(action) => return Ok(action),
+                    Err(err) => app.report("reword", err),
+                }
+            }
+        }
+        KeyCode::Char('Z') => {
+            if let Err(err) = app.squash_preview() {
+                app.report("squash", err);
+            }
+        }
+        KeyCode::Char('i') => {
+            if let Some(selected) = app.view().selected() {
+                app.show_details(selected);
+            }
+        }
+        KeyCode::Char('R') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.show_containing(selected)
+            {
+                app.report("refs", err);
+            }
+        }
+        KeyCode::Char('t') => {
+            let roots = app.roots;
+            app.view_mut().toggle_grouping(roots);
+        }
+        KeyCode::Char('H') if app.submodules.is_empty() => {
+            app.message = Some("no submodules".into());
+        }
+        KeyCode::Char('H') => {
+            let roots = app.roots;
+            let view = app.view_mut();
+            view.toggle_submodules(roots);
+            app.message = Some(match view.all_sources {
+                Some(_) => "submodules hidden".into(),
+                None => "submodules shown".into(),
+            });
+        }
+        KeyCode::Char('U') => return Ok(Action::UpdateSubmodules(app.view().selected())),
+        KeyCode::Char('S') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.jump_to_landing(selected)
+            {
+                app.report("landing", err);
+            }
+        }
+        KeyCode::Char('o') => {
+            if let Some(selected) = app.view().selected() {
+                app.open_source_tab(selected);
+            }
+        }
+        KeyCode::Char('x') => app.close_tab(),
+        KeyCode::Char('r') => app.open_ref_switcher(),
+        KeyCode::Char('A') => {
+            if let Some((base, args)) = app.view().rebase_onto {
+                let prompt = format!("Rebase onto {}?", base.to_hex_with_len(abbrev));
+                app.confirm = Some((prompt, Action::Rebase(app.current, base, args)));
+                return Ok(Action::Continue);
+            }
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.autosquash_preview(selected)
+            {
+                app.report("autosquash", err);
+            }
+        }
+        KeyCode::Char('C') => match app.operation() {
+            Some((source, state)) => match state.continue_args() {
+                Some(args) => return Ok(Action::Resolve(source, args)),
+                None => app.message = Some(format!("{} can't be continued", state.label())),
+            },
+            None => app.message = Some("no operation in progress".into()),
+        },
+        KeyCode::Char('X') => match app.operation() {
+            Some((source, state)) => {
+                let prompt = format!("Abort the {} and discard its progress?", state.label());
+                app.confirm = Some((prompt, Action::Resolve(source, state.abort_args())));
+            }
+            None => app.message = Some("no operation in progress".into()),
+        },
+        KeyCode::Char('u') => {
+            if let Err(err) = app.undo() {
+                app.report("undo", err);
+            }
+        }
+        KeyCode::Char('F') => {
+            if let Some(selected) = app.view().selected()
+                && let Err(err) = app.fixup_prompt(selected)
+            {
+                app.report("fixup", err);
+            }
+        }
+        KeyCode::Tab => app.current = (app.current + 1) % app.views.len(),
+        KeyCode::BackTab => app.current = (app.current + app.views.len() - 1) % app.views.len(),
+        KeyCode::Char(c @ '1'..='9') => {
+            let i = c as usize - '1' as usize;
+            if i < app.views.len() {
+                app.current = i;
+            }
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.view_mut().next(),
+        KeyCode::Char('k') | KeyCode::Up => app.view_mut().previous(),
+        KeyCode::PageDown => {
+            let page = (app.list_height / 2).max(1) as usize;
+            app.view_mut().page_down(page);
+        }
+        KeyCode::PageUp => {
+            let page = (app.list_height / 2).max(1) as usize;
+            app.view_mut().page_up(page);
+        }
+        KeyCode::Home => app.view_mut().go_to_start(),
+        KeyCode::End => app.view_mut().go_to_end(),
+        _ => {}
+    }
+
+    Ok(Action::Continue)
+}
+
+fn submodules_header(submodules: &[(String, Option<submodule::Health>)]) -> Line<'static> {
+    let mut spans = vec![Span::styled("submodules:", Style::new().gray())];
+    for (name, health) in submodules {
+        let style = match health {
+            Some(submodule::Health::Clean) => Style::new().green(),
+            Some(submodule::Health::Uninitialized) => Style::new().dark_gray(),
+            Some(submodule::Health::OutOfSync) => Style::new().yellow(),
+            Some(submodule::Health::Dirty) | None => Style::new().red(),
+        };
+        let label = health.map_or("unknown", |h| h.label());
+        spans.push(Span::raw(format!(" {name} ")));
+        spans.push(Span::styled(format!("[{label}]"), style));
+    }
+    Line::from(spans)
+}
+
+/// The entry at `selected` in words, with what the list only shows with colors or symbols.
+fn spoken(app: &App, selected: usize) -> String {
+    let view = app.view();
+    let (entry, source) = &view.items[selected];
+    let subject = entry.message.lines().next().unwrap_or_default();
+    let mut text = format!(
+        "commit {} of {}: {}, by {}, {}, {}",
+        selected + 1,
+        view.items.len(),
+        subject.to_str_lossy(),
+        entry.author,
+        view.date.format(entry.time_of(view.time)).trim_end(),
+        entry.id.to_hex_with_len(app.options.config.abbrev),
+    );
+    let label = source.label();
+    if !label.is_empty() {
+        text += &format!(", in {label}");
+    }
+    if let Some(Some(describe)) = app.describe.as_ref().and_then(|d| d.get(&entry.id)) {
+        text += &format!(", described as {describe}");
+    }
+    if let Some(Some(relation)) = app.relations.get(&entry.id) {
+        text += &format!(", {}", relation.describe(app.options.config.abbrev));
+    }
+    if view.bisect.bad == Some(entry.id) {
+        text += ", marked bad";
+    } else if view.bisect.good.contains(&entry.id) {
+        text += ", marked good";
+    }
+    if view.new.contains(&entry.id) {
+        text += ", new since the last run";
+    }
+    if let Some(label) = view.prs.as_ref().and_then(|prs| prs.get(&entry.id)) {
+        text += &format!(", from pull request {label}");
+    }
+    if let Some(status) = view.ci.as_ref().and_then(|ci| ci.get(&entry.id)) {
+        text += &format!(", checks {}", status.label());
+    }
+    let failures = view.lint_failures(selected);
+    if !failures.is_empty() {
+        text += &format!(", message fails: {}", failures.join(", "));
+    }
+    if view.duplicates.contains(&entry.id) {
+        text += ", same patch as another commit";
+    }
+    if entry.boundary {
+        text += ", boundary, left out of the range";
+    } else if entry.is_root() {
+        text += ", root commit";
+    }
+    if view.folded.contains(&entry.id) {
+        text += ", folded into the commit above";
+    }
+    if let Some(range) = view.range() {
+        text += &format!(", {} commits in range", range.count());
+    }
+    match (entry.side, entry.equivalent) {
+        (Some(_), true) => text += ", equivalent patch on both sides",
+        (Some(Side::Left), false) => text += ", only on the left side",
+        (Some(Side::Right), false) => text += ", only on the right side",
+        (None, _) => {}
+    }
+    text
+}
+
+fn operations_banner(operations: &[(Source, Operation)]) -> Line<'static> {
+    let mut text: Vec<_> = operations
+        .iter()
+        .map(|(source, state)| match source.label() {
+            label if label.is_empty() => format!("{} in progress", state.label()),
+            label => format!("{} in progress in {label}", state.label()),
+        })
+        .collect();
+    text.push("C continue, X abort".into());
+    Line::from(text.join(" - ")).style(Style::new().black().bold().on_yellow())
+}
+
+/// The summary of the current tab, once made.
+fn stats_footer(view: &View) -> Line<'static> {
+    match &view.summary {
+        Summary::Made(line) => line.clone(),
+        _ => Line::from("summarizing…").style(Style::new().black().on_gray()),
+    }
+}
+
+/// Summary of `entries`: how many, by how many authors, over which dates, and how many in each
+/// of `sources` when there are several.
+///
+/// Each entry is given by the index of its source, its id, the email of its author unless it
+/// is to be read from the repository of its source, and its time.
+fn summary(
+    entries: Vec<(usize, gix::ObjectId, Option<Shared>, gix::date::Time)>,
+    sources: Vec<(String, Option<gix::Repository>)>,
+) -> Line<'static> {
+    let authors: HashSet<_> = entries
+        .iter()
+        .filter_map(|(source, id, email, _)| match email {
+            Some(email) => Some(email.to_ascii_lowercase()),
+            None => {
+                let commit = sources[*source].1.as_ref()?.find_commit(*id).ok()?;
+                Some(commit.author().ok()?.email.to_ascii_lowercase())
+            }
+        })
+        .collect();
+    let count = |n: usize, unit: &str| match n {
+        1 => format!("1 {unit}"),
+        n => format!("{n} {unit}s"),
+    };
+    let mut text = vec![
+        count(entries.len(), "commit"),
+        count(authors.len(), "author"),
+    ];
+    let times = entries.iter().map(|(.., time)| *time);
+    if let (Some(first), Some(last)) = (
+        times.clone().min_by_key(|t| t.seconds),
+        times.max_by_key(|t| t.seconds),
+    ) {
+        let days = (last.seconds - first.seconds) / 86_400 + 1;
+        text.push(format!(
+            "{} to {} ({})",
+            first.format(gix::date::time::format::SHORT),
+            last.format(gix::date::time::format::SHORT),
+            count(days as usize, "day"),
+        ));
+    }
+    let mut counts = vec![0; sources.len()];
+    for (source, ..) in &entries {
+        counts[*source] += 1;
+    }
+    if sources.len() > 1 {
+        let counts = sources.iter().zip(counts);
+        text.extend(counts.map(|((label, _), n)| format!("{label} {n}")));
+    }
+    Line::from(text.join(" · ")).style(Style::new().black().on_gray())
+}
+
+/// The minimap of the current tab, for `height` rows, marking the commits found by the query of
+/// the finder of commits while it is open. The rows of the `page` shown have a background.
+fn minimap(app: &mut App, height: u16, page: u16) -> Vec<Line<'static>> {
+    let finder = app.finder.as_ref().filter(|finder| finder.title == FIND);
+    let query = finder
+        .map(|finder| finder.query.as_str())
+        .filter(|query| !query.is_empty());
+    let found = || {
+        (finder.into_iter().flat_map(Finder::matching))
+            .filter_map(|found| match found {
+                Found::Commit(id) => Some(*id),
+                _ => None,
+            })
+            .collect::<HashSet<_>>()
+    };
+    let view = &mut app.views[app.current];
+    let (offset, len, page) = (view.state.offset(), view.items.len(), page as usize);
+    let rows = view.minimap(height as usize, query, found);
+    (rows.iter().enumerate())
+        .map(|(row, (symbol, style))| {
+            let start = row * len / rows.len();
+            let end = ((row + 1) * len / rows.len()).max(start + 1);
+            let shown = start < offset + page && offset < end;
+            let style = match shown {
+                true => style.on_dark_gray(),
+                false => *style,
+            };
+            Line::styled(*symbol, style)
+        })
+        .collect()
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
+    let header_height = u16::from(!app.submodule_health.is_empty());
+    let banner_height = u16::from(!app.operations.is_empty());
+    let stats_height = u16::from(app.stats);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(header_height),
+                Constraint::Length(banner_height),
+                Constraint::Percentage(100),
+                Constraint::Length(stats_height),
+                Constraint::Min(1),
+            ]
+            .as_ref(),
+        )
+        .split(f.area());
+    if header_height > 0 {
+        f.render_widget(submodules_header(&app.submodule_health), chunks[0]);
+    }
+    if banner_height > 0 {
+        f.render_widget(operations_banner(&app.operations), chunks[1]);
+    }
+    let chunks = &chunks[1..];
+    let mut list_area = chunks[1];
+    if app.views.len() > 1 {
+        let [tabs_area, area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(list_area);
+        let titles = app.views.iter().enumerate().map(|(i, view)| {
+            match app.options.screen_reader && i == app.current {
+                true => format!("{} {} (current)", i + 1, view.title),
+                false => format!("{} {}", i + 1, view.title),
+            }
+        });
+        let tabs = Tabs::new(titles)
+            .select(app.current)
+            .highlight_style(Style::new().bold().reversed());
+        f.render_widget(tabs, tabs_area);
+        list_area = area;
+    }
+    if let Some(sidebar) = &mut app.sidebar {
+        let width = sidebar.width().min(list_area.width / 3);
+        let [sidebar_area, area] =
+            Layout::horizontal([Constraint::Length(width), Constraint::Min(0)]).areas(list_area);
+        sidebar.render(f, sidebar_area);
+        list_area = area;
+    }
+    if app.minimap.is_some() {
+        let [area, minimap_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(1)]).areas(list_area);
+        let lines = minimap(app, minimap_area.height, list_area.height);
+        f.render_widget(Paragraph::new(lines), minimap_area);
+        app.minimap = Some(minimap_area);
+        list_area = area;
+    }
+    app.list_height = list_area.height.saturating_sub(2);
+
+    let view = &mut app.views[app.current];
+    match &mut app.pane {
+        Some(pane) => pane.render(f, list_area),
+        None => view.render(f, list_area),
+    }
+
+    if stats_height > 0 {
+        f.render_widget(stats_footer(view), chunks[2]);
+    }
+
+    // commit activity over time, left out on narrow terminals and for screen readers
+    let sparkline_width = match chunks[3].width >= 100 && !app.options.screen_reader {
+        true => ACTIVITY_BUCKETS as u16 + 1,
+        false => 0,
+    };
+    let status_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(100),
+                Constraint::Length(sparkline_width),
+                Constraint::Min(4),
+            ]
+            .as_ref(),
+        )
+        .split(chunks[3]);
+
+    let view = app.view();
+    let len = view.items.len();
+    let selected = view.selected().unwrap_or(0);
+    let item = &view.items[selected];
+    let status = if let Some(input) = &app.command {
+        let hint = command::complete(input).join(" ");
+        Line::from(vec![
+            Span::raw(format!(":{input}")),
+            Span::raw("█").slow_blink(),
+            Span::styled(format!("  {hint}"), Style::new().gray().not_bold()),
+        ])
+    } else {
+        Line::from(match (&app.message, &app.pane, &view.bisect_status) {
+            (Some(message), _, _) | (None, None, Some(message)) => message.clone(),
+            (None, Some(pane), _) => pane.status(),
+            (None, None, None) if app.options.screen_reader => spoken(app, selected),
+            (None, None, None) => {
+                let describe = app
+                    .describe
+                    .as_ref()
+                    .and_then(|d| d.get(&item.0.id)?.clone());
+                let mut position = item.0.id.to_string();
+                if let Some(describe) = describe {
+                    position += &format!(" ({describe})");
+                }
+                if let Some(Some(relation)) = app.relations.get(&item.0.id) {
+                    position += &format!(" - {}", relation.describe(app.options.config.abbrev));
+                }
+                position += &format!(" - commit {} of {}", selected + 1, len);
+                if let Some(range) = view.range() {
+                    position += &format!(", {} in range", range.count());
+                }
+                match view
+                    .items
+                    .iter()
+                    .filter(|(e, _)| e.unreadable.is_some())
+                    .count()
+                {
+                    0 => position,
+                    1 => format!("{position}, 1 unreadable commit skipped"),
+                    n => format!("{position}, {n} unreadable commits skipped"),
+                }
+            }
+        })
+    }
+    .style(Style::new().white().bold().on_light_blue());
+    f.render_widget(status, status_layout[0]);
+    if app.options.screen_reader && app.command.is_none() {
+        // screen readers follow the cursor
+        f.set_cursor_position(status_layout[0].as_position());
+    }
+    if sparkline_width > 0 {
+        let sparkline = Sparkline::default()
+            .data(view.activity.iter().copied())
+            .style(Style::new().white().on_light_blue());
+        f.render_widget(sparkline, status_layout[1]);
+    }
+    let perc = Line::from(format!(
+        "{}%",
+        (selected * 100).checked_div(len).unwrap_or(0)
     ))
     .style(Style::new().white().bold().on_light_blue());
-    f.render_widget(perc, status_layout[1]);
+    f.render_widget(perc, status_layout[2]);
+
+    let preview = app
+        .finder
+        .as_ref()
+        .and_then(|finder| finder.selected())
+        .map(|found| app.preview(found))
+        .unwrap_or_default();
+    if let Some(finder) = &mut app.finder {
+        finder.render(f, preview);
+    }
+    if let Some((_, editor)) = &mut app.todo {
+        editor.render(f);
+    }
+    if let Some((prompt, _)) = &app.confirm {
+        let lines = vec![Line::raw(prompt.clone()), Line::raw(""), Line::raw("[y/N]")];
+        Popup::new("confirm", lines).render(f);
+    }
+    if let Some(popup) = &app.popup {
+        popup.render(f);
+    }
+    if app.options.ascii {
+        theme::to_ascii(f.buffer_mut());
+    }
+    if let Some(theme) = &app.theme {
+        theme.apply(f.buffer_mut());
+    } else {
+        // keep highlights visible without colors
+        for cell in &mut f.buffer_mut().content {
+            if cell.bg != Color::Reset {
+                cell.modifier.insert(Modifier::REVERSED);
+            }
+            cell.fg = Color::Reset;
+            cell.bg = Color::Reset;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gixl_core::fixture::Fixture;
+
+    use super::*;
+
+    const ALICE: (&str, &str) = ("Alice", "alice@example.com");
+    const BOB: (&str, &str) = ("Bob", "bob@example.com");
+
+    /// A parser growing over four commits, with a lexer added on a side branch and merged back.
+    fn project(dir: &std::path::Path) -> Result<Root> {
+        let mut fixture = Fixture::init(dir)?;
+        let base = fixture.commit(ALICE, "Add README", &[("README", "parser\n")])?;
+        fixture.commit(ALICE, "Add the parser", &[("parse.rs", "fn parse() {}\n")])?;
+        fixture.commit(
+            BOB,
+            "Fix the parser",
+            &[("parse.rs", "fn parse() { todo!() }\n")],
+        )?;
+        let parser = fixture.commit(
+            ALICE,
+            "Document the parser",
+            &[("README", "parser\ndocs\n")],
+        )?;
+        fixture.reset(base);
+        let lexer = fixture.commit(BOB, "Add the lexer", &[("lex.rs", "fn lex() {}\n")])?;
+        fixture.reset(parser);
+        fixture.merge(ALICE, "Merge the lexer", lexer)?;
+        Ok(fixture.root(""))
+    }
+
+    fn open(roots: &[Root], options: Options) -> Result<Headless<'_>> {
+        let mut problems = Vec::new();
+        let filter = log::Filter::default();
+        let entries = gixl_core::collect(
+            roots,
+            &[],
+            &filter,
+            false,
+            None,
+            &mut |_, _| true,
+            &mut problems,
+        );
+        assert!(problems.is_empty(), "{problems:?}");
+        Headless::new(roots, &[], entries, options, 100, 12)
+    }
+
+    /// Check the rows of the last frame of `ui` above the status bar against `expected`, given
+    /// from its next line on.
+    fn assert_frame(ui: &Headless, expected: &str) {
+        let screen = ui.screen();
+        let rows: Vec<_> = screen.lines().collect();
+        let frame = rows[..rows.len() - 1].join("\n");
+        let expected = expected.strip_prefix('\n').unwrap_or(expected);
+        assert_eq!(frame, expected.trim_end_matches('\n'));
+    }
+
+    /// The status bar of the last frame.
+    fn status(ui: &Headless) -> String {
+        let screen = ui.screen();
+        screen.lines().last().unwrap_or_default().to_owned()
+    }
+
+    #[test]
+    fn placeholders_are_filled_once() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut fixture = Fixture::init(dir.path())?;
+        let id = fixture.commit(ALICE, "Expand %h and %an", &[("README", "gixl\n")])?;
+        let roots = [fixture.root("")];
+        let filter = log::Filter::default();
+        let mut problems = Vec::new();
+        let items = gixl_core::collect(
+            &roots,
+            &[],
+            &filter,
+            false,
+            None,
+            &mut |_, _| true,
+            &mut problems,
+        );
+        let (entry, source) = &items[0];
+        let text = format_entry("%h %an: %s (100%)", entry, source, 7);
+        let abbrev = id.to_hex_with_len(7);
+        assert_eq!(text, format!("{abbrev} Alice: Expand %h and %an (100%)"));
+        Ok(())
+    }
+
+    #[test]
+    fn lists_the_log() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let ui = open(&roots, Options::default())?;
+        assert_frame(
+            &ui,
+            r"
+>>   2024-01-01 05:00:00 +0000 Alice                                     Merge the lexer
+     2024-01-01 04:00:00 +0000 Bob                                       Add the lexer
+     2024-01-01 03:00:00 +0000 Alice                                     Document the parser
+     2024-01-01 02:00:00 +0000 Bob                                       Fix the parser
+     2024-01-01 01:00:00 +0000 Alice                                     Add the parser
+   ◇ 2024-01-01 00:00:00 +0000 Alice                                     Add README
+",
+        );
+        assert!(status(&ui).contains(" - commit 1 of 6 "));
+        Ok(())
+    }
+
+    #[test]
+    fn details_of_the_selection() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let mut ui = open(&roots, Options::default())?;
+        ui.keys("jji")?;
+        assert_frame(
+            &ui,
+            r"
+     2024-01-01 05:00:00 +0000 Alice                                     Merge the lexer
+     2024-┌ details ─────────────────────────────────────────────────────────────────────┐
+>>   2024-│commit    b7a9bccdf4bec867ef4746506bf93f13b5886173                            │er
+     2024-│author    Alice                                                               │
+     2024-│date      2024-01-01 03:00:00 +0000                                           │
+   ◇ 2024-│committer Alice                                                               │
+          │committed 2024-01-01 03:00:00 +0000                                           │
+          │checkout  in HEAD                                                             │
+          │                                                                              │
+          └──────────────────────────────────────────────────────────────────────────────┘
+",
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn goto_a_revision() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let mut ui = open(&roots, Options::default())?;
+        ui.keys(":goto HEAD~3")?;
+        ui.press(KeyCode::Enter)?;
+        assert_frame(
+            &ui,
+            r"
+     2024-01-01 05:00:00 +0000 Alice                                     Merge the lexer
+     2024-01-01 04:00:00 +0000 Bob                                       Add the lexer
+     2024-01-01 03:00:00 +0000 Alice                                     Document the parser
+     2024-01-01 02:00:00 +0000 Bob                                       Fix the parser
+>>   2024-01-01 01:00:00 +0000 Alice                                     Add the parser
+   ◇ 2024-01-01 00:00:00 +0000 Alice                                     Add README
+",
+        );
+        assert!(status(&ui).contains(" - commit 5 of 6 "));
+        Ok(())
+    }
+
+    #[test]
+    fn moves_with_keys() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let mut ui = open(&roots, Options::default())?;
+        ui.press(KeyCode::End)?;
+        assert!(status(&ui).contains(" - commit 6 of 6 "));
+        ui.keys("kk")?;
+        assert!(status(&ui).contains(" - commit 4 of 6 "));
+        ui.press(KeyCode::Home)?;
+        assert!(
+            ui.screen()
+                .starts_with(">>   2024-01-01 05:00:00 +0000 Alice")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn filters_into_a_tab() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let mut ui = open(&roots, Options::default())?;
+        ui.keys(":filter author=Bob")?;
+        ui.press(KeyCode::Enter)?;
+        let screen = ui.screen();
+        let rows: Vec<_> = screen.lines().filter(|row| row.contains("2024-")).collect();
+        assert_eq!(rows.len(), 2, "{screen}");
+        assert!(rows[0].ends_with("Bob                                       Add the lexer"));
+        assert!(rows[1].ends_with("Bob                                       Fix the parser"));
+        ui.keys(":filter author=Carol")?;
+        ui.press(KeyCode::Enter)?;
+        assert!(status(&ui).starts_with("filter: no match"));
+        Ok(())
+    }
+
+    #[test]
+    fn tabs_of_revisions() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let mut ui = open(&roots, Options::default())?;
+        ui.keys(":tab HEAD~2")?;
+        ui.press(KeyCode::Enter)?;
+        let screen = ui.screen();
+        let rows: Vec<_> = screen.lines().filter(|row| row.contains("2024-")).collect();
+        assert_eq!(rows.len(), 3, "{screen}");
+        assert!(rows[0].ends_with("Bob                                       Fix the parser"));
+        assert!(status(&ui).contains(" - commit 1 of 3 "));
+        Ok(())
+    }
+
+    #[test]
+    fn complete_commands() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let mut ui = open(&roots, Options::default())?;
+        ui.keys(":go")?;
+        ui.press(KeyCode::Tab)?;
+        assert!(status(&ui).starts_with(":go█  goto  good "));
+        ui.keys("t")?;
+        ui.press(KeyCode::Tab)?;
+        assert!(status(&ui).starts_with(":goto █ "));
+        Ok(())
+    }
+
+    #[test]
+    fn bisect() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let mut ui = open(&roots, Options::default())?;
+        ui.keys("b")?;
+        assert!(status(&ui).starts_with("bisect: waiting for both good and bad commits"));
+        ui.press(KeyCode::End)?;
+        ui.keys("g")?;
+        assert!(status(&ui).starts_with("bisect: 2 revisions left to test after this "));
+        // the selection moved to the commit to test
+        assert!(ui.screen().contains(">>   2024-01-01 03:00:00 +0000 Alice"));
+        Ok(())
+    }
+
+    #[test]
+    fn read_only_refuses_writes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let options = Options {
+            read_only: true,
+            ..Options::default()
+        };
+        let mut ui = open(&roots, options)?;
+        ui.keys("jF")?;
+        assert!(status(&ui).starts_with("disabled in read-only mode"));
+        for command in ["revert", "delete-branch main", "delete-tag v1"] {
+            ui.keys(&format!(":{command}"))?;
+            ui.press(KeyCode::Enter)?;
+            let refused = status(&ui).starts_with("disabled in read-only mode");
+            assert!(refused, ":{command}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn history_edits_keep_merges() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let short = |rev: &str| -> Result<String> {
+            Ok(roots[0]
+                .repo
+                .rev_parse_single(rev)?
+                .to_hex_with_len(7)
+                .to_string())
+        };
+        let (merge, fix, readme) = (short("HEAD")?, short("HEAD~2")?, short("HEAD~4")?);
+        let mut ui = open(&roots, Options::default())?;
+        ui.keys("d")?;
+        assert!(status(&ui).starts_with(&format!("drop: {merge} is a merge")));
+
+        ui.press(KeyCode::End)?;
+        ui.keys("w")?;
+        assert!(status(&ui).starts_with(&format!("reword: {readme} is a root commit")));
+        ui.keys("d")?;
+        assert!(status(&ui).starts_with(&format!("drop: {readme} is a root commit")));
+        ui.keys("vkZ")?;
+        assert!(status(&ui).starts_with("squash: the oldest commit has no parent"));
+        ui.keys("v")?;
+
+        // the lexer is merged after the fix
+        ui.press(KeyCode::Home)?;
+        ui.keys("jjjw")?;
+        let lost = format!("merges after {fix} would be lost");
+        assert!(status(&ui).starts_with(&format!("reword: {lost}")));
+        ui.keys("d")?;
+        assert!(status(&ui).starts_with(&format!("drop: {lost}")));
+        ui.keys("I")?;
+        assert!(status(&ui).starts_with(&format!("rebase: {lost}")));
+        Ok(())
+    }
+
+    #[test]
+    fn pushed_commits_are_not_rewritten() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let repo = &roots[0].repo;
+        let pushed = repo.rev_parse_single("HEAD~1")?.detach();
+        repo.reference(
+            "refs/remotes/origin/main",
+            pushed,
+            gix::refs::transaction::PreviousValue::Any,
+            "push",
+        )?;
+        let fix = repo
+            .rev_parse_single("HEAD~2")?
+            .to_hex_with_len(7)
+            .to_string();
+        let mut ui = open(&roots, Options::default())?;
+        ui.keys("jjjw")?;
+        let refusal = format!("{fix} is already pushed to origin/main");
+        assert!(status(&ui).starts_with(&format!("reword: {refusal}")));
+        ui.keys("d")?;
+        assert!(status(&ui).starts_with(&format!("drop: {refusal}")));
+        ui.keys("I")?;
+        assert!(status(&ui).starts_with(&format!("rebase: {refusal}")));
+        Ok(())
+    }
+
+    #[test]
+    fn ref_commands_check_their_names() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let roots = [project(dir.path())?];
+        let head = roots[0].repo.head_name()?.expect("HEAD is on a branch");
+        let branch = head.shorten().to_string();
+        let mut ui = open(&roots, Options::default())?;
+        for (command, refusal) in [
+            (
+                format!("delete-branch {branch}"),
+                format!("delete-branch: {branch} is checked out"),
+            ),
+            (
+                "delete-branch topic".into(),
+                "delete-branch: no branch topic".into(),
+            ),
+            (
+                "rename-branch topic feature".into(),
+                "rename-branch: no branch topic".into(),
+            ),
+            ("delete-tag v1".into(), "delete-tag: no tag v1".into()),
+            ("push-tag v1".into(), "push-tag: no tag v1".into()),
+            (
+                "remote remove origin".into(),
+                "remote: no remote origin".into(),
+            ),
+        ] {
+            ui.keys(&format!(":{command}"))?;
+            ui.press(KeyCode::Enter)?;
+            assert!(status(&ui).starts_with(&refusal), ":{command}");
+        }
+        Ok(())
+    }
 }
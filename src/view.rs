@@ -0,0 +1,626 @@
+use std::collections::{HashMap, HashSet};
+
+use gix::bstr::ByteSlice;
+use ratatui::{prelude::*, widgets::*};
+
+use crate::{
+    bisect::{Bisect, Step},
+    ci,
+    config::Column,
+    forge::Request,
+    lint::Lint,
+    log::{DateFormat, Role, Side},
+    source::{Root, Source},
+    text::Decoding,
+    width,
+};
+
+pub use gixl_core::Item;
+
+/// A list of entries shown in its own tab, with its own selection and marks.
+pub struct View<'repo> {
+    pub title: String,
+    pub items: Vec<Item<'repo>>,
+    /// Selection and offset among the entries, only those shown being made into lines.
+    pub state: ListState,
+    /// The time-interleaved order, saved while entries are grouped by source.
+    pub interleaved: Option<Vec<Item<'repo>>>,
+    /// The entries of every source in time order, saved while only those of the repositories
+    /// are shown.
+    pub all_sources: Option<Vec<Item<'repo>>>,
+    pub bisect: Bisect,
+    pub bisect_status: Option<String>,
+    /// First pick of an interactive `--compare`.
+    pub compare_from: Option<usize>,
+    /// Entry the range of entries up to the selected one starts from, to act on them all.
+    pub range_from: Option<gix::ObjectId>,
+    pub date: DateFormat,
+    /// Whose time the date column shows.
+    pub time: Role,
+    /// Base of the interactive rebase previewed in this tab.
+    pub rebase_onto: Option<(gix::ObjectId, &'static [&'static str])>,
+    /// Entries the rebase folds into the one above, shown indented.
+    pub folded: HashSet<gix::ObjectId>,
+    /// Status of the checks of the entries, when shown.
+    pub ci: Option<HashMap<gix::ObjectId, ci::Status>>,
+    /// Pull or merge request which introduced the entries, like `#123`, when shown.
+    pub prs: Option<HashMap<gix::ObjectId, Request>>,
+    /// Entries which arrived since the previous run.
+    pub new: HashSet<gix::ObjectId>,
+    /// Entries whose patch is also the one of another entry.
+    pub duplicates: HashSet<gix::ObjectId>,
+    /// Columns of text picked from the messages, as configured.
+    pub columns: Vec<Column>,
+    /// Width of each column, as wide as its longest text, `0` for those without any.
+    widths: Vec<usize>,
+    /// Whether a root or boundary entry is listed, every line then keeping a column for their
+    /// markers.
+    edges: bool,
+    /// Checks of the messages, marking the entries failing them.
+    pub lint: Lint,
+    pub summary: Summary,
+    /// Number of entries in each of [`ACTIVITY_BUCKETS`] equal periods, oldest first.
+    pub activity: Vec<u64>,
+    /// The rows of the minimap last drawn, made again once the entries or their marks change.
+    minimap: Option<Minimap>,
+}
+
+/// Symbol and style of each row of a minimap, for the query of the finder whose matches it
+/// shows.
+struct Minimap {
+    query: Option<String>,
+    rows: Vec<(&'static str, Style)>,
+}
+
+/// Periods the activity of a view is counted over, one per bar of its sparkline.
+pub const ACTIVITY_BUCKETS: usize = 23;
+
+/// Summary of the entries of a view, made in the background.
+#[derive(Default)]
+pub enum Summary {
+    #[default]
+    Stale,
+    /// Being made, as the summary of this number.
+    Making(u64),
+    Made(Line<'static>),
+}
+
+impl<'repo> View<'repo> {
+    pub fn new(title: impl Into<String>, items: Vec<Item<'repo>>) -> View<'repo> {
+        let mut state = ListState::default();
+        state.select((!items.is_empty()).then_some(0));
+        View {
+            title: title.into(),
+            items,
+            state,
+            interleaved: None,
+            all_sources: None,
+            bisect: Bisect::default(),
+            bisect_status: None,
+            compare_from: None,
+            range_from: None,
+            date: DateFormat::default(),
+            time: Role::default(),
+            rebase_onto: None,
+            folded: HashSet::new(),
+            ci: None,
+            prs: None,
+            new: HashSet::new(),
+            duplicates: HashSet::new(),
+            columns: Vec::new(),
+            widths: Vec::new(),
+            edges: false,
+            lint: Lint::default(),
+            summary: Summary::Stale,
+            activity: Vec::new(),
+            minimap: None,
+        }
+    }
+
+    /// Whether the entry at `i` stands out on the minimap: marked by bisect, new, a merge, or
+    /// failing the checks of its message.
+    pub fn stands_out(&self, i: usize) -> bool {
+        let entry = &self.items[i].0;
+        self.bisect.bad == Some(entry.id)
+            || self.bisect.good.contains(&entry.id)
+            || self.new.contains(&entry.id)
+            || entry.parents.len() > 1
+            || !self.lint_failures(i).is_empty()
+    }
+
+    /// The checks the message of the entry at `i` fails, none for placeholders.
+    pub fn lint_failures(&self, i: usize) -> Vec<String> {
+        let entry = &self.items[i].0;
+        match self.lint.enabled() && entry.unreadable.is_none() {
+            true => self.lint.check(&entry.message),
+            false => Vec::new(),
+        }
+    }
+
+    /// Select the next entry standing out, after the selected one or before with `backward`.
+    pub fn jump(&mut self, backward: bool) {
+        let Some(selected) = self.selected() else {
+            return;
+        };
+        let found = match backward {
+            true => (0..selected).rev().find(|i| self.stands_out(*i)),
+            false => (selected + 1..self.items.len()).find(|i| self.stands_out(*i)),
+        };
+        if let Some(i) = found {
+            self.state.select(Some(i));
+        }
+    }
+
+    /// The selected entry, none in an empty view.
+    pub fn selected(&self) -> Option<usize> {
+        self.state.selected().filter(|i| *i < self.items.len())
+    }
+
+    /// Start a range of entries at the selected one, or drop the one started.
+    pub fn toggle_range(&mut self) {
+        self.range_from = match self.range_from {
+            Some(_) => None,
+            None => self.selected().map(|i| self.items[i].0.id),
+        };
+    }
+
+    /// Positions of the entries from the start of the range to the selected one, if started.
+    pub fn range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let from = self
+            .items
+            .iter()
+            .position(|(e, _)| Some(e.id) == self.range_from)?;
+        let selected = self.selected()?;
+        Some(from.min(selected)..=from.max(selected))
+    }
+
+    /// Select the entry of commit `id`, if listed.
+    pub fn select_id(&mut self, id: gix::ObjectId) -> bool {
+        match self.items.iter().position(|(e, _)| e.id == id) {
+            Some(i) => {
+                self.state.select(Some(i));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// One symbol per row of `height` for the entries in it: bisect marks, entries `found` by
+    /// the `query` of the finder and new entries first, then merges, shaded by their density.
+    /// The rows are only made again once the entries, their marks, `height` or `query` change.
+    pub fn minimap(
+        &mut self,
+        height: usize,
+        query: Option<&str>,
+        found: impl FnOnce() -> HashSet<gix::ObjectId>,
+    ) -> &[(&'static str, Style)] {
+        let len = self.items.len();
+        let rows = height.min(len).max(1);
+        let fresh = (self.minimap.as_ref())
+            .is_some_and(|minimap| minimap.rows.len() == rows && minimap.query.as_deref() == query);
+        if !fresh {
+            let found = match query {
+                Some(_) => found(),
+                None => HashSet::new(),
+            };
+            let marks = [
+                Style::new().light_cyan(),
+                Style::new().light_yellow(),
+                Style::new().green(),
+                Style::new().red(),
+            ];
+            // the strongest mark of each row, its index in `marks` plus one
+            let (mut marked, mut merges) = (vec![0; rows], vec![0; rows]);
+            for (i, (entry, _)) in self.items.iter().enumerate() {
+                let row = ((i + 1) * rows - 1) / len;
+                let mark = if self.bisect.bad == Some(entry.id) {
+                    4
+                } else if self.bisect.good.contains(&entry.id) {
+                    3
+                } else if found.contains(&entry.id) {
+                    2
+                } else {
+                    usize::from(self.new.contains(&entry.id))
+                };
+                marked[row] = marked[row].max(mark);
+                merges[row] += usize::from(entry.parents.len() > 1);
+            }
+            let symbols = (0..rows).map(|row| {
+                let size = ((row + 1) * len / rows).max(row * len / rows + 1) - row * len / rows;
+                match (marked[row], merges[row] * 3 / size) {
+                    (0, _) if merges[row] == 0 => ("│", Style::new().dark_gray()),
+                    (0, 0) => ("░", Style::new().magenta()),
+                    (0, 1) => ("▒", Style::new().magenta()),
+                    (0, _) => ("▓", Style::new().magenta()),
+                    (mark, _) => ("█", marks[mark - 1]),
+                }
+            });
+            self.minimap = Some(Minimap {
+                query: query.map(str::to_owned),
+                rows: symbols.collect(),
+            });
+        }
+        match &self.minimap {
+            Some(minimap) => &minimap.rows,
+            None => &[],
+        }
+    }
+
+    /// Measure the columns and count the activity over the entries, after either changed: the
+    /// lines of the entries are only made when drawn, see [`View::render`]. Their summary is made
+    /// again once shown.
+    pub fn measure(&mut self) {
+        self.summary = Summary::Stale;
+        self.activity = activity(&self.items, ACTIVITY_BUCKETS);
+        self.minimap = None;
+        self.widths = self
+            .columns
+            .iter()
+            .map(|column| {
+                let width = self
+                    .items
+                    .iter()
+                    .filter_map(|(entry, source)| column.cell(entry, source))
+                    .map(|(text, _)| width::width(&text))
+                    .max()
+                    .unwrap_or(0);
+                width.min(20)
+            })
+            .collect();
+        self.edges = self
+            .items
+            .iter()
+            .any(|(entry, _)| entry.boundary || entry.is_root());
+    }
+
+    /// Switch between time-interleaved entries and entries grouped by source, repositories
+    /// before their submodules.
+    pub fn toggle_grouping(&mut self, roots: &'repo [Root]) {
+        let selected = self.selected().map(|i| self.items[i].0.id);
+        match self.interleaved.take() {
+            Some(items) => self.items = items,
+            None => {
+                self.interleaved = Some(self.items.clone());
+                let mut order: Vec<Source> = roots.iter().map(Source::Root).collect();
+                for (_, source) in &self.items {
+                    if !order.contains(source) {
+                        order.push(*source);
+                    }
+                }
+                self.items
+                    .sort_by_key(|(_, source)| order.iter().position(|s| s == source));
+            }
+        }
+        self.minimap = None;
+        if let Some(id) = selected {
+            self.select_id(id);
+        }
+    }
+
+    /// Switch between the entries of every source and those of the repositories only, the
+    /// entries of submodules being kept aside.
+    pub fn toggle_submodules(&mut self, roots: &'repo [Root]) {
+        let id = self.selected().map(|i| self.items[i].0.id);
+        let grouped = self.interleaved.is_some();
+        if !self.ungroup() {
+            self.hide_submodules();
+        }
+        self.measure();
+        if grouped {
+            self.toggle_grouping(roots);
+        }
+        if let Some(id) = id
+            && !self.select_id(id)
+        {
+            // the entry selected was hidden, the next one shown is
+            let next = self
+                .all_sources
+                .iter()
+                .flatten()
+                .skip_while(|(e, _)| e.id != id)
+                .find(|(_, source)| matches!(source, Source::Root(_)))
+                .map(|(e, _)| e.id);
+            if !next.is_some_and(|next| self.select_id(next)) {
+                self.state.select(self.items.len().checked_sub(1));
+            }
+        }
+    }
+
+    /// Show every entry in time order, for them to be changed, telling whether only those of
+    /// the repositories were shown, to hide the others again with [`View::hide_submodules`].
+    pub fn ungroup(&mut self) -> bool {
+        if let Some(items) = self.interleaved.take() {
+            self.items = items;
+        }
+        match self.all_sources.take() {
+            Some(items) => {
+                self.items = items;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keep only the entries of the repositories, saving those of every source.
+    pub fn hide_submodules(&mut self) {
+        self.all_sources = Some(self.items.clone());
+        self.items
+            .retain(|(_, source)| matches!(source, Source::Root(_)));
+    }
+
+    /// Mark an entry good or bad, and select the next commit to test.
+    /// Returns an error message when the mark isn't accepted.
+    pub fn bisect_mark(&mut self, selected: usize, good: bool) -> Option<String> {
+        let (entry, source) = &self.items[selected];
+        if !self.bisect.mark(&source.label(), entry.id, good) {
+            return Some("bisect: commit is from another source".into());
+        }
+        self.minimap = None;
+
+        let source = self.bisect.source.as_deref();
+        let graph = self
+            .items
+            .iter()
+            .filter(|(_, s)| Some(s.label().as_str()) == source)
+            .map(|(e, _)| (e.id, e.parents.as_slice()));
+        self.bisect_status = Some(match self.bisect.step(graph) {
+            Step::NeedMarks => "bisect: waiting for both good and bad commits".into(),
+            Step::Next {
+                next,
+                remaining,
+                steps,
+            } => {
+                self.select_id(next);
+                format!(
+                    "bisect: {remaining} revisions left to test after this (roughly {steps} steps)"
+                )
+            }
+            Step::Found(id) => {
+                self.select_id(id);
+                format!("bisect: {id} is the first bad commit")
+            }
+        });
+        None
+    }
+
+    pub fn bisect_reset(&mut self) {
+        self.bisect = Bisect::default();
+        self.minimap = None;
+        self.bisect_status = None;
+    }
+
+    /// Select entry `i`, or the last one past it, none when there are no entries.
+    fn select_within(&mut self, i: usize) {
+        let last = self.items.len().checked_sub(1);
+        self.state.select(last.map(|last| i.min(last)));
+    }
+
+    pub fn next(&mut self) {
+        let i = self.state.selected().map_or(0, |i| i + 1);
+        self.select_within(i);
+    }
+
+    pub fn previous(&mut self) {
+        let i = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.select_within(i);
+    }
+
+    pub fn page_down(&mut self, page_size: usize) {
+        let i = self.state.selected().map_or(0, |i| i + page_size);
+        self.select_within(i);
+    }
+
+    pub fn page_up(&mut self, page_size: usize) {
+        let i = self
+            .state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(page_size));
+        self.select_within(i);
+    }
+
+    pub fn go_to_start(&mut self) {
+        self.select_within(0);
+    }
+
+    pub fn go_to_end(&mut self) {
+        self.select_within(usize::MAX);
+    }
+
+    /// Draw the entries which fit in `area`, scrolling to the selected one.
+    pub fn render(&mut self, f: &mut Frame, area: Rect) {
+        let height = area.height as usize;
+        let (offset, selected) = (self.state.offset(), self.selected().unwrap_or(0));
+        // from the entry at the top, or a screen above the selected one when below it
+        let start = offset.min(selected).max(selected.saturating_sub(height));
+        let end = (selected.max(start + height) + 1).min(self.items.len());
+        // the selected entry is marked, the others shifted as much
+        let cells = usize::from(area.width).saturating_sub(3);
+        let items: Vec<_> = (start..end).map(|i| self.list_item(i, cells)).collect();
+        let list = List::new(items)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::LightGreen)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ");
+        let mut state = ListState::default()
+            .with_offset(offset.saturating_sub(start))
+            .with_selected(self.selected().map(|i| i - start));
+        f.render_stateful_widget(list, area, &mut state);
+        *self.state.offset_mut() = start + state.offset();
+    }
+
+    /// The line of the entry at `i`, under the name of its source when entries are grouped by
+    /// source and it is the first of it, its subject cut to fit in `cells` columns.
+    fn list_item(&self, i: usize, cells: usize) -> ListItem<'static> {
+        let (entry, source) = &self.items[i];
+        let prev_source = i.checked_sub(1).map(|prev| self.items[prev].1);
+        let message_lines = entry.message.split(|c| *c == b'\n').collect::<Vec<_>>();
+        let first_line = String::from_utf8_lossy(message_lines[0]).into_owned();
+        let mut time = self.date.format(entry.time_of(self.time));
+        // the time of a placeholder is that of its child
+        if entry.unreadable.is_some() {
+            time = " ".repeat(time.chars().count());
+        }
+        let author = width::fit(&entry.author.to_str_lossy(), 20, false);
+
+        // Only show submodule if it changed from the previous entry
+        let source_changed = prev_source.is_some_and(|s| s != *source)
+            || prev_source.is_none() && source.submodule().is_some();
+        let source_display = if source_changed || prev_source.is_none() {
+            width::fit(&source.label(), 20, true)
+        } else {
+            format!("{:^20}", "")
+        };
+        let grouped = self.interleaved.is_some();
+        let header = (grouped && (source_changed || i == 0)).then(|| {
+            let name = match (source.label(), source) {
+                (label, Source::Root(_)) if label.is_empty() => "superproject".into(),
+                (label, _) => label,
+            };
+            Line::styled(format!("── {name} ──"), Style::new().bold().cyan())
+        });
+
+        let mark = if self.range_from == Some(entry.id) {
+            Span::styled("from ", Style::new().light_yellow().bold())
+        } else if self.bisect.bad == Some(entry.id) {
+            Span::styled("bad ", Style::new().red().bold())
+        } else if self.bisect.good.contains(&entry.id) {
+            Span::styled("good ", Style::new().green().bold())
+        } else if self.new.contains(&entry.id) {
+            Span::styled("new ", Style::new().light_cyan().bold())
+        } else {
+            Span::raw("")
+        };
+
+        let fold = if self.folded.contains(&entry.id) {
+            Span::styled("  ↳ ", Style::new().dark_gray())
+        } else {
+            Span::raw("")
+        };
+
+        let duplicate = if self.duplicates.contains(&entry.id) {
+            Span::styled("≡ ", Style::new().light_magenta())
+        } else {
+            Span::raw("")
+        };
+
+        let side = match (entry.side, entry.equivalent) {
+            (Some(Side::Left), false) => Span::styled("< ", Style::new().yellow()),
+            (Some(Side::Right), false) => Span::styled("> ", Style::new().magenta()),
+            (Some(_), true) => Span::styled("= ", Style::new().dark_gray()),
+            (None, _) => Span::raw(""),
+        };
+
+        // like `git log --boundary`, where the range stops
+        let edge = if entry.boundary {
+            Span::styled("- ", Style::new().dark_gray())
+        } else if entry.is_root() {
+            Span::styled("◇ ", Style::new().light_blue())
+        } else if self.edges {
+            Span::raw("  ")
+        } else {
+            Span::raw("")
+        };
+
+        let status = match self.ci.as_ref().map(|ci| ci.get(&entry.id)) {
+            Some(Some(status)) => {
+                let style = match status {
+                    ci::Status::Success => Style::new().green(),
+                    ci::Status::Failure => Style::new().red(),
+                    ci::Status::Pending => Style::new().yellow(),
+                };
+                Span::styled(format!("{} ", status.symbol()), style)
+            }
+            Some(None) => Span::raw("  "),
+            None => Span::raw(""),
+        };
+
+        let pr = match &self.prs {
+            Some(prs) => {
+                let label = prs.get(&entry.id).map(Request::to_string);
+                let label = label.unwrap_or_default();
+                Span::styled(format!("{label:>6} "), Style::new().magenta())
+            }
+            None => Span::raw(""),
+        };
+
+        // columns without any text are left out
+        let columns = self.columns.iter().zip(&self.widths);
+        let extra = columns
+            .filter(|(_, width)| **width > 0)
+            .map(|(column, width)| {
+                let (text, style) = column.cell(entry, source).unwrap_or_default();
+                Span::styled(format!("{} ", width::fit(&text, *width, false)), style)
+            });
+
+        let lint = match self.lint.enabled() {
+            true if !self.lint_failures(i).is_empty() => Span::styled("! ", Style::new().yellow()),
+            true => Span::raw("  "),
+            false => Span::raw(""),
+        };
+
+        let mut spans = vec![mark, fold, duplicate, side, edge, status, pr, lint];
+        spans.extend(extra);
+        spans.extend([
+            // time
+            Span::styled(time, Style::new().blue()),
+            Span::raw(" "),
+            // author
+            Span::styled(author, Style::default().green()),
+            Span::raw(" "),
+            // source
+            Span::styled(source_display, Style::default().gray()),
+            Span::raw(" "),
+        ]);
+        // what couldn't be read is only replaced
+        let lossy = matches!(entry.decoding, Decoding::Lossy(_))
+            .then(|| Span::styled(" (invalid encoding)", Style::new().dark_gray()));
+        // the subject takes the columns left
+        let used = spans.iter().chain(&lossy);
+        let used: usize = used.map(|span| width::width(&span.content)).sum();
+        let subject = width::truncate(&first_line, cells.saturating_sub(used));
+        spans.push(match entry.unreadable {
+            Some(_) => Span::styled(subject, Style::new().red()),
+            None if entry.boundary => Span::styled(subject, Style::new().dark_gray()),
+            None => Span::raw(subject),
+        });
+        spans.extend(lossy);
+        let mut lines: Vec<Line> = header.into_iter().collect();
+        lines.push(Line::from(spans));
+        ListItem::new(lines).style(Style::default())
+    }
+}
+
+/// Number of `items` in each of `buckets` equal periods, oldest first, from the oldest to the
+/// newest.
+fn activity(items: &[Item], buckets: usize) -> Vec<u64> {
+    let mut counts = vec![0; buckets];
+    let times = items.iter().map(|(entry, _)| entry.author_time.seconds);
+    let (Some(first), Some(last)) = (times.clone().min(), times.clone().max()) else {
+        return counts;
+    };
+    let span = (last - first) as u128 + 1;
+    for time in times {
+        let bucket = (time - first) as u128 * buckets as u128 / span;
+        counts[bucket as usize] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moves_in_an_empty_view() {
+        let mut view = View::new("empty", Vec::new());
+        view.go_to_end();
+        view.page_down(10);
+        view.next();
+        view.previous();
+        view.page_up(10);
+        view.go_to_start();
+        assert_eq!(view.selected(), None);
+    }
+}
@@ -0,0 +1,73 @@
+//! Text fitted to columns of the terminal a grapheme cluster at a time, the way it is drawn:
+//! emoji, characters with combining marks and ZWJ sequences take the columns of their whole
+//! cluster, and are never cut through.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns `text` takes on the terminal.
+pub fn width(text: &str) -> usize {
+    text.graphemes(true).map(UnicodeWidthStr::width).sum()
+}
+
+/// `text` cut to at most `columns`, ending with `…` when it was cut.
+pub fn truncate(text: &str, columns: usize) -> String {
+    if width(text) <= columns {
+        return text.to_owned();
+    }
+    let mut cut = String::new();
+    let mut used = 0;
+    for grapheme in text.graphemes(true) {
+        let width = grapheme.width();
+        if used + width + 1 > columns {
+            break;
+        }
+        cut.push_str(grapheme);
+        used += width;
+    }
+    if columns > 0 {
+        cut.push('…');
+    }
+    cut
+}
+
+/// `text` in exactly `columns`, cut like [`truncate`] does, padded with spaces after it, or on
+/// both sides when `centered`.
+pub fn fit(text: &str, columns: usize, centered: bool) -> String {
+    let text = truncate(text, columns);
+    let padding = columns.saturating_sub(width(&text));
+    let before = if centered { padding / 2 } else { 0 };
+    format!(
+        "{}{text}{}",
+        " ".repeat(before),
+        " ".repeat(padding - before)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_keeps_what_fits() {
+        assert_eq!(truncate("hello", 5), "hello");
+        assert_eq!(truncate("hello world", 8), "hello w…");
+        assert_eq!(truncate("hello", 0), "");
+    }
+
+    #[test]
+    fn truncate_never_cuts_a_cluster() {
+        // two columns each
+        assert_eq!(truncate("日本語", 4), "日…");
+        assert_eq!(truncate("👍🏽👍🏽", 3), "👍🏽…");
+        assert_eq!(truncate("e\u{301}te\u{301}", 2), "e\u{301}…");
+    }
+
+    #[test]
+    fn fit_pads() {
+        assert_eq!(fit("ab", 5, false), "ab   ");
+        assert_eq!(fit("ab", 5, true), " ab  ");
+        assert_eq!(fit("日本", 5, true), "日本 ");
+        assert_eq!(fit("abcdef", 4, true), "abc…");
+    }
+}